@@ -0,0 +1,216 @@
+// Deterministic generators of the benchmark inputs.
+//
+// The same seed always produces the same document,
+// so results are comparable between runs and machines.
+
+use std::fmt::Write;
+
+#[path = "../../tests/common/rng.rs"]
+mod rng;
+use self::rng::Rng;
+
+/// A benchmark input.
+pub struct Corpus {
+    pub name: &'static str,
+    pub text: String,
+}
+
+/// Generates all corpora with roughly `size` bytes each.
+pub fn all(size: usize) -> Vec<Corpus> {
+    vec![
+        Corpus { name: "svg", text: svg(size) },
+        Corpus { name: "docbook", text: docbook(size) },
+        Corpus { name: "nested", text: nested(size) },
+        Corpus { name: "cdata", text: cdata(size) },
+        Corpus { name: "comments", text: comments(size) },
+        Corpus { name: "dtd", text: dtd(size) },
+    ]
+}
+
+impl Rng {
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.range(items.len())]
+    }
+
+    fn words(&mut self, out: &mut String, count: usize) {
+        for i in 0..count {
+            if i != 0 {
+                out.push(' ');
+            }
+
+            out.push_str(self.pick(WORDS));
+        }
+    }
+}
+
+const WORDS: &[&str] = &[
+    "lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit",
+    "sed", "do", "eiusmod", "tempor", "incididunt", "ut", "labore", "et", "dolore",
+    "magna", "aliqua", "текст", "données", "数据",
+];
+
+const COLORS: &[&str] = &[
+    "#000000", "#ff0000", "#00ff00", "#0000ff", "none", "currentColor", "red",
+];
+
+// Attribute-heavy SVG.
+fn svg(size: usize) -> String {
+    let mut rng = Rng::new();
+    let mut s = String::with_capacity(size + 1024);
+    s.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n");
+    s.push_str("<svg xmlns=\"http://www.w3.org/2000/svg\" \
+                xmlns:xlink=\"http://www.w3.org/1999/xlink\" viewBox=\"0 0 1000 1000\">\n");
+
+    while s.len() < size {
+        writeln!(s, "  <g id=\"g{}\" transform=\"translate({} {})\" opacity=\"0.{}\">",
+                 rng.range(100_000), rng.range(1000), rng.range(1000), rng.range(10)).unwrap();
+
+        for _ in 0..rng.range(8) + 1 {
+            s.push_str("    <path d=\"M");
+            for _ in 0..rng.range(16) + 2 {
+                write!(s, " {}.{} {}.{} L", rng.range(1000), rng.range(100),
+                       rng.range(1000), rng.range(100)).unwrap();
+            }
+
+            writeln!(s, " {} {} Z\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\" \
+                         xlink:title='shape &amp; line'/>",
+                     rng.range(1000), rng.range(1000), rng.pick(COLORS), rng.pick(COLORS),
+                     rng.range(5)).unwrap();
+        }
+
+        s.push_str("  </g>\n");
+    }
+
+    s.push_str("</svg>\n");
+    s
+}
+
+// Text-heavy DocBook.
+fn docbook(size: usize) -> String {
+    let mut rng = Rng::new();
+    let mut s = String::with_capacity(size + 1024);
+    s.push_str("<?xml version=\"1.0\"?>\n<book>\n");
+
+    let mut chapter = 0;
+    while s.len() < size {
+        chapter += 1;
+        writeln!(s, "<chapter id=\"ch{}\">\n<title>Chapter {}</title>", chapter, chapter).unwrap();
+
+        for _ in 0..rng.range(10) + 1 {
+            s.push_str("<para>");
+            for _ in 0..rng.range(6) + 1 {
+                let n = rng.range(40) + 10;
+                rng.words(&mut s, n);
+                match rng.range(4) {
+                    0 => s.push_str(" &amp; "),
+                    1 => {
+                        s.push_str(" <emphasis>");
+                        rng.words(&mut s, 2);
+                        s.push_str("</emphasis> ");
+                    }
+                    2 => s.push_str(" &#x201C;quoted&#x201D; "),
+                    _ => s.push_str(".\n"),
+                }
+            }
+            s.push_str("</para>\n");
+        }
+
+        s.push_str("<!-- end of the chapter -->\n</chapter>\n");
+    }
+
+    s.push_str("</book>\n");
+    s
+}
+
+// Deeply nested data with short values.
+fn nested(size: usize) -> String {
+    let mut rng = Rng::new();
+    let mut s = String::with_capacity(size + 1024);
+    s.push_str("<root>");
+
+    while s.len() < size {
+        let depth = rng.range(256) + 1;
+        for i in 0..depth {
+            write!(s, "<n{} k=\"{}\">", i % 10, rng.range(100)).unwrap();
+        }
+
+        write!(s, "{}", rng.range(1_000_000)).unwrap();
+
+        for i in (0..depth).rev() {
+            write!(s, "</n{}>", i % 10).unwrap();
+        }
+
+        s.push('\n');
+    }
+
+    s.push_str("</root>");
+    s
+}
+
+// Large CDATA sections.
+fn cdata(size: usize) -> String {
+    let mut rng = Rng::new();
+    let mut s = String::with_capacity(size + 1024);
+    s.push_str("<scripts>\n");
+
+    while s.len() < size {
+        s.push_str("<script><![CDATA[\n");
+        for _ in 0..rng.range(200) + 50 {
+            writeln!(s, "if (a[{}] < b && c > d[i]) {{ x = y[{}]; }}",
+                     rng.range(100), rng.range(100)).unwrap();
+        }
+        s.push_str("]]></script>\n");
+    }
+
+    s.push_str("</scripts>\n");
+    s
+}
+
+// Multi-line comments and processing instructions.
+fn comments(size: usize) -> String {
+    let mut rng = Rng::new();
+    let mut s = String::with_capacity(size + 1024);
+    s.push_str("<doc>\n");
+
+    while s.len() < size {
+        let (start, end) = if rng.range(2) == 0 { ("<!--", "-->") } else { ("<?pi", "?>") };
+        s.push_str(start);
+        for _ in 0..rng.range(20) + 5 {
+            s.push_str("\n\t");
+            let n = rng.range(10) + 5;
+            rng.words(&mut s, n);
+        }
+        s.push_str(end);
+        s.push('\n');
+    }
+
+    s.push_str("</doc>\n");
+    s
+}
+
+// A large internal DTD subset and a small document.
+fn dtd(size: usize) -> String {
+    let mut rng = Rng::new();
+    let mut s = String::with_capacity(size + 1024);
+    s.push_str("<?xml version=\"1.0\"?>\n<!DOCTYPE doc SYSTEM \"doc.dtd\" [\n");
+
+    let mut n = 0;
+    while s.len() < size {
+        n += 1;
+        match rng.range(5) {
+            0 => writeln!(s, "  <!ELEMENT e{} (#PCDATA|a|b)*>", n).unwrap(),
+            1 => writeln!(s, "  <!ATTLIST e{} id ID #IMPLIED kind (x|y|z) \"x\">", n).unwrap(),
+            2 => writeln!(s, "  <!ENTITY ext{} SYSTEM \"ext{}.xml\">", n, n).unwrap(),
+            3 => writeln!(s, "  <!ENTITY pub{} PUBLIC \"-//Test//{}//EN\" \"p.xml\" NDATA gif>",
+                          n, n).unwrap(),
+            _ => {
+                write!(s, "  <!ENTITY ent{} \"", n).unwrap();
+                rng.words(&mut s, 8);
+                s.push_str("\">\n");
+            }
+        }
+    }
+
+    s.push_str("]>\n<doc/>\n");
+    s
+}
@@ -0,0 +1,152 @@
+// Tokenizer throughput benchmarks.
+//
+// Run with `cargo bench`. Corpora can be filtered by name: `cargo bench -- svg`.
+// The corpus size can be changed via the `XMLPARSER_BENCH_SIZE` env variable (in bytes).
+//
+// For each corpus, prints the overall throughput and a per token kind breakdown.
+// The breakdown is measured by timing each token separately,
+// so it's less precise than the overall number and should be used only
+// for comparison between runs.
+
+extern crate xmlparser as xml;
+
+mod corpus;
+
+use std::env;
+use std::time::{Duration, Instant};
+
+const DEFAULT_SIZE: usize = 4 * 1024 * 1024;
+const ITERATIONS: usize = 10;
+
+fn main() {
+    let size = env::var("XMLPARSER_BENCH_SIZE").ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_SIZE);
+
+    let filters: Vec<String> = env::args().skip(1).filter(|a| !a.starts_with("--")).collect();
+
+    for corpus in corpus::all(size) {
+        if !filters.is_empty() && !filters.iter().any(|f| corpus.name.contains(f.as_str())) {
+            continue;
+        }
+
+        bench(&corpus);
+    }
+}
+
+fn bench(corpus: &corpus::Corpus) {
+    let text = corpus.text.as_str();
+
+    // Make sure the corpus is valid, otherwise the results are meaningless.
+    let count = count_tokens(text);
+
+    let mut best = Duration::from_secs(u64::MAX);
+    for _ in 0..ITERATIONS {
+        let now = Instant::now();
+        let n = count_tokens(text);
+        let elapsed = now.elapsed();
+        assert_eq!(n, count);
+
+        if elapsed < best {
+            best = elapsed;
+        }
+    }
+
+    println!("{}: {} bytes, {} tokens, {:.2} MB/s ({:?})",
+             corpus.name, text.len(), count, throughput(text.len(), best), best);
+
+    let mut kinds = vec![Kind::default(); KINDS.len()];
+    for _ in 0..ITERATIONS {
+        breakdown(text, &mut kinds);
+    }
+
+    for (name, kind) in KINDS.iter().zip(kinds.iter()) {
+        if kind.count == 0 {
+            continue;
+        }
+
+        let bytes = kind.bytes / ITERATIONS;
+        let time = kind.time / ITERATIONS as u32;
+        println!("  {:<18} {:>9} tokens {:>11} bytes {:>10.2} MB/s",
+                 name, kind.count / ITERATIONS, bytes, throughput(bytes, time));
+    }
+
+    println!();
+}
+
+fn count_tokens(text: &str) -> usize {
+    let mut n = 0;
+    for token in xml::Tokenizer::from(text) {
+        if let Err(e) = token {
+            panic!("{}", xml::Diagnostic::new(&e, text));
+        }
+
+        n += 1;
+    }
+
+    n
+}
+
+const KINDS: &[&str] = &[
+    "Declaration",
+    "PI",
+    "Comment",
+    "DTD",
+    "EntityDeclaration",
+    "ElementStart",
+    "Attribute",
+    "ElementEnd",
+    "Text",
+    "Whitespaces",
+    "CDATA",
+];
+
+#[derive(Clone, Default)]
+struct Kind {
+    count: usize,
+    bytes: usize,
+    time: Duration,
+}
+
+fn breakdown(text: &str, kinds: &mut [Kind]) {
+    let mut tokenizer = xml::Tokenizer::from(text);
+    loop {
+        let start = tokenizer.pos();
+        let now = Instant::now();
+        let token = match tokenizer.next() {
+            Some(token) => token.unwrap(),
+            None => break,
+        };
+        let elapsed = now.elapsed();
+
+        let kind = &mut kinds[kind_index(&token)];
+        kind.count += 1;
+        kind.bytes += tokenizer.pos() - start;
+        kind.time += elapsed;
+    }
+}
+
+fn kind_index(token: &xml::Token) -> usize {
+    match *token {
+        xml::Token::Declaration(..) => 0,
+        xml::Token::ProcessingInstruction(..) => 1,
+        xml::Token::Comment(..) => 2,
+        xml::Token::DtdStart(..) | xml::Token::EmptyDtd(..) | xml::Token::DtdEnd => 3,
+        xml::Token::EntityDeclaration(..) => 4,
+        xml::Token::ElementStart(..) => 5,
+        xml::Token::Attribute(..) => 6,
+        xml::Token::ElementEnd(..) => 7,
+        xml::Token::Text(..) => 8,
+        xml::Token::Whitespaces(..) => 9,
+        xml::Token::Cdata(..) => 10,
+    }
+}
+
+fn throughput(bytes: usize, time: Duration) -> f64 {
+    let secs = time.as_secs() as f64 + f64::from(time.subsec_nanos()) / 1e9;
+    if secs == 0.0 {
+        return 0.0;
+    }
+
+    bytes as f64 / (1024.0 * 1024.0) / secs
+}
@@ -14,7 +14,7 @@ fn main() {
     let text = load_file(&args[1]);
 
     if let Err(e) = parse(&text) {
-        println!("Error: {}.", e);
+        print!("{}", xml::Diagnostic::new(&e, &text));
     }
 }
 
@@ -0,0 +1,138 @@
+// Content models compiled into Glushkov automata.
+//
+// Used by the DTD and the XML Schema validators.
+
+// A content particle.
+pub(crate) enum Particle {
+    // An index in the automaton symbols.
+    Symbol(usize),
+    Seq(Vec<Particle>),
+    Choice(Vec<Particle>),
+    Optional(Box<Particle>),
+    ZeroOrMore(Box<Particle>),
+    OneOrMore(Box<Particle>),
+}
+
+impl Particle {
+    // Fills the `follow` sets of the Glushkov automaton
+    // and returns whether the particle is nullable, its first and its last positions.
+    fn compile(&self, follow: &mut [Vec<usize>]) -> (bool, Vec<usize>, Vec<usize>) {
+        match *self {
+            Particle::Symbol(idx) => (false, vec![idx], vec![idx]),
+            Particle::Seq(ref items) => {
+                let mut nullable = true;
+                let mut first = Vec::new();
+                let mut last: Vec<usize> = Vec::new();
+                for item in items {
+                    let (n, f, l) = item.compile(follow);
+                    for &p in &last {
+                        follow[p].extend_from_slice(&f);
+                    }
+
+                    if nullable {
+                        first.extend_from_slice(&f);
+                    }
+
+                    if n {
+                        last.extend(l);
+                    } else {
+                        last = l;
+                    }
+
+                    nullable &= n;
+                }
+
+                (nullable, first, last)
+            }
+            Particle::Choice(ref items) => {
+                let mut nullable = false;
+                let mut first = Vec::new();
+                let mut last = Vec::new();
+                for item in items {
+                    let (n, f, l) = item.compile(follow);
+                    nullable |= n;
+                    first.extend(f);
+                    last.extend(l);
+                }
+
+                (nullable, first, last)
+            }
+            Particle::Optional(ref p) => {
+                let (_, first, last) = p.compile(follow);
+                (true, first, last)
+            }
+            Particle::ZeroOrMore(ref p) | Particle::OneOrMore(ref p) => {
+                let (n, first, last) = p.compile(follow);
+                for &l in &last {
+                    follow[l].extend_from_slice(&first);
+                }
+
+                let nullable = n || matches!(*self, Particle::ZeroOrMore(_));
+                (nullable, first, last)
+            }
+        }
+    }
+}
+
+// A Glushkov automaton.
+//
+// Each state is a position of a symbol in the model, plus the initial state.
+// Content models are not required to be deterministic,
+// so the automaton is simulated with a set of states.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct Automaton<T> {
+    symbols: Vec<T>,
+    // Indexed by state. The initial state is the last one.
+    follow: Vec<Vec<usize>>,
+    accept: Vec<bool>,
+}
+
+impl<T> Automaton<T> {
+    pub(crate) fn new(particle: &Particle, symbols: Vec<T>) -> Self {
+        let mut follow = vec![Vec::new(); symbols.len() + 1];
+        let (nullable, first, last) = particle.compile(&mut follow);
+
+        let start = symbols.len();
+        follow[start] = first;
+        for f in &mut follow {
+            f.sort();
+            f.dedup();
+        }
+
+        let mut accept = vec![false; symbols.len() + 1];
+        for p in last {
+            accept[p] = true;
+        }
+        accept[start] = nullable;
+
+        Automaton { symbols, follow, accept }
+    }
+
+    pub(crate) fn start(&self) -> Vec<usize> {
+        vec![self.symbols.len()]
+    }
+
+    // Returns the next states. Empty when no symbol is matched by `f`.
+    pub(crate) fn step<F: Fn(&T) -> bool>(&self, states: &[usize], f: F) -> Vec<usize> {
+        let mut next = Vec::new();
+        for &s in states {
+            for &p in &self.follow[s] {
+                if f(&self.symbols[p]) && !next.contains(&p) {
+                    next.push(p);
+                }
+            }
+        }
+
+        next
+    }
+
+    // Returns the symbol matched by the transition into the state.
+    #[cfg(feature = "schema")]
+    pub(crate) fn symbol(&self, state: usize) -> Option<&T> {
+        self.symbols.get(state)
+    }
+
+    pub(crate) fn accepts(&self, states: &[usize]) -> bool {
+        states.iter().any(|&s| self.accept[s])
+    }
+}
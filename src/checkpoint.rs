@@ -0,0 +1,210 @@
+use std::error;
+use std::fmt;
+
+use {
+    State,
+    Stream,
+    Tokenizer,
+    XmlByteExt,
+};
+
+
+/// A checkpoint restoring error.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CheckpointError {
+    /// The position is outside the tokenizer text.
+    OutOfRange(usize),
+    /// The position is not at a token boundary.
+    NotTokenBoundary(usize),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CheckpointError::OutOfRange(pos) => {
+                write!(f, "checkpoint position {} is out of range", pos)
+            }
+            CheckpointError::NotTokenBoundary(pos) => {
+                write!(f, "checkpoint position {} is not at a token boundary", pos)
+            }
+        }
+    }
+}
+
+impl error::Error for CheckpointError {
+    fn description(&self) -> &str {
+        "a checkpoint restoring error"
+    }
+}
+
+
+/// A tokenizer state snapshot.
+///
+/// Contains only a byte position and a parsing state,
+/// so it can be stored and used with any copy of the same text.
+///
+/// Created by [`Tokenizer::checkpoint`].
+///
+/// [`Tokenizer::checkpoint`]: struct.Tokenizer.html#method.checkpoint
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Checkpoint {
+    pos: usize,
+    state: State,
+    depth: usize,
+    fragment_parsing: bool,
+    xml11: bool,
+}
+
+impl Checkpoint {
+    /// Creates a checkpoint at the element content.
+    ///
+    /// `pos` is an absolute byte position, usually a start of a child element,
+    /// and `depth` is a number of currently open elements.
+    /// Useful when only positions were stored, like in a records index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Checkpoint, Token, Tokenizer};
+    ///
+    /// let text = "<records><record id='1'/><record id='2'/></records>";
+    /// let pos = text.find("<record id='2'").unwrap();
+    ///
+    /// let mut p = Tokenizer::from_checkpoint(text, Checkpoint::new(pos, 1)).unwrap();
+    /// match p.nth(1) {
+    ///     Some(Ok(Token::Attribute(_, value))) => assert_eq!(value.to_str(), "2"),
+    ///     _ => panic!(),
+    /// }
+    /// ```
+    pub fn new(pos: usize, depth: usize) -> Self {
+        Checkpoint {
+            pos,
+            state: State::Elements,
+            depth,
+            fragment_parsing: false,
+            xml11: false,
+        }
+    }
+
+    /// Returns an absolute byte position in the original text.
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns a number of open elements at the checkpoint.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+
+impl<'a> Tokenizer<'a> {
+    /// Creates a tokenizer that starts at the checkpoint.
+    ///
+    /// The checkpoint can be made by a tokenizer of another copy of the same text.
+    /// Modes, like the strict mode, are not stored in a checkpoint,
+    /// except the XML 1.1 mode, and should be enabled again.
+    ///
+    /// See [`restore`] for details.
+    ///
+    /// [`restore`]: #method.restore
+    pub fn from_checkpoint(text: &'a str, checkpoint: Checkpoint) -> Result<Self, CheckpointError> {
+        let mut tokenizer = Tokenizer::from(text);
+        tokenizer.restore(checkpoint)?;
+        Ok(tokenizer)
+    }
+
+    /// Returns the current tokenizer state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Tokenizer;
+    ///
+    /// let mut p = Tokenizer::from("<a><b/><c/></a>");
+    /// p.next(); // <a
+    /// p.next(); // >
+    ///
+    /// let checkpoint = p.checkpoint();
+    /// let tokens: Vec<_> = p.by_ref().map(|t| t.unwrap()).collect();
+    ///
+    /// p.restore(checkpoint).unwrap();
+    /// assert_eq!(p.map(|t| t.unwrap()).collect::<Vec<_>>(), tokens);
+    /// ```
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            pos: self.pos(),
+            state: self.state,
+            depth: self.depth,
+            fragment_parsing: self.fragment_parsing,
+            xml11: self.stream.is_xml11_mode(),
+        }
+    }
+
+    /// Restores the tokenizer state from the checkpoint.
+    ///
+    /// The checkpoint must be made by a tokenizer of the same text.
+    /// Only a few basic checks are performed, like that the position
+    /// is inside the tokenizer text and that the previous token ends right before it.
+    /// On error, the tokenizer is left unchanged.
+    pub fn restore(&mut self, checkpoint: Checkpoint) -> Result<(), CheckpointError> {
+        let span = self.stream.span();
+        if checkpoint.pos < span.start() || checkpoint.pos > span.end() {
+            return Err(CheckpointError::OutOfRange(checkpoint.pos));
+        }
+
+        let pos = checkpoint.pos - span.start();
+        if !is_token_boundary(span.to_str(), pos, checkpoint.state) {
+            return Err(CheckpointError::NotTokenBoundary(checkpoint.pos));
+        }
+
+        let mut stream = Stream::from(span);
+        if self.stream.is_cr_line_breaks_mode() {
+            stream.enable_cr_line_breaks_mode();
+        }
+
+        if checkpoint.xml11 {
+            stream.enable_xml11_mode();
+        }
+
+        stream.advance(pos);
+
+        self.stream = stream;
+        self.peeked.clear();
+        self.state = checkpoint.state;
+        self.depth = checkpoint.depth;
+        self.fragment_parsing = checkpoint.fragment_parsing;
+        Ok(())
+    }
+}
+
+// Checks that a token can start at `pos`.
+//
+// All tokens, except text, end with `>`, and text ends before `<`.
+// Inside a start tag, attributes are separated by spaces, but can
+// follow a quote of the previous one.
+fn is_token_boundary(text: &str, pos: usize, state: State) -> bool {
+    if !text.is_char_boundary(pos) {
+        return false;
+    }
+
+    let bytes = text.as_bytes();
+    if pos == 0 || pos == bytes.len() || state == State::End {
+        return true;
+    }
+
+    let prev = bytes[pos - 1];
+    let curr = bytes[pos];
+    match state {
+        State::Attributes => {
+            prev == b'\'' || prev == b'"' || prev.is_xml_space()
+                || curr.is_xml_space() || curr == b'/' || curr == b'>'
+        }
+        State::Dtd => {
+            prev == b'>' || prev == b'[' || curr == b'<' || curr == b']'
+        }
+        _ => {
+            prev == b'>' || curr == b'<'
+        }
+    }
+}
@@ -0,0 +1,719 @@
+// Parsers for the XML Schema built-in datatypes lexical forms.
+//
+// See https://www.w3.org/TR/xmlschema11-2/#built-in-datatypes
+
+use {
+    Stream,
+    StreamError,
+    StrSpan,
+    XmlByteExt,
+    XmlCharExt,
+};
+
+type Result<T> = ::std::result::Result<T, StreamError>;
+
+
+/// An [`xs:date`](https://www.w3.org/TR/xmlschema11-2/#date) value.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Date {
+    /// A year. Can be negative.
+    pub year: i32,
+    /// A month in the 1..=12 range.
+    pub month: u8,
+    /// A day in the 1..=31 range.
+    pub day: u8,
+    /// A timezone offset in minutes.
+    pub timezone: Option<i16>,
+}
+
+/// An [`xs:dateTime`](https://www.w3.org/TR/xmlschema11-2/#dateTime) value.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DateTime {
+    /// A date. The timezone is stored in the date.
+    pub date: Date,
+    /// An hour in the 0..=24 range. `24` is allowed only for `24:00:00`.
+    pub hour: u8,
+    /// A minute in the 0..=59 range.
+    pub minute: u8,
+    /// A second in the 0..=59 range.
+    pub second: u8,
+    /// A fractional part of a second. Digits after the ninth one are ignored.
+    pub nanosecond: u32,
+}
+
+/// An [`xs:duration`](https://www.w3.org/TR/xmlschema11-2/#duration) value.
+///
+/// Components are stored as is, without normalization, so `PT90M` has 90 minutes.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+#[allow(missing_docs)]
+pub struct Duration {
+    pub negative: bool,
+    pub years: u32,
+    pub months: u32,
+    pub days: u32,
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+    pub nanoseconds: u32,
+}
+
+
+/// An iterator over whitespace-separated list items.
+///
+/// Can be used for list datatypes, like `xs:NMTOKENS` or `xs:IDREFS`.
+/// Items can be parsed further using a `Stream`.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::ValueList;
+///
+/// let items: Vec<_> = ValueList::from(" a\tb  c ").map(|s| s.to_str()).collect();
+/// assert_eq!(items, vec!["a", "b", "c"]);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ValueList<'a> {
+    stream: Stream<'a>,
+}
+
+impl<'a> From<&'a str> for ValueList<'a> {
+    fn from(text: &'a str) -> Self {
+        Self::from(StrSpan::from(text))
+    }
+}
+
+impl<'a> From<StrSpan<'a>> for ValueList<'a> {
+    fn from(span: StrSpan<'a>) -> Self {
+        ValueList { stream: Stream::from(span) }
+    }
+}
+
+impl<'a> Iterator for ValueList<'a> {
+    type Item = StrSpan<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let s = &mut self.stream;
+        s.skip_ascii_spaces();
+        if s.at_end() {
+            return None;
+        }
+
+        let start = s.pos();
+        s.skip_bytes(|_, c| !c.is_xml_space());
+        Some(s.slice_back(start))
+    }
+}
+
+
+impl<'a> Stream<'a> {
+    /// Parses a whole value, like an attribute value, using one of the `consume_*` methods.
+    ///
+    /// Leading and trailing whitespaces are allowed, like in the XML Schema `collapse` mode.
+    /// The value is parsed as is, so references are not resolved.
+    ///
+    /// Positions in errors are relative to the original text, not to the value.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidCharacter` - when there is data after the parsed value.
+    /// - Any error of the `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Stream, Token, Tokenizer};
+    ///
+    /// let p = Tokenizer::from("<rect width='10' height=' 1e2 ' x='1px'/>");
+    /// let mut values = Vec::new();
+    /// for token in p {
+    ///     if let Token::Attribute(_, value) = token.unwrap() {
+    ///         values.push(Stream::parse_value(value, Stream::consume_double));
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(values[0].as_ref().unwrap(), &10.0);
+    /// assert_eq!(values[1].as_ref().unwrap(), &100.0);
+    /// assert_eq!(values[2].as_ref().unwrap_err().to_string(),
+    ///            "character 'p' is not allowed at 1:37");
+    /// ```
+    pub fn parse_value<T, F>(value: StrSpan<'a>, f: F) -> Result<T>
+        where F: FnOnce(&mut Stream<'a>) -> Result<T>
+    {
+        let mut s = Stream::from(value);
+        s.skip_ascii_spaces();
+        let v = f(&mut s)?;
+        s.skip_ascii_spaces();
+
+        if !s.at_end() {
+            let c = s.curr_char()?;
+            return Err(StreamError::InvalidCharacter(c, s.gen_text_pos()));
+        }
+
+        Ok(v)
+    }
+
+    /// Consumes an [`xs:boolean`](https://www.w3.org/TR/xmlschema11-2/#boolean) value.
+    ///
+    /// `true`, `false`, `1` or `0`.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidValue`
+    pub fn consume_boolean(&mut self) -> Result<bool> {
+        for &(text, value) in &[("true", true), ("false", false), ("1", true), ("0", false)] {
+            if self.starts_with(text.as_bytes()) {
+                self.advance(text.len());
+                return Ok(value);
+            }
+        }
+
+        Err(invalid(self, "boolean"))
+    }
+
+    /// Consumes an [`xs:integer`](https://www.w3.org/TR/xmlschema11-2/#integer) value.
+    ///
+    /// Values outside of the `i64` range are not supported.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidValue`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Stream;
+    ///
+    /// assert_eq!(Stream::from("-0042").consume_integer().unwrap(), -42);
+    /// assert!(Stream::from("9223372036854775808").consume_integer().is_err());
+    /// ```
+    pub fn consume_integer(&mut self) -> Result<i64> {
+        let start = *self;
+        let negative = consume_sign(self);
+        let value = consume_digits(self, "integer")?;
+
+        // `i64::MIN` doesn't have a positive counterpart.
+        let max = if negative { i64::MAX as u64 + 1 } else { i64::MAX as u64 };
+        if value > max {
+            return Err(invalid(&start, "integer"));
+        }
+
+        let value = value as i64;
+        Ok(if negative { value.wrapping_neg() } else { value })
+    }
+
+    /// Consumes an [`xs:decimal`](https://www.w3.org/TR/xmlschema11-2/#decimal) value.
+    ///
+    /// The value is converted to `f64`, so it can lose precision.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidValue`
+    pub fn consume_decimal(&mut self) -> Result<f64> {
+        let start = self.pos();
+        consume_sign(self);
+        consume_fraction(self, "decimal")?;
+        parse_f64(self, start, "decimal")
+    }
+
+    /// Consumes an [`xs:double`](https://www.w3.org/TR/xmlschema11-2/#double) value.
+    ///
+    /// Also used for `xs:float`. Includes `INF`, `-INF`, `+INF` and `NaN`.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidValue`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Stream;
+    ///
+    /// assert_eq!(Stream::from("-1.5E2").consume_double().unwrap(), -150.0);
+    /// assert_eq!(Stream::from(".5").consume_double().unwrap(), 0.5);
+    /// assert!(Stream::from("-INF").consume_double().unwrap().is_infinite());
+    /// assert!(Stream::from("NaN").consume_double().unwrap().is_nan());
+    /// ```
+    pub fn consume_double(&mut self) -> Result<f64> {
+        let start = self.pos();
+        let negative = consume_sign(self);
+
+        if self.starts_with(b"INF") {
+            self.advance(3);
+            return Ok(if negative { f64::NEG_INFINITY } else { f64::INFINITY });
+        }
+
+        // `NaN` cannot have a sign.
+        if self.pos() == start && self.starts_with(b"NaN") {
+            self.advance(3);
+            return Ok(f64::NAN);
+        }
+
+        consume_fraction(self, "double")?;
+
+        if let Some(b'e') | Some(b'E') = self.get_curr_byte() {
+            self.advance(1);
+            consume_sign(self);
+            if skip_digits(self) == 0 {
+                return Err(invalid(self, "double"));
+            }
+        }
+
+        parse_f64(self, start, "double")
+    }
+
+    /// Consumes an [`xs:date`](https://www.w3.org/TR/xmlschema11-2/#date) value.
+    ///
+    /// Like `2001-10-26`, `-0044-03-15` or `2001-10-26+02:00`.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidValue` - on an invalid syntax or a value out of range,
+    ///   like `2001-02-29`.
+    pub fn consume_date(&mut self) -> Result<Date> {
+        let (year, month, day) = consume_date_parts(self, "date")?;
+        let timezone = consume_timezone(self, "date")?;
+        Ok(Date { year, month, day, timezone })
+    }
+
+    /// Consumes an [`xs:dateTime`](https://www.w3.org/TR/xmlschema11-2/#dateTime) value.
+    ///
+    /// Like `2001-10-26T21:32:52`, `2001-10-26T21:32:52.12679Z`
+    /// or `2001-10-26T24:00:00-05:00`.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidValue` - on an invalid syntax or a value out of range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Stream;
+    ///
+    /// let dt = Stream::from("2001-10-26T21:32:52.5+02:00").consume_date_time().unwrap();
+    /// assert_eq!((dt.date.year, dt.date.month, dt.date.day), (2001, 10, 26));
+    /// assert_eq!((dt.hour, dt.minute, dt.second), (21, 32, 52));
+    /// assert_eq!(dt.nanosecond, 500_000_000);
+    /// assert_eq!(dt.date.timezone, Some(120));
+    /// ```
+    pub fn consume_date_time(&mut self) -> Result<DateTime> {
+        const NAME: &str = "dateTime";
+
+        let (year, month, day) = consume_date_parts(self, NAME)?;
+        consume_char(self, b'T', NAME)?;
+
+        let hour_start = *self;
+        let hour = consume_fixed(self, 2, NAME)?;
+        consume_char(self, b':', NAME)?;
+        let minute_start = *self;
+        let minute = consume_fixed(self, 2, NAME)?;
+        consume_char(self, b':', NAME)?;
+        let second_start = *self;
+        let second = consume_fixed(self, 2, NAME)?;
+
+        let mut nanosecond = 0;
+        if self.is_curr_byte_eq(b'.') {
+            self.advance(1);
+            let start = *self;
+            let mut count = 0;
+            while let Some(c) = self.get_curr_byte() {
+                if !c.is_xml_digit() {
+                    break;
+                }
+
+                if count < 9 {
+                    nanosecond = nanosecond * 10 + u32::from(c - b'0');
+                }
+
+                count += 1;
+                self.advance(1);
+            }
+
+            if count == 0 {
+                return Err(invalid(&start, NAME));
+            }
+
+            for _ in count..9 {
+                nanosecond *= 10;
+            }
+        }
+
+        if minute > 59 {
+            return Err(invalid(&minute_start, NAME));
+        }
+
+        if second > 59 {
+            return Err(invalid(&second_start, NAME));
+        }
+
+        // `24:00:00` is the end of the day.
+        if hour > 24 || (hour == 24 && (minute != 0 || second != 0 || nanosecond != 0)) {
+            return Err(invalid(&hour_start, NAME));
+        }
+
+        let timezone = consume_timezone(self, NAME)?;
+
+        Ok(DateTime {
+            date: Date { year, month, day, timezone },
+            hour: hour as u8,
+            minute: minute as u8,
+            second: second as u8,
+            nanosecond,
+        })
+    }
+
+    /// Consumes an [`xs:duration`](https://www.w3.org/TR/xmlschema11-2/#duration) value.
+    ///
+    /// Like `P1Y2M3DT10H30M`, `-P120D` or `PT1.5S`.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidValue` - on an invalid syntax or a component larger than `u32`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Stream;
+    ///
+    /// let d = Stream::from("P1Y2MT0.25S").consume_duration().unwrap();
+    /// assert_eq!((d.years, d.months, d.days), (1, 2, 0));
+    /// assert_eq!((d.seconds, d.nanoseconds), (0, 250_000_000));
+    /// ```
+    pub fn consume_duration(&mut self) -> Result<Duration> {
+        const NAME: &str = "duration";
+
+        let mut duration = Duration::default();
+        if self.is_curr_byte_eq(b'-') {
+            self.advance(1);
+            duration.negative = true;
+        }
+
+        consume_char(self, b'P', NAME)?;
+
+        let mut has_value = false;
+        {
+            let mut fields = [
+                (b'Y', &mut duration.years),
+                (b'M', &mut duration.months),
+                (b'D', &mut duration.days),
+            ];
+            has_value |= consume_duration_fields(self, &mut fields, NAME)? != 0;
+        }
+
+        if self.is_curr_byte_eq(b'T') {
+            self.advance(1);
+
+            // Seconds with a fraction are handled separately.
+            let start = *self;
+            let mut fields = [
+                (b'H', &mut duration.hours),
+                (b'M', &mut duration.minutes),
+                (b'S', &mut duration.seconds),
+            ];
+            let next = consume_duration_fields(self, &mut fields, NAME)?;
+            let has_fraction = self.get_curr_byte().map_or(false, |c| c.is_xml_digit());
+            if next == 0 && !has_fraction {
+                return Err(invalid(&start, NAME));
+            }
+
+            // Like `PT1S1.5S`.
+            if has_fraction && next == fields.len() {
+                return Err(invalid(self, NAME));
+            }
+
+            if has_fraction {
+                let seconds_start = *self;
+                let seconds = consume_digits(self, NAME)?;
+                if seconds > u64::from(u32::MAX) {
+                    return Err(invalid(&seconds_start, NAME));
+                }
+
+                consume_char(self, b'.', NAME)?;
+                let fraction_start = self.pos();
+                if skip_digits(self) == 0 {
+                    return Err(invalid(self, NAME));
+                }
+
+                let fraction = self.slice_back(fraction_start).to_str().as_bytes();
+                let mut nanoseconds = 0;
+                for i in 0..9 {
+                    let digit = fraction.get(i).map(|c| u32::from(c - b'0')).unwrap_or(0);
+                    nanoseconds = nanoseconds * 10 + digit;
+                }
+
+                consume_char(self, b'S', NAME)?;
+                duration.seconds = seconds as u32;
+                duration.nanoseconds = nanoseconds;
+            }
+
+            has_value = true;
+        }
+
+        if !has_value {
+            return Err(invalid(self, NAME));
+        }
+
+        Ok(duration)
+    }
+
+    /// Consumes an [`xs:QName`](https://www.w3.org/TR/xmlschema11-2/#QName) value
+    /// and returns its prefix and local part.
+    ///
+    /// Unlike `consume_qname`, checks that both parts are valid
+    /// [NCName](https://www.w3.org/TR/xml-names/#NT-NCName)s.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidValue`
+    pub fn consume_xs_qname(&mut self) -> Result<(StrSpan<'a>, StrSpan<'a>)> {
+        let start = *self;
+        let (prefix, local) = self.consume_qname().map_err(|_| invalid(&start, "QName"))?;
+
+        // An empty prefix, like in `:name`.
+        if prefix.is_empty() && local.start() != start.span().start() + start.pos() {
+            return Err(invalid(&start, "QName"));
+        }
+
+        for part in &[prefix, local] {
+            let is_valid = part.to_str().chars().next().map_or(true, |c| c.is_xml_name_start())
+                && !part.to_str().contains(':');
+            if !is_valid {
+                let pos = part.start() - start.span().start();
+                return Err(StreamError::InvalidValue("QName", start.gen_text_pos_from(pos)));
+            }
+        }
+
+        Ok((prefix, local))
+    }
+
+    /// Consumes an [`xs:anyURI`](https://www.w3.org/TR/xmlschema11-2/#anyURI) value.
+    ///
+    /// Consumes everything up to a whitespace. Checks that the value contains only
+    /// characters allowed in an [IRI](https://tools.ietf.org/html/rfc3987)
+    /// and that `%` starts a valid escape. An empty value is allowed.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidValue`
+    pub fn consume_any_uri(&mut self) -> Result<StrSpan<'a>> {
+        let start = self.pos();
+        while let Some(c) = self.get_curr_byte() {
+            match c {
+                b' ' | b'\t' | b'\n' | b'\r' => break,
+                b'%' => {
+                    let mut s = *self;
+                    s.advance(1);
+                    for _ in 0..2 {
+                        if !s.get_curr_byte().map_or(false, |c| c.is_xml_hex_digit()) {
+                            return Err(invalid(self, "anyURI"));
+                        }
+
+                        s.advance(1);
+                    }
+
+                    *self = s;
+                }
+                0x00..=0x1F | 0x7F | b'<' | b'>' | b'"' | b'{' | b'}' | b'|' | b'\\' | b'^' | b'`' => {
+                    return Err(invalid(self, "anyURI"));
+                }
+                _ => self.advance(1),
+            }
+        }
+
+        Ok(self.slice_back(start))
+    }
+}
+
+fn invalid(s: &Stream, name: &'static str) -> StreamError {
+    StreamError::InvalidValue(name, s.gen_text_pos())
+}
+
+fn consume_char(s: &mut Stream, c: u8, name: &'static str) -> Result<()> {
+    if !s.is_curr_byte_eq(c) {
+        return Err(invalid(s, name));
+    }
+
+    s.advance(1);
+    Ok(())
+}
+
+// Returns `true` for `-`.
+fn consume_sign(s: &mut Stream) -> bool {
+    match s.get_curr_byte() {
+        Some(b'+') => {
+            s.advance(1);
+            false
+        }
+        Some(b'-') => {
+            s.advance(1);
+            true
+        }
+        _ => false,
+    }
+}
+
+// Returns the number of skipped digits.
+fn skip_digits(s: &mut Stream) -> usize {
+    let start = s.pos();
+    s.skip_bytes(|_, c| c.is_xml_digit());
+    s.pos() - start
+}
+
+// At least one digit is required.
+fn consume_digits(s: &mut Stream, name: &'static str) -> Result<u64> {
+    let start = *s;
+    let mut value = Some(0u64);
+    while let Some(c) = s.get_curr_byte() {
+        if !c.is_xml_digit() {
+            break;
+        }
+
+        value = value.and_then(|v| v.checked_mul(10)).and_then(|v| v.checked_add(u64::from(c - b'0')));
+        s.advance(1);
+    }
+
+    if s.pos() == start.pos() {
+        return Err(invalid(s, name));
+    }
+
+    value.ok_or_else(|| invalid(&start, name))
+}
+
+// Exactly `len` digits.
+fn consume_fixed(s: &mut Stream, len: usize, name: &'static str) -> Result<u32> {
+    let mut value = 0;
+    for _ in 0..len {
+        match s.get_curr_byte() {
+            Some(c) if c.is_xml_digit() => value = value * 10 + u32::from(c - b'0'),
+            _ => return Err(invalid(s, name)),
+        }
+
+        s.advance(1);
+    }
+
+    Ok(value)
+}
+
+// `\d+ ('.' \d*)? | '.' \d+`
+fn consume_fraction(s: &mut Stream, name: &'static str) -> Result<()> {
+    let mut count = skip_digits(s);
+    if s.is_curr_byte_eq(b'.') {
+        s.advance(1);
+        count += skip_digits(s);
+    }
+
+    if count == 0 {
+        return Err(invalid(s, name));
+    }
+
+    Ok(())
+}
+
+fn parse_f64(s: &Stream, start: usize, name: &'static str) -> Result<f64> {
+    s.slice_back(start).to_str().parse().map_err(|_| {
+        StreamError::InvalidValue(name, s.gen_text_pos_from(start))
+    })
+}
+
+// '-'? yyyy '-' mm '-' dd
+fn consume_date_parts(s: &mut Stream, name: &'static str) -> Result<(i32, u8, u8)> {
+    let negative = s.is_curr_byte_eq(b'-');
+    if negative {
+        s.advance(1);
+    }
+
+    // At least four digits and no leading zeros in longer years.
+    let year_start = *s;
+    let year = consume_digits(s, name)?;
+    let len = s.pos() - year_start.pos();
+    if len < 4 || (len > 4 && year_start.curr_byte_unchecked() == b'0') || year > i32::MAX as u64 {
+        return Err(invalid(&year_start, name));
+    }
+
+    let year = if negative { -(year as i32) } else { year as i32 };
+
+    consume_char(s, b'-', name)?;
+    let month_start = *s;
+    let month = consume_fixed(s, 2, name)?;
+    if !(1..=12).contains(&month) {
+        return Err(invalid(&month_start, name));
+    }
+
+    consume_char(s, b'-', name)?;
+    let day_start = *s;
+    let day = consume_fixed(s, 2, name)?;
+    if day < 1 || day > days_in_month(year, month) {
+        return Err(invalid(&day_start, name));
+    }
+
+    Ok((year, month as u8, day as u8))
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 => {
+            let is_leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+            if is_leap { 29 } else { 28 }
+        }
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// ('Z' | ('+' | '-') hh ':' mm)?
+fn consume_timezone(s: &mut Stream, name: &'static str) -> Result<Option<i16>> {
+    let negative = match s.get_curr_byte() {
+        Some(b'Z') => {
+            s.advance(1);
+            return Ok(Some(0));
+        }
+        Some(b'+') => false,
+        Some(b'-') => true,
+        _ => return Ok(None),
+    };
+
+    let start = *s;
+    s.advance(1);
+    let hours = consume_fixed(s, 2, name)?;
+    consume_char(s, b':', name)?;
+    let minutes = consume_fixed(s, 2, name)?;
+
+    if minutes > 59 || hours > 14 || (hours == 14 && minutes != 0) {
+        return Err(invalid(&start, name));
+    }
+
+    let offset = (hours * 60 + minutes) as i16;
+    Ok(Some(if negative { -offset } else { offset }))
+}
+
+// Consumes `\d+ designator` pairs in order. A pair followed by `.` is left as is.
+//
+// Returns the index of the field after the last consumed one or 0 when nothing was consumed.
+fn consume_duration_fields(s: &mut Stream, fields: &mut [(u8, &mut u32)], name: &'static str)
+    -> Result<usize>
+{
+    let mut idx = 0;
+    while s.get_curr_byte().map_or(false, |c| c.is_xml_digit()) {
+        let start = *s;
+        let value = consume_digits(s, name)?;
+        if s.is_curr_byte_eq(b'.') {
+            *s = start;
+            break;
+        }
+
+        let designator = s.curr_byte().map_err(|_| invalid(s, name))?;
+        let offset = match fields[idx..].iter().position(|f| f.0 == designator) {
+            Some(i) => i,
+            None => return Err(invalid(s, name)),
+        };
+
+        if value > u64::from(u32::MAX) {
+            return Err(invalid(&start, name));
+        }
+
+        s.advance(1);
+        *fields[idx + offset].1 = value as u32;
+        idx += offset + 1;
+    }
+
+    Ok(idx)
+}
@@ -0,0 +1,923 @@
+//! A [serde](https://serde.rs) deserializer built on top of the [`tree`].
+//!
+//! Elements are mapped to structs and maps:
+//!
+//! - Attributes and child elements are mapped to fields with the same name.
+//!   Struct fields of attributes can also be named with the `@` prefix, like `@id`,
+//!   the way the [`ser`] module expects them.
+//! - Repeated child elements are mapped to a `Vec` or any other sequence.
+//!   They don't have to be adjacent.
+//! - The element text is mapped to the [`TEXT_FIELD`] field.
+//!   Whitespaces-only text is ignored.
+//!
+//! Elements without attributes and child elements, like `<size>10</size>`,
+//! and attribute values can be mapped to strings, numbers, booleans,
+//! unit enum variants and sequences of whitespace-separated values.
+//!
+//! An enum can also be mapped to an element with a single child element,
+//! whose name is used as a variant name.
+//!
+//! Namespace declarations are ignored. By default, names are matched by the local name.
+//! With [`Deserializer::enable_expanded_names`], names in a namespace are matched
+//! by the expanded name in the `{uri}local` form.
+//!
+//! References are unescaped. When no unescaping is needed, strings are borrowed
+//! from the input, so `&str` fields can be used with `#[serde(borrow)]`.
+//!
+//! # Example
+//!
+//! ```
+//! #[macro_use] extern crate serde_derive;
+//! extern crate xmlparser;
+//!
+//! #[derive(Deserialize, PartialEq, Debug)]
+//! struct Config<'a> {
+//!     version: u32,
+//!     #[serde(borrow)]
+//!     name: &'a str,
+//!     #[serde(rename = "server")]
+//!     servers: Vec<Server>,
+//! }
+//!
+//! #[derive(Deserialize, PartialEq, Debug)]
+//! struct Server {
+//!     port: u16,
+//!     #[serde(rename = "$text")]
+//!     host: String,
+//! }
+//!
+//! # fn main() {
+//! let text = "
+//! <config version='2'>
+//!     <name>Example</name>
+//!     <server port='80'>example.com</server>
+//!     <server port='8080'>localhost</server>
+//! </config>";
+//!
+//! let config: Config = xmlparser::de::from_str(text).unwrap();
+//! assert_eq!(config, Config {
+//!     version: 2,
+//!     name: "Example",
+//!     servers: vec![
+//!         Server { port: 80, host: "example.com".to_string() },
+//!         Server { port: 8080, host: "localhost".to_string() },
+//!     ],
+//! });
+//! # }
+//! ```
+//!
+//! [`tree`]: ../tree/index.html
+//! [`ser`]: ../ser/index.html
+//! [`TEXT_FIELD`]: constant.TEXT_FIELD.html
+//! [`Deserializer::enable_expanded_names`]: struct.Deserializer.html#method.enable_expanded_names
+
+use std::borrow::Cow;
+use std::error;
+use std::fmt;
+use std::iter;
+use std::vec;
+
+use serde::de::{
+    self,
+    DeserializeSeed,
+    EnumAccess,
+    MapAccess,
+    SeqAccess,
+    Unexpected,
+    VariantAccess,
+    Visitor,
+};
+use serde::Deserialize;
+
+use sax::is_namespace_declaration;
+use text::unescape;
+use tree::{
+    Document,
+    Node,
+    NodeKind,
+};
+use {
+    Attribute,
+    CharsKind,
+    StrSpan,
+    TextPos,
+    XmlByteExt,
+};
+
+
+/// The name of the field the element text is mapped to.
+pub const TEXT_FIELD: &str = "$text";
+
+pub(crate) const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+
+/// A deserialization error.
+#[derive(Debug)]
+pub enum Error {
+    /// An XML parsing error.
+    Parse(::Error),
+    /// An error produced by a deserialized type, like a missing field
+    /// or an invalid number.
+    ///
+    /// Contains a position of the element or attribute value
+    /// that caused the error, when known.
+    Custom(String, Option<TextPos>),
+}
+
+impl Error {
+    /// Returns the error position.
+    pub fn pos(&self) -> Option<TextPos> {
+        match *self {
+            Error::Parse(ref e) => Some(e.pos()),
+            Error::Custom(_, pos) => pos,
+        }
+    }
+
+    // Sets the position at the byte `offset`, unless it's already set.
+    //
+    // The position is calculated with the document modes,
+    // just like the positions of parsing errors.
+    fn at(self, doc: &Document, offset: usize) -> Self {
+        match self {
+            Error::Custom(msg, None) => {
+                Error::Custom(msg, Some(doc.text_pos_at(offset)))
+            }
+            e => e,
+        }
+    }
+}
+
+impl From<::Error> for Error {
+    fn from(e: ::Error) -> Self {
+        Error::Parse(e)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Parse(ref e) => write!(f, "{}", e),
+            Error::Custom(ref msg, Some(pos)) => write!(f, "{} at {}", msg, pos),
+            Error::Custom(ref msg, None) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        "an XML deserialization error"
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Custom(msg.to_string(), None)
+    }
+}
+
+
+/// Deserializes an instance of `T` from the root element of the XML text.
+pub fn from_str<'a, T: Deserialize<'a>>(text: &'a str) -> Result<T, Error> {
+    let doc = Document::parse(text)?;
+    from_document(&doc)
+}
+
+/// Deserializes an instance of `T` from the root element of the document.
+pub fn from_document<'a, T: Deserialize<'a>>(doc: &Document<'a>) -> Result<T, Error> {
+    T::deserialize(Deserializer::from_document(doc)?)
+}
+
+
+/// An element deserializer.
+#[derive(Clone, Copy)]
+pub struct Deserializer<'a, 'd> {
+    node: Node<'a, 'd>,
+    expanded_names: bool,
+}
+
+impl<'a, 'd> Deserializer<'a, 'd> {
+    /// Creates a deserializer for the root element of the document.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate serde;
+    /// # extern crate xmlparser;
+    ///
+    /// use serde::Deserialize;
+    /// use xmlparser::de::Deserializer;
+    /// use xmlparser::tree::Document;
+    ///
+    /// # fn main() {
+    /// let doc = Document::parse("<x:size xmlns:x='urn:x'>10</x:size>").unwrap();
+    /// let mut de = Deserializer::from_document(&doc).unwrap();
+    /// de.enable_expanded_names();
+    /// assert_eq!(u32::deserialize(de).unwrap(), 10);
+    /// # }
+    /// ```
+    pub fn from_document(doc: &'d Document<'a>) -> Result<Self, Error> {
+        match doc.root_element() {
+            Some(node) => Ok(Self::from_node(node)),
+            None => Err(Error::Custom("the document has no root element".to_string(), None)),
+        }
+    }
+
+    /// Creates a deserializer for the element.
+    ///
+    /// # Panics
+    ///
+    /// - if the node is not an element
+    pub fn from_node(node: Node<'a, 'd>) -> Self {
+        assert!(node.is_element());
+
+        Deserializer {
+            node,
+            expanded_names: false,
+        }
+    }
+
+    /// Enables names matching by the expanded name.
+    ///
+    /// Names in a namespace will be mapped as `{uri}local`,
+    /// like `{http://www.w3.org/2000/svg}rect`.
+    /// Names without a namespace and names with an unknown prefix are mapped
+    /// by the local name, as usual.
+    pub fn enable_expanded_names(&mut self) {
+        self.expanded_names = true;
+    }
+
+    fn child(&self, node: Node<'a, 'd>) -> Self {
+        Deserializer {
+            node,
+            expanded_names: self.expanded_names,
+        }
+    }
+
+    fn document(&self) -> &'d Document<'a> {
+        self.node.document()
+    }
+
+    fn is_xml11(&self) -> bool {
+        self.node.document().is_xml11_mode()
+    }
+
+    fn offset(&self) -> usize {
+        self.node.span().start()
+    }
+
+    // Returns the merged text of the element, excluding descendants.
+    fn text(&self) -> Cow<'a, str> {
+        let xml11 = self.is_xml11();
+        let mut text = Cow::Borrowed("");
+        for node in self.node.children() {
+            let part = match node.kind() {
+                NodeKind::Text(s) => unescape(s.to_str(), CharsKind::CharData, xml11),
+                NodeKind::Cdata(s) => unescape(s.to_str(), CharsKind::CData, xml11),
+                _ => continue,
+            };
+
+            if text.is_empty() {
+                text = part;
+            } else {
+                text.to_mut().push_str(&part);
+            }
+        }
+
+        text
+    }
+
+    fn value(&self) -> Value<'a, 'd> {
+        Value {
+            text: self.text(),
+            doc: self.document(),
+            offset: self.offset(),
+        }
+    }
+
+    fn has_structure(&self) -> bool {
+        self.node.attributes().iter().any(|a| !is_namespace_declaration(a))
+            || self.node.children().any(|n| n.is_element())
+    }
+
+    fn element_name(&self, node: Node<'a, 'd>) -> Cow<'a, str> {
+        let (prefix, local) = node.tag_name().unwrap();
+        self.name(node, prefix, local)
+    }
+
+    // Unprefixed attributes are not in a namespace.
+    fn attribute_name(&self, attr: &Attribute<'a>) -> Cow<'a, str> {
+        if attr.prefix().is_empty() {
+            return Cow::Borrowed(attr.local().to_str());
+        }
+
+        self.name(self.node, attr.prefix(), attr.local())
+    }
+
+    fn name(&self, node: Node<'a, 'd>, prefix: StrSpan<'a>, local: StrSpan<'a>) -> Cow<'a, str> {
+        if self.expanded_names {
+            if let Some(uri) = resolve_namespace(node, prefix.to_str()) {
+                return Cow::Owned(format!("{{{}}}{}", uri, local));
+            }
+        }
+
+        Cow::Borrowed(local.to_str())
+    }
+
+    // Attributes are named with the `@` prefix when the struct has such a field.
+    fn entries(&self, fields: &[&str]) -> Vec<(Cow<'a, str>, Entry<'a, 'd>)> {
+        let mut entries = Vec::new();
+
+        for attr in self.node.attributes() {
+            if !is_namespace_declaration(attr) {
+                let mut name = self.attribute_name(attr);
+                if fields.iter().any(|f| f.strip_prefix('@') == Some(&*name)) {
+                    name = Cow::Owned(format!("@{}", name));
+                }
+
+                entries.push((name, Entry::Attribute(*attr)));
+            }
+        }
+
+        // Group child elements by name, preserving the order of the first appearance.
+        let attrs_count = entries.len();
+        for node in self.node.children().filter(|n| n.is_element()) {
+            let name = self.element_name(node);
+            let group = entries[attrs_count..].iter_mut().find(|e| e.0 == name);
+            match group {
+                Some(&mut (_, Entry::Elements(ref mut nodes))) => nodes.push(node),
+                _ => entries.push((name, Entry::Elements(vec![node]))),
+            }
+        }
+
+        if !self.text().bytes().all(|c| c.is_xml_space()) {
+            entries.push((Cow::Borrowed(TEXT_FIELD), Entry::Text));
+        }
+
+        entries
+    }
+}
+
+// Returns a non-empty namespace URI bound to the prefix.
+fn resolve_namespace<'a>(node: Node<'a, '_>, prefix: &str) -> Option<&'a str> {
+    if prefix == "xml" {
+        return Some(XML_NAMESPACE);
+    }
+
+    for node in iter::once(node).chain(node.ancestors()) {
+        for attr in node.attributes() {
+            let is_binding = if prefix.is_empty() {
+                attr.prefix().is_empty() && attr.local().to_str() == "xmlns"
+            } else {
+                attr.prefix().to_str() == "xmlns" && attr.local().to_str() == prefix
+            };
+
+            if is_binding {
+                let uri = attr.value().to_str();
+                return if uri.is_empty() { None } else { Some(uri) };
+            }
+        }
+    }
+
+    None
+}
+
+macro_rules! forward_to_value {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.value().$method(visitor)
+            }
+        )*
+    }
+}
+
+impl<'a, 'd> de::Deserializer<'a> for Deserializer<'a, 'd> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.has_structure() {
+            self.deserialize_map(visitor)
+        } else {
+            self.value().deserialize_any(visitor)
+        }
+    }
+
+    forward_to_value! {
+        deserialize_bool deserialize_char deserialize_str deserialize_string
+        deserialize_bytes deserialize_byte_buf deserialize_identifier
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_f32 deserialize_f64 deserialize_seq
+    }
+
+    fn deserialize_option<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'a>>(self, _: &'static str, visitor: V)
+        -> Result<V::Value, Error>
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'a>>(self, _: &'static str, visitor: V)
+        -> Result<V::Value, Error>
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: Visitor<'a>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'a>>(self, _: &'static str, _: usize, visitor: V)
+        -> Result<V::Value, Error>
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_struct("", &[], visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'a>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_map(ElementMap {
+            de: self,
+            entries: self.entries(fields).into_iter(),
+            value: None,
+        }).map_err(|e| e.at(self.document(), self.offset()))
+    }
+
+    fn deserialize_enum<V: Visitor<'a>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.node.children().find(|n| n.is_element()) {
+            Some(child) => {
+                visitor.visit_enum(ElementEnum { de: self, child })
+                    .map_err(|e| e.at(self.document(), self.offset()))
+            }
+            None => self.value().deserialize_enum(name, variants, visitor),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+}
+
+
+enum Entry<'a, 'd> {
+    Attribute(Attribute<'a>),
+    Elements(Vec<Node<'a, 'd>>),
+    Text,
+}
+
+struct ElementMap<'a, 'd> {
+    de: Deserializer<'a, 'd>,
+    entries: vec::IntoIter<(Cow<'a, str>, Entry<'a, 'd>)>,
+    value: Option<Entry<'a, 'd>>,
+}
+
+impl<'a, 'd> MapAccess<'a> for ElementMap<'a, 'd> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'a>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        let (name, entry) = match self.entries.next() {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        let offset = match entry {
+            Entry::Attribute(ref attr) => attr.local().start(),
+            Entry::Elements(ref nodes) => nodes[0].span().start(),
+            Entry::Text => self.de.offset(),
+        };
+
+        self.value = Some(entry);
+        seed.deserialize(Value { text: name, doc: self.de.document(), offset }).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'a>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let doc = self.de.document();
+        match self.value.take().expect("next_value_seed called before next_key_seed") {
+            Entry::Attribute(attr) => {
+                let value = attr.value();
+                let text = unescape(value.to_str(), CharsKind::AttributeValue, self.de.is_xml11());
+                seed.deserialize(Value { text, doc, offset: value.start() })
+            }
+            Entry::Elements(nodes) => {
+                let offset = nodes[0].span().start();
+                seed.deserialize(Elements { de: self.de, nodes })
+                    .map_err(|e| e.at(doc, offset))
+            }
+            Entry::Text => {
+                seed.deserialize(self.de.value())
+            }
+        }
+    }
+}
+
+
+struct ElementEnum<'a, 'd> {
+    de: Deserializer<'a, 'd>,
+    child: Node<'a, 'd>,
+}
+
+impl<'a, 'd> EnumAccess<'a> for ElementEnum<'a, 'd> {
+    type Error = Error;
+    type Variant = Deserializer<'a, 'd>;
+
+    fn variant_seed<V: DeserializeSeed<'a>>(self, seed: V) -> Result<(V::Value, Self::Variant), Error> {
+        let name = self.de.element_name(self.child);
+        let child = self.de.child(self.child);
+        let variant = seed.deserialize(Value { text: name, doc: child.document(), offset: child.offset() })?;
+        Ok((variant, child))
+    }
+}
+
+impl<'a, 'd> VariantAccess<'a> for Deserializer<'a, 'd> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'a>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'a>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'a>>(self, fields: &'static [&'static str], visitor: V)
+        -> Result<V::Value, Error>
+    {
+        de::Deserializer::deserialize_struct(self, "", fields, visitor)
+    }
+}
+
+
+// Child elements with the same name.
+struct Elements<'a, 'd> {
+    de: Deserializer<'a, 'd>,
+    nodes: Vec<Node<'a, 'd>>,
+}
+
+impl<'a, 'd> Elements<'a, 'd> {
+    fn single(self) -> Result<Deserializer<'a, 'd>, Error> {
+        if let Some(node) = self.nodes.get(1) {
+            let name = self.de.element_name(*node);
+            let e = Error::Custom(format!("duplicated element '{}'", name), None);
+            return Err(e.at(self.de.document(), node.span().start()));
+        }
+
+        Ok(self.de.child(self.nodes[0]))
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    }
+}
+
+impl<'a, 'd> de::Deserializer<'a> for Elements<'a, 'd> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.nodes.len() == 1 {
+            self.single()?.deserialize_any(visitor)
+        } else {
+            self.deserialize_seq(visitor)
+        }
+    }
+
+    forward_to_single! {
+        deserialize_bool deserialize_char deserialize_str deserialize_string
+        deserialize_bytes deserialize_byte_buf deserialize_identifier
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_f32 deserialize_f64 deserialize_unit deserialize_map
+    }
+
+    fn deserialize_option<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'a>>(self, name: &'static str, visitor: V)
+        -> Result<V::Value, Error>
+    {
+        self.single()?.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'a>>(self, _: &'static str, visitor: V)
+        -> Result<V::Value, Error>
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        let de = self.de;
+        visitor.visit_seq(ElementsSeq {
+            de,
+            nodes: self.nodes.into_iter(),
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'a>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'a>>(self, _: &'static str, _: usize, visitor: V)
+        -> Result<V::Value, Error>
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'a>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.single()?.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'a>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+}
+
+struct ElementsSeq<'a, 'd> {
+    de: Deserializer<'a, 'd>,
+    nodes: vec::IntoIter<Node<'a, 'd>>,
+}
+
+impl<'a, 'd> SeqAccess<'a> for ElementsSeq<'a, 'd> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'a>>(&mut self, seed: T) -> Result<Option<T::Value>, Error> {
+        match self.nodes.next() {
+            Some(node) => {
+                let de = self.de.child(node);
+                seed.deserialize(de).map(Some).map_err(|e| e.at(de.document(), de.offset()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.nodes.len())
+    }
+}
+
+
+// An attribute value, an element text or a name.
+struct Value<'a, 'd> {
+    text: Cow<'a, str>,
+    doc: &'d Document<'a>,
+    offset: usize,
+}
+
+impl<'a, 'd> Value<'a, 'd> {
+    fn error(&self, e: Error) -> Error {
+        e.at(self.doc, self.offset)
+    }
+
+    fn invalid_value<V: Visitor<'a>>(&self, visitor: &V) -> Error {
+        self.error(de::Error::invalid_value(Unexpected::Str(&self.text), visitor))
+    }
+}
+
+macro_rules! deserialize_number {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.text.trim().parse() {
+                    Ok(n) => visitor.$visit(n).map_err(|e| self.error(e)),
+                    Err(_) => Err(self.invalid_value(&visitor)),
+                }
+            }
+        )*
+    }
+}
+
+impl<'a, 'd> de::Deserializer<'a> for Value<'a, 'd> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        let (doc, offset) = (self.doc, self.offset);
+        match self.text {
+            Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+            Cow::Owned(s) => visitor.visit_string(s),
+        }.map_err(|e: Error| e.at(doc, offset))
+    }
+
+    fn deserialize_bool<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        // https://www.w3.org/TR/xmlschema-2/#boolean
+        match self.text.trim() {
+            "true" | "1" => visitor.visit_bool(true),
+            "false" | "0" => visitor.visit_bool(false),
+            _ => Err(self.invalid_value(&visitor)),
+        }
+    }
+
+    deserialize_number! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_char<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        let mut chars = self.text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(self.invalid_value(&visitor)),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        let (doc, offset) = (self.doc, self.offset);
+        match self.text {
+            Cow::Borrowed(s) => visitor.visit_borrowed_bytes(s.as_bytes()),
+            Cow::Owned(s) => visitor.visit_byte_buf(s.into_bytes()),
+        }.map_err(|e: Error| e.at(doc, offset))
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'a>>(self, _: &'static str, visitor: V)
+        -> Result<V::Value, Error>
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'a>>(self, _: &'static str, visitor: V)
+        -> Result<V::Value, Error>
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // Whitespace-separated values, like `xs:list`.
+    fn deserialize_seq<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        let items: Vec<Cow<'a, str>> = match self.text {
+            Cow::Borrowed(s) => s.split_whitespace().map(Cow::Borrowed).collect(),
+            Cow::Owned(ref s) => s.split_whitespace().map(|s| Cow::Owned(s.to_string())).collect(),
+        };
+
+        visitor.visit_seq(ValueSeq {
+            items: items.into_iter(),
+            doc: self.doc,
+            offset: self.offset,
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'a>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'a>>(self, _: &'static str, _: usize, visitor: V)
+        -> Result<V::Value, Error>
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        Err(self.error(de::Error::invalid_type(Unexpected::Str(&self.text), &visitor)))
+    }
+
+    fn deserialize_struct<V: Visitor<'a>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'a>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let (doc, offset) = (self.doc, self.offset);
+        visitor.visit_enum(self).map_err(|e| e.at(doc, offset))
+    }
+
+    fn deserialize_identifier<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+}
+
+impl<'a, 'd> EnumAccess<'a> for Value<'a, 'd> {
+    type Error = Error;
+    type Variant = UnitVariant;
+
+    fn variant_seed<V: DeserializeSeed<'a>>(self, seed: V) -> Result<(V::Value, UnitVariant), Error> {
+        seed.deserialize(self).map(|v| (v, UnitVariant))
+    }
+}
+
+// Text can be mapped only to unit variants.
+struct UnitVariant;
+
+impl<'a> VariantAccess<'a> for UnitVariant {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'a>>(self, _: T) -> Result<T::Value, Error> {
+        Err(de::Error::invalid_type(Unexpected::UnitVariant, &"newtype variant"))
+    }
+
+    fn tuple_variant<V: Visitor<'a>>(self, _: usize, _: V) -> Result<V::Value, Error> {
+        Err(de::Error::invalid_type(Unexpected::UnitVariant, &"tuple variant"))
+    }
+
+    fn struct_variant<V: Visitor<'a>>(self, _: &'static [&'static str], _: V)
+        -> Result<V::Value, Error>
+    {
+        Err(de::Error::invalid_type(Unexpected::UnitVariant, &"struct variant"))
+    }
+}
+
+struct ValueSeq<'a, 'd> {
+    items: vec::IntoIter<Cow<'a, str>>,
+    doc: &'d Document<'a>,
+    offset: usize,
+}
+
+impl<'a, 'd> SeqAccess<'a> for ValueSeq<'a, 'd> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'a>>(&mut self, seed: T) -> Result<Option<T::Value>, Error> {
+        match self.items.next() {
+            Some(text) => {
+                seed.deserialize(Value { text, doc: self.doc, offset: self.offset }).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
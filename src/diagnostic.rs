@@ -0,0 +1,335 @@
+use std::cmp;
+use std::fmt;
+
+use {
+    Error,
+    LineIndex,
+    StreamError,
+    TokenType,
+    ValidityError,
+};
+
+
+/// A human-readable error renderer.
+///
+/// Prints the error message, the offending line with the error span underlined,
+/// a few lines of context around it and an explanation of the error.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{Diagnostic, Tokenizer};
+///
+/// let text = "<svg>\n  <rect x=10/>\n</svg>";
+/// let err = Tokenizer::from(text).filter_map(|t| t.err()).next().unwrap();
+/// let diag = Diagnostic::new(&err, text).to_string();
+/// assert_eq!(diag, "\
+/// error[XML-E006]: invalid token 'Attribute' at 2:8 cause expected quote mark not '1' at 2:11
+///  --> 2:8
+///   |
+/// 1 | <svg>
+/// 2 |   <rect x=10/>
+///   |        ^^^^ attribute values must be enclosed in single or double quotes
+/// 3 | </svg>
+///   |
+///   = see: https://www.w3.org/TR/xml/#NT-Attribute
+/// ");
+/// ```
+pub struct Diagnostic<'a> {
+    error: &'a Error,
+    text: &'a str,
+    context: usize,
+    colors: bool,
+    xml11: bool,
+    cr_line_breaks: bool,
+}
+
+impl<'a> Diagnostic<'a> {
+    /// Creates a new renderer.
+    ///
+    /// `text` must be the same text that was used to produce the `error`.
+    ///
+    /// By default, the output has no colors and two lines of context.
+    pub fn new(error: &'a Error, text: &'a str) -> Self {
+        Diagnostic {
+            error,
+            text,
+            context: 2,
+            colors: false,
+            xml11: false,
+            cr_line_breaks: false,
+        }
+    }
+
+    /// Enables ANSI terminal colors.
+    pub fn enable_colors(&mut self) {
+        self.colors = true;
+    }
+
+    /// Sets the amount of lines to print before and after the offending line.
+    pub fn set_context_lines(&mut self, n: usize) {
+        self.context = n;
+    }
+
+    /// Treats NEL and LINE SEPARATOR as line breaks.
+    ///
+    /// Must be enabled when the `error` was produced in the XML 1.1 mode,
+    /// so the printed lines will match the error position.
+    pub fn enable_xml11_mode(&mut self) {
+        self.xml11 = true;
+    }
+
+    /// Treats a lone `\r` as a line break.
+    ///
+    /// Must be enabled when the `error` was produced in the CR line breaks mode,
+    /// so the printed lines will match the error position.
+    pub fn enable_cr_line_breaks_mode(&mut self) {
+        self.cr_line_breaks = true;
+    }
+
+    fn paint(&self, f: &mut fmt::Formatter, style: &str, text: &str) -> fmt::Result {
+        if self.colors {
+            write!(f, "\x1b[{}m{}\x1b[0m", style, text)
+        } else {
+            write!(f, "{}", text)
+        }
+    }
+}
+
+const STYLE_ERROR: &str = "1;31";
+const STYLE_GUTTER: &str = "1;34";
+const STYLE_BOLD: &str = "1";
+
+impl<'a> fmt::Display for Diagnostic<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = self.text;
+        let range = self.error.range();
+        let start = cmp::min(range.start, text.len());
+        let end = cmp::max(start, cmp::min(range.end, text.len()));
+
+        let lines = line_offsets(text, self.xml11, self.cr_line_breaks);
+
+        // Underline the line with the character that caused the error.
+        let last = if end > start { end - 1 } else { end };
+        let idx = lines.iter().rposition(|&(offset, _)| offset <= last).unwrap_or(0);
+        let (line_offset, line) = lines.get(idx).cloned().unwrap_or((0, ""));
+
+        let first = idx.saturating_sub(self.context);
+        let last = cmp::min(idx + self.context, lines.len().saturating_sub(1));
+        let gutter = (last + 1).to_string().len();
+        let pad = " ".repeat(gutter);
+
+        self.paint(f, STYLE_ERROR, &format!("error[{}]", self.error.code()))?;
+        self.paint(f, STYLE_BOLD, &format!(": {}", self.error))?;
+        writeln!(f)?;
+
+        self.paint(f, STYLE_GUTTER, &format!("{}--> ", pad))?;
+        writeln!(f, "{}", self.error.pos())?;
+        self.paint(f, STYLE_GUTTER, &format!("{} |", pad))?;
+        writeln!(f)?;
+
+        for (i, &(_, l)) in lines.iter().enumerate().take(last + 1).skip(first) {
+            self.paint(f, STYLE_GUTTER, &format!("{:>w$} |", i + 1, w = gutter))?;
+            if l.is_empty() {
+                writeln!(f)?;
+            } else {
+                writeln!(f, " {}", l)?;
+            }
+
+            if i == idx {
+                // A range can start at a line break or inside a character.
+                let from = floor_char_boundary(line, cmp::max(start, line_offset) - line_offset);
+                let to = floor_char_boundary(line, end - line_offset);
+                let to = cmp::max(from, to);
+
+                // Keep tabs, so the underline will be aligned with the text.
+                let prefix: String = line[..from].chars()
+                    .map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+                let marks = "^".repeat(cmp::max(1, line[from..to].chars().count()));
+
+                self.paint(f, STYLE_GUTTER, &format!("{} |", pad))?;
+                write!(f, " {}", prefix)?;
+                self.paint(f, STYLE_ERROR, &format!("{} {}", marks, explain(self.error)))?;
+                writeln!(f)?;
+            }
+        }
+
+        self.paint(f, STYLE_GUTTER, &format!("{} |", pad))?;
+        writeln!(f)?;
+        self.paint(f, STYLE_GUTTER, &format!("{} = ", pad))?;
+        writeln!(f, "see: {}", self.error.spec_url())
+    }
+}
+
+// Returns the largest char boundary that is less than or equal to `idx`.
+fn floor_char_boundary(text: &str, idx: usize) -> usize {
+    let mut idx = cmp::min(idx, text.len());
+    while !text.is_char_boundary(idx) {
+        idx -= 1;
+    }
+
+    idx
+}
+
+// Returns lines with their byte offsets. Line breaks are not included.
+//
+// Lines are split just like in the tokenizer with the same modes,
+// so the line numbers will match the error position.
+fn line_offsets(text: &str, xml11: bool, cr_line_breaks: bool) -> Vec<(usize, &str)> {
+    let index = LineIndex::with_modes(text, xml11, cr_line_breaks);
+    (0..index.lines_count()).map(|i| {
+        let start = index.line_start(i as u32 + 1).unwrap_or(0);
+        let end = index.line_start(i as u32 + 2).unwrap_or(text.len());
+        (start, trim_line_break(&text[start..end]))
+    }).collect()
+}
+
+fn trim_line_break(line: &str) -> &str {
+    let mut chars = line.chars();
+    match chars.next_back() {
+        Some('\n') | Some('\u{85}') => chars.as_str().trim_end_matches('\r'),
+        Some('\r') | Some('\u{2028}') => chars.as_str(),
+        _ => line,
+    }
+}
+
+fn explain(error: &Error) -> String {
+    match *error {
+        Error::InvalidToken(_, _, Some(ref cause), _) => explain_cause(cause),
+        Error::InvalidToken(token_type, _, None, _) => {
+            match token_type {
+                TokenType::Comment => {
+                    "comments must not contain '--' and must end with '-->'".to_string()
+                }
+                _ => format!("this {} is malformed", token_type),
+            }
+        }
+        Error::UnexpectedToken(token_type, _, _) => {
+            match token_type {
+                TokenType::XMLDecl => {
+                    "the XML declaration is allowed only at the very start of the document".to_string()
+                }
+                TokenType::ElementStart => {
+                    "a document must have exactly one root element".to_string()
+                }
+                TokenType::ElementClose => {
+                    "this close tag doesn't have a matching open tag".to_string()
+                }
+                TokenType::DoctypeDecl => {
+                    "DOCTYPE is allowed only once and only before the root element".to_string()
+                }
+                _ => format!("{} is not allowed here", token_type),
+            }
+        }
+        Error::UnknownToken(..) => {
+            "this is not a valid markup; text is allowed only inside elements".to_string()
+        }
+        Error::UnexpectedCloseTag(ref expected, _, _, _) => {
+            format!("expected '</{}>' here", expected)
+        }
+        Error::UnclosedTag(ref name, _, _) => {
+            format!("element '{}' is never closed", name)
+        }
+        Error::InvalidDocument(ref cause, _, _) => explain_validity(cause),
+    }
+}
+
+fn explain_validity(cause: &ValidityError) -> String {
+    match *cause {
+        ValidityError::NoDtd => {
+            "a valid document must have a DOCTYPE with element declarations".to_string()
+        }
+        ValidityError::RootElementMismatch(ref expected, _) => {
+            format!("the DOCTYPE declares '{}' as the root element", expected)
+        }
+        ValidityError::DuplicateElementDecl(..) => {
+            "an element type must not be declared more than once".to_string()
+        }
+        ValidityError::UndeclaredElement(ref name) => {
+            format!("add '<!ELEMENT {} ...>' to the DTD", name)
+        }
+        ValidityError::UnexpectedElement(ref parent, _) => {
+            format!("this element doesn't match the '{}' content model", parent)
+        }
+        ValidityError::UnexpectedText(ref name) => {
+            format!("'{}' can contain only elements and whitespaces", name)
+        }
+        ValidityError::NotEmpty(..) => {
+            "elements declared as EMPTY must not have any content".to_string()
+        }
+        ValidityError::IncompleteContent(ref name) => {
+            format!("'{}' is missing required child elements", name)
+        }
+        ValidityError::UndeclaredAttribute(ref element, ref name) => {
+            format!("add '<!ATTLIST {} {} ...>' to the DTD", element, name)
+        }
+        ValidityError::MissingAttribute(..) => {
+            "this element must have all the #REQUIRED attributes".to_string()
+        }
+        ValidityError::InvalidAttributeValue(_, ref expected) => {
+            format!("expected {}", expected)
+        }
+        ValidityError::FixedAttributeValue(..) => {
+            "the attribute is declared as #FIXED and cannot have a different value".to_string()
+        }
+        ValidityError::DuplicateId(..) => {
+            "ID values must be unique in the document".to_string()
+        }
+        ValidityError::UnknownId(..) => {
+            "IDREF values must match an ID in the document".to_string()
+        }
+        ValidityError::InvalidElementValue(_, ref expected) => {
+            format!("expected {}", expected)
+        }
+    }
+}
+
+fn explain_cause(cause: &StreamError) -> String {
+    match *cause {
+        StreamError::UnexpectedEndOfStream => {
+            "the document ended unexpectedly".to_string()
+        }
+        StreamError::InvalidName => {
+            "expected a name, which must start with a letter, '_' or ':'".to_string()
+        }
+        StreamError::InvalidAttributeValue => {
+            "attribute values must not contain '<', use '&lt;' instead".to_string()
+        }
+        StreamError::InvalidChar(actual, expected, _) => {
+            format!("expected '{}', found '{}'", expected as char, actual)
+        }
+        StreamError::InvalidCharMultiple(actual, ref expected, _) => {
+            let list: Vec<String> = expected.iter().map(|c| format!("'{}'", *c as char)).collect();
+            format!("expected one of {}, found '{}'", list.join(", "), actual)
+        }
+        StreamError::InvalidQuote(..) => {
+            "attribute values must be enclosed in single or double quotes".to_string()
+        }
+        StreamError::InvalidSpace(..) => {
+            "expected a whitespace".to_string()
+        }
+        StreamError::InvalidString(ref actual, ref expected, _) => {
+            let list: Vec<String> = expected.iter().map(|s| format!("'{}'", s)).collect();
+            if list.len() == 1 {
+                format!("expected {}, found '{}'", list[0], actual)
+            } else {
+                format!("expected one of {}, found '{}'", list.join(", "), actual)
+            }
+        }
+        StreamError::InvalidReference => {
+            "invalid reference, use '&amp;' to write a literal '&'".to_string()
+        }
+        StreamError::InvalidExternalID => {
+            "expected an external ID, which must start with 'SYSTEM' or 'PUBLIC'".to_string()
+        }
+        StreamError::InvalidCharacter(c, _) => {
+            format!("character {:?} is not allowed here", c)
+        }
+        StreamError::ReservedName(ref name, _) => {
+            format!("'{}' is reserved by the XML specification", name)
+        }
+        StreamError::InvalidValue(name, _) => {
+            format!("this is not a valid xs:{} value", name)
+        }
+    }
+}
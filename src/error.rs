@@ -1,39 +1,170 @@
 use std::fmt;
 use std::error;
+use std::ops::Range;
 
 use {
     TokenType,
+    ValidityError,
 };
 
 
 /// An XML parser errors.
+///
+/// All errors contain a position (row:column) and an absolute byte range
+/// in the original text.
 #[derive(Debug)]
 pub enum Error {
     /// An invalid token with an optional cause.
-    InvalidToken(TokenType, TextPos, Option<StreamError>),
+    InvalidToken(TokenType, TextPos, Option<Box<StreamError>>, Range<usize>),
 
     /// An unexpected token.
-    UnexpectedToken(TokenType, TextPos),
+    UnexpectedToken(TokenType, TextPos, Range<usize>),
 
     /// An unknown token.
-    UnknownToken(TextPos),
+    UnknownToken(TextPos, Range<usize>),
+
+    /// A close tag that doesn't match the currently open element.
+    ///
+    /// Contains the expected and the actual qualified names.
+    ///
+    /// Tokenizer doesn't check the tree structure, so this error can be
+    /// produced only by higher-level APIs.
+    UnexpectedCloseTag(Box<str>, Box<str>, TextPos, Range<usize>),
+
+    /// An element that was not closed before the end of the document.
+    ///
+    /// Contains the qualified name of the element and its start position.
+    UnclosedTag(Box<str>, TextPos, Range<usize>),
+
+    /// A validity constraint violation.
+    ///
+    /// Produced by [`Tokenizer::validate`] and by the `schema` module validator.
+    ///
+    /// [`Tokenizer::validate`]: struct.Tokenizer.html#method.validate
+    InvalidDocument(Box<ValidityError>, TextPos, Range<usize>),
 }
 
 impl Error {
     /// Returns the error position.
     pub fn pos(&self) -> TextPos {
         match *self {
-            Error::InvalidToken(_, pos, _) => pos,
-            Error::UnexpectedToken(_, pos) => pos,
-            Error::UnknownToken(pos) => pos,
+            Error::InvalidToken(_, pos, _, _) => pos,
+            Error::UnexpectedToken(_, pos, _) => pos,
+            Error::UnknownToken(pos, _) => pos,
+            Error::UnexpectedCloseTag(_, _, pos, _) => pos,
+            Error::UnclosedTag(_, pos, _) => pos,
+            Error::InvalidDocument(_, pos, _) => pos,
+        }
+    }
+
+    /// Returns an absolute byte range in the original text covered by the error.
+    ///
+    /// Starts at the beginning of the invalid token and ends after
+    /// the character that caused the error.
+    /// Can be empty when the error was caused by the end of the text.
+    pub fn range(&self) -> Range<usize> {
+        match *self {
+            Error::InvalidToken(_, _, _, ref range) => range.clone(),
+            Error::UnexpectedToken(_, _, ref range) => range.clone(),
+            Error::UnknownToken(_, ref range) => range.clone(),
+            Error::UnexpectedCloseTag(_, _, _, ref range) => range.clone(),
+            Error::UnclosedTag(_, _, ref range) => range.clone(),
+            Error::InvalidDocument(_, _, ref range) => range.clone(),
+        }
+    }
+
+    /// Returns the token type that caused the error, if known.
+    pub fn token_type(&self) -> Option<TokenType> {
+        match *self {
+            Error::InvalidToken(token_type, _, _, _) => Some(token_type),
+            Error::UnexpectedToken(token_type, _, _) => Some(token_type),
+            Error::UnknownToken(..) => None,
+            Error::UnexpectedCloseTag(..) => Some(TokenType::ElementClose),
+            Error::UnclosedTag(..) => Some(TokenType::ElementStart),
+            Error::InvalidDocument(..) => None,
+        }
+    }
+
+    /// Returns the underlying stream error, if any.
+    pub fn cause(&self) -> Option<&StreamError> {
+        match *self {
+            Error::InvalidToken(_, _, Some(ref cause), _) => Some(cause),
+            _ => None,
+        }
+    }
+
+    /// Returns a stable, machine-readable error code.
+    ///
+    /// When an error has a cause, the code of the cause is returned,
+    /// since it's more specific.
+    ///
+    /// | Code       | Error                      |
+    /// |------------|----------------------------|
+    /// | `XML-E001` | `UnexpectedEndOfStream`    |
+    /// | `XML-E002` | `InvalidName`              |
+    /// | `XML-E003` | `InvalidAttributeValue`    |
+    /// | `XML-E004` | `InvalidChar`              |
+    /// | `XML-E005` | `InvalidCharMultiple`      |
+    /// | `XML-E006` | `InvalidQuote`             |
+    /// | `XML-E007` | `InvalidSpace`             |
+    /// | `XML-E008` | `InvalidString`            |
+    /// | `XML-E009` | `InvalidReference`         |
+    /// | `XML-E010` | `InvalidExternalID`        |
+    /// | `XML-E011` | `InvalidToken` w/o a cause |
+    /// | `XML-E012` | `UnexpectedToken`          |
+    /// | `XML-E013` | `UnknownToken`             |
+    /// | `XML-E014` | `UnexpectedCloseTag`       |
+    /// | `XML-E015` | `UnclosedTag`              |
+    /// | `XML-E016` | `InvalidCharacter`         |
+    /// | `XML-E017` | `ReservedName`             |
+    /// | `XML-E018` | `InvalidValue`             |
+    ///
+    /// Validity errors have their own `XML-V` codes.
+    /// See [`ValidityError::code`].
+    ///
+    /// Codes are never reused or changed, only appended.
+    ///
+    /// [`ValidityError::code`]: enum.ValidityError.html#method.code
+    pub fn code(&self) -> &'static str {
+        match *self {
+            Error::InvalidToken(_, _, Some(ref cause), _) => cause.code(),
+            Error::InvalidToken(_, _, None, _) => "XML-E011",
+            Error::UnexpectedToken(..) => "XML-E012",
+            Error::UnknownToken(..) => "XML-E013",
+            Error::UnexpectedCloseTag(..) => "XML-E014",
+            Error::UnclosedTag(..) => "XML-E015",
+            Error::InvalidDocument(ref cause, _, _) => cause.code(),
+        }
+    }
+
+    /// Returns a link to the relevant production or constraint
+    /// of the [XML 1.0](https://www.w3.org/TR/xml/) specification.
+    pub fn spec_url(&self) -> &'static str {
+        match *self {
+            Error::InvalidToken(_, _, Some(ref cause), _) => {
+                match **cause {
+                    StreamError::InvalidName => "https://www.w3.org/TR/xml/#NT-Name",
+                    StreamError::InvalidAttributeValue => "https://www.w3.org/TR/xml/#CleanAttrVals",
+                    StreamError::InvalidReference => "https://www.w3.org/TR/xml/#NT-Reference",
+                    StreamError::InvalidExternalID => "https://www.w3.org/TR/xml/#NT-ExternalID",
+                    StreamError::ReservedName(..) => "https://www.w3.org/TR/xml/#NT-PITarget",
+                    _ => self.token_type().map(|t| t.spec_url()).unwrap_or(DOCUMENT_URL),
+                }
+            }
+            Error::UnexpectedCloseTag(..) => "https://www.w3.org/TR/xml/#GIMatch",
+            Error::UnclosedTag(..) => "https://www.w3.org/TR/xml/#NT-element",
+            Error::InvalidDocument(ref cause, _, _) => cause.spec_url(),
+            _ => self.token_type().map(|t| t.spec_url()).unwrap_or(DOCUMENT_URL),
         }
     }
 }
 
+const DOCUMENT_URL: &str = "https://www.w3.org/TR/xml/#NT-document";
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::InvalidToken(token_type, pos, ref cause) => {
+            Error::InvalidToken(token_type, pos, ref cause, _) => {
                 match *cause {
                     Some(ref cause) => {
                         write!(f, "invalid token '{}' at {} cause {}", token_type, pos, cause)
@@ -43,12 +174,21 @@ impl fmt::Display for Error {
                     }
                 }
             }
-            Error::UnexpectedToken(token_type, pos) => {
+            Error::UnexpectedToken(token_type, pos, _) => {
                 write!(f, "unexpected token '{}' at {}", token_type, pos)
             }
-            Error::UnknownToken(pos) => {
+            Error::UnknownToken(pos, _) => {
                 write!(f, "unknown token at {}", pos)
             }
+            Error::UnexpectedCloseTag(ref expected, ref actual, pos, _) => {
+                write!(f, "expected '{}' close tag not '{}' at {}", expected, actual, pos)
+            }
+            Error::UnclosedTag(ref name, pos, _) => {
+                write!(f, "unclosed tag '{}' at {}", name, pos)
+            }
+            Error::InvalidDocument(ref cause, pos, _) => {
+                write!(f, "{} at {}", cause, pos)
+            }
         }
     }
 }
@@ -77,10 +217,13 @@ pub enum StreamError {
 
     /// An invalid/unexpected character.
     ///
-    /// The first byte is an actual one, others - expected.
+    /// Contains the actual character and the expected byte.
+    InvalidChar(char, u8, TextPos),
+
+    /// An invalid/unexpected character.
     ///
-    /// We are using a single value to reduce the struct size.
-    InvalidChar(Vec<u8>, TextPos),
+    /// Contains the actual character and a list of the expected bytes.
+    InvalidCharMultiple(char, Vec<u8>, TextPos),
 
     /// An unexpected character instead of `"` or `'`.
     InvalidQuote(char, TextPos),
@@ -90,18 +233,69 @@ pub enum StreamError {
     /// Includes: `' ' \n \r \t &#x20; &#x9; &#xD; &#xA;`.
     InvalidSpace(char, TextPos),
 
-    /// An unexpected character instead of an XML space.
-    ///
-    /// The first string is an actual one, others - expected.
+    /// An unexpected string.
     ///
-    /// We are using a single value to reduce the struct size.
-    InvalidString(Vec<String>, TextPos),
+    /// Contains the actual string and a list of the expected ones.
+    InvalidString(String, Vec<String>, TextPos),
 
     /// An invalid reference.
     InvalidReference,
 
     /// An invalid ExternalID in the DTD.
     InvalidExternalID,
+
+    /// A character that is not allowed at this position.
+    InvalidCharacter(char, TextPos),
+
+    /// A reserved name.
+    ///
+    /// Like the `xml` processing instruction target in any case.
+    ReservedName(String, TextPos),
+
+    /// An invalid XML Schema datatype value.
+    ///
+    /// Contains the datatype name, like `integer`.
+    InvalidValue(&'static str, TextPos),
+}
+
+impl StreamError {
+    /// Returns a stable, machine-readable error code.
+    ///
+    /// See [`Error::code`] for the full list.
+    ///
+    /// [`Error::code`]: enum.Error.html#method.code
+    pub fn code(&self) -> &'static str {
+        match *self {
+            StreamError::UnexpectedEndOfStream => "XML-E001",
+            StreamError::InvalidName => "XML-E002",
+            StreamError::InvalidAttributeValue => "XML-E003",
+            StreamError::InvalidChar(..) => "XML-E004",
+            StreamError::InvalidCharMultiple(..) => "XML-E005",
+            StreamError::InvalidQuote(..) => "XML-E006",
+            StreamError::InvalidSpace(..) => "XML-E007",
+            StreamError::InvalidString(..) => "XML-E008",
+            StreamError::InvalidReference => "XML-E009",
+            StreamError::InvalidExternalID => "XML-E010",
+            StreamError::InvalidCharacter(..) => "XML-E016",
+            StreamError::ReservedName(..) => "XML-E017",
+            StreamError::InvalidValue(..) => "XML-E018",
+        }
+    }
+
+    /// Returns the error position, if known.
+    pub fn pos(&self) -> Option<TextPos> {
+        match *self {
+            StreamError::InvalidChar(_, _, pos)
+            | StreamError::InvalidCharMultiple(_, _, pos)
+            | StreamError::InvalidQuote(_, pos)
+            | StreamError::InvalidSpace(_, pos)
+            | StreamError::InvalidString(_, _, pos)
+            | StreamError::InvalidCharacter(_, pos)
+            | StreamError::ReservedName(_, pos)
+            | StreamError::InvalidValue(_, pos) => Some(pos),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for StreamError {
@@ -116,13 +310,14 @@ impl fmt::Display for StreamError {
             StreamError::InvalidAttributeValue => {
                 write!(f, "attribute value with '<' character is not allowed")
             }
-            StreamError::InvalidChar(ref chars, pos) => {
-                // Vec<u8> -> Vec<String>
-                let list: Vec<String> =
-                    chars.iter().skip(1).map(|c| String::from_utf8(vec![*c]).unwrap()).collect();
-
+            StreamError::InvalidChar(actual, expected, pos) => {
+                write!(f, "expected '{}' not '{}' at {}",
+                       expected as char, actual, pos)
+            }
+            StreamError::InvalidCharMultiple(actual, ref expected, pos) => {
+                let list: Vec<String> = expected.iter().map(|c| (*c as char).to_string()).collect();
                 write!(f, "expected '{}' not '{}' at {}",
-                       list.join("', '"), chars[0] as char, pos)
+                       list.join("', '"), actual, pos)
             }
             StreamError::InvalidQuote(c, pos) => {
                 write!(f, "expected quote mark not '{}' at {}", c, pos)
@@ -130,9 +325,9 @@ impl fmt::Display for StreamError {
             StreamError::InvalidSpace(c, pos) => {
                 write!(f, "expected space not '{}' at {}", c, pos)
             }
-            StreamError::InvalidString(ref strings, pos) => {
+            StreamError::InvalidString(ref actual, ref expected, pos) => {
                 write!(f, "expected '{}' not '{}' at {}",
-                       strings[1..].join("', '"), strings[0], pos)
+                       expected.join("', '"), actual, pos)
             }
             StreamError::InvalidReference => {
                 write!(f, "invalid reference")
@@ -140,6 +335,15 @@ impl fmt::Display for StreamError {
             StreamError::InvalidExternalID => {
                 write!(f, "invalid ExternalID")
             }
+            StreamError::InvalidCharacter(c, pos) => {
+                write!(f, "character {:?} is not allowed at {}", c, pos)
+            }
+            StreamError::ReservedName(ref name, pos) => {
+                write!(f, "name '{}' is reserved at {}", name, pos)
+            }
+            StreamError::InvalidValue(name, pos) => {
+                write!(f, "invalid xs:{} value at {}", name, pos)
+            }
         }
     }
 }
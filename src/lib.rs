@@ -62,16 +62,56 @@ If you are looking for a more high-level solution - checkout
 #![warn(missing_docs)]
 
 
+#[cfg(feature = "memchr")]
+extern crate memchr;
+#[cfg(feature = "serde")]
+extern crate serde;
+
+use std::cmp;
+use std::collections::VecDeque;
 use std::fmt;
+use std::mem;
+use std::ops::Range;
 
+mod automaton;
+mod checkpoint;
+mod datatypes;
+mod diagnostic;
 mod error;
+mod lineindex;
+mod parallel;
+mod path;
+mod peek;
+mod sax;
+mod scan;
+mod selector;
 mod stream;
 mod strspan;
+mod subtree;
+mod text;
+mod validate;
 mod xmlchar;
-
+#[cfg(feature = "tree")]
+pub mod tree;
+#[cfg(feature = "serde")]
+pub mod de;
+#[cfg(feature = "serde")]
+pub mod ser;
+#[cfg(feature = "schema")]
+pub mod schema;
+
+pub use checkpoint::*;
+pub use datatypes::*;
+pub use diagnostic::*;
 pub use error::*;
+pub use lineindex::*;
+pub use path::*;
+pub use sax::*;
+pub use selector::*;
 pub use stream::*;
 pub use strspan::*;
+pub use text::*;
+pub use validate::*;
 pub use xmlchar::*;
 
 
@@ -129,11 +169,11 @@ pub enum Token<'a> {
     /// Contains text between elements including whitespaces.
     /// Basically everything between `>` and `<`.
     ///
-    /// Contains text as is. Use [`TextUnescape`] to unescape it.
+    /// Contains text as is. Use [`Tokenizer::merge_text`] to unescape it.
     ///
     /// Example: `<text>text</text>`
     ///
-    /// [`TextUnescape`]: struct.TextUnescape.html
+    /// [`Tokenizer::merge_text`]: struct.Tokenizer.html#method.merge_text
     Text(StrSpan<'a>),
     /// Whitespaces token.
     ///
@@ -147,6 +187,28 @@ pub enum Token<'a> {
     Cdata(StrSpan<'a>),
 }
 
+impl<'a> Token<'a> {
+    /// Returns the token type.
+    ///
+    /// All `ElementEnd` tokens are `ElementClose`.
+    pub fn token_type(&self) -> TokenType {
+        match *self {
+            Token::Declaration(..) => TokenType::XMLDecl,
+            Token::ProcessingInstruction(..) => TokenType::PI,
+            Token::Comment(..) => TokenType::Comment,
+            Token::DtdStart(..) | Token::EmptyDtd(..) => TokenType::DoctypeDecl,
+            Token::EntityDeclaration(..) => TokenType::EntityDecl,
+            Token::DtdEnd => TokenType::DoctypeEnd,
+            Token::ElementStart(..) => TokenType::ElementStart,
+            Token::Attribute(..) => TokenType::Attribute,
+            Token::ElementEnd(..) => TokenType::ElementClose,
+            Token::Text(..) => TokenType::CharData,
+            Token::Whitespaces(..) => TokenType::Whitespace,
+            Token::Cdata(..) => TokenType::CDSect,
+        }
+    }
+}
+
 
 /// `ElementEnd` token.
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -231,8 +293,33 @@ impl fmt::Display for TokenType {
     }
 }
 
+impl TokenType {
+    /// Returns a link to the corresponding production of
+    /// the [XML 1.0](https://www.w3.org/TR/xml/) specification.
+    pub fn spec_url(&self) -> &'static str {
+        match *self {
+            TokenType::XMLDecl => "https://www.w3.org/TR/xml/#NT-XMLDecl",
+            TokenType::Comment => "https://www.w3.org/TR/xml/#NT-Comment",
+            TokenType::PI => "https://www.w3.org/TR/xml/#NT-PI",
+            TokenType::DoctypeDecl => "https://www.w3.org/TR/xml/#NT-doctypedecl",
+            TokenType::ElementDecl => "https://www.w3.org/TR/xml/#NT-elementdecl",
+            TokenType::AttlistDecl => "https://www.w3.org/TR/xml/#NT-AttlistDecl",
+            TokenType::EntityDecl => "https://www.w3.org/TR/xml/#NT-EntityDecl",
+            TokenType::NotationDecl => "https://www.w3.org/TR/xml/#NT-NotationDecl",
+            TokenType::DoctypeEnd => "https://www.w3.org/TR/xml/#NT-doctypedecl",
+            TokenType::ElementStart => "https://www.w3.org/TR/xml/#NT-STag",
+            TokenType::ElementClose => "https://www.w3.org/TR/xml/#NT-ETag",
+            TokenType::Attribute => "https://www.w3.org/TR/xml/#NT-Attribute",
+            TokenType::CDSect => "https://www.w3.org/TR/xml/#NT-CDSect",
+            TokenType::Whitespace => "https://www.w3.org/TR/xml/#NT-S",
+            TokenType::CharData => "https://www.w3.org/TR/xml/#NT-CharData",
+            TokenType::Unknown => "https://www.w3.org/TR/xml/#NT-document",
+        }
+    }
+}
 
-#[derive(Clone, Copy, PartialEq)]
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 enum State {
     Start,
     Dtd,
@@ -245,11 +332,17 @@ enum State {
 
 
 /// Tokenizer for the XML structure.
+#[derive(Clone)]
 pub struct Tokenizer<'a> {
     stream: Stream<'a>,
     state: State,
     depth: usize,
     fragment_parsing: bool,
+    recovery: bool,
+    strict: bool,
+    // Tokens parsed by `peek_nth` and the tokenizer state after each of them.
+    // Cleared when a mode is enabled, so the tokens are parsed again in that mode.
+    peeked: VecDeque<(Token<'a>, Tokenizer<'a>)>,
 }
 
 impl<'a> From<&'a str> for Tokenizer<'a> {
@@ -265,6 +358,9 @@ impl<'a> From<StrSpan<'a>> for Tokenizer<'a> {
             state: State::Start,
             depth: 0,
             fragment_parsing: false,
+            recovery: false,
+            strict: false,
+            peeked: VecDeque::new(),
         }
     }
 }
@@ -283,9 +379,11 @@ macro_rules! map_err_at {
         let mut start = $stream.pos() as isize + $d;
         debug_assert!(start >= 0);
         if start < 0 { start = 0; }
-        $fun.map_err(|e|
-            Error::InvalidToken($token, $stream.gen_text_pos_from(start as usize), Some(e))
-        )
+        $fun.map_err(|e| {
+            let start = start as usize;
+            let pos = $stream.gen_text_pos_from(start);
+            Error::InvalidToken($token, pos, Some(Box::new(e)), error_range($stream, start))
+        })
     }}
 }
 
@@ -297,11 +395,143 @@ impl<'a> Tokenizer<'a> {
     /// This method switch the parser to the root element content parsing mode.
     /// So it will treat any data as a content of the root element.
     pub fn enable_fragment_mode(&mut self) {
+        self.peeked.clear();
         self.state = State::Elements;
         self.fragment_parsing = true;
     }
 
-    fn parse_next_impl(s: &mut Stream<'a>, state: State) -> Option<Result<Token<'a>>> {
+    /// Enables error recovery.
+    ///
+    /// By default, the tokenizer will stop on the first error.
+    /// In the recovery mode, the error will be returned as usual,
+    /// but the tokenizer will skip the invalid data and will continue parsing.
+    ///
+    /// Inside an element start tag, the tokenizer will skip data up to the `>` or `/>`,
+    /// so the next token will be `ElementEnd`. In other cases - up to the next `<`.
+    ///
+    /// Useful for linters, where all errors should be collected at once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<a><b x></b><></a>");
+    /// p.enable_recovery_mode();
+    /// assert_eq!(p.filter(|t| t.is_err()).count(), 2);
+    /// ```
+    pub fn enable_recovery_mode(&mut self) {
+        self.peeked.clear();
+        self.recovery = true;
+    }
+
+    /// Enables strict well-formedness checking.
+    ///
+    /// By default, the tokenizer accepts some malformed data to stay fast.
+    /// In the strict mode, the XML declaration is checked according to the
+    /// [XMLDecl](https://www.w3.org/TR/xml/#NT-XMLDecl) production:
+    ///
+    /// - `VersionNum` must be `1.` followed by at least one digit.
+    /// - `EncName` must start with a letter.
+    /// - Pseudo-attributes must be separated by whitespaces.
+    /// - Attribute values must be enclosed in the same quote marks.
+    ///
+    /// And processing instructions with the `xml` target in any case,
+    /// like `<?XML ...?>`, are not allowed.
+    ///
+    /// Text, CDATA and attribute values are checked to contain only
+    /// [Char](https://www.w3.org/TR/xml/#NT-Char) characters.
+    /// Text and attribute values must not contain an `&` that doesn't start
+    /// a valid reference. Text must not contain `]]>`.
+    /// And comments must not end with `--->`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<?xml version='1.x'?><a/>");
+    /// p.enable_strict_mode();
+    /// assert_eq!(p.next().unwrap().unwrap_err().to_string(),
+    ///            "invalid token 'Declaration' at 1:1 cause expected '0', '1', '2', '3', '4', \
+    ///             '5', '6', '7', '8', '9' not 'x' at 1:18");
+    /// ```
+    pub fn enable_strict_mode(&mut self) {
+        self.peeked.clear();
+        self.strict = true;
+    }
+
+    /// Enables the [XML 1.1](https://www.w3.org/TR/xml11/) mode.
+    ///
+    /// The mode is enabled automatically when the XML declaration
+    /// has the `version="1.1"` attribute.
+    ///
+    /// In this mode, NEL (`#x85`) and LINE SEPARATOR (`#x2028`) are treated as line ends,
+    /// control characters are allowed as character references
+    /// and C1 control characters are not allowed in comments
+    /// and processing instructions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Token, Tokenizer};
+    ///
+    /// let mut p = Tokenizer::from("<a\u{85}b='1'/>");
+    /// p.enable_xml11_mode();
+    /// assert!(p.all(|t| t.is_ok()));
+    ///
+    /// let mut p = Tokenizer::from("<?xml version='1.1'?><a>\u{2028}</a>");
+    /// match p.nth(3) {
+    ///     Some(Ok(Token::Whitespaces(_))) => {}
+    ///     _ => panic!(),
+    /// }
+    /// ```
+    pub fn enable_xml11_mode(&mut self) {
+        self.peeked.clear();
+        self.stream.enable_xml11_mode();
+    }
+
+    /// Checks that the XML 1.1 mode is enabled.
+    pub fn is_xml11_mode(&self) -> bool {
+        self.stream.is_xml11_mode()
+    }
+
+    /// Enables the CR line breaks mode.
+    ///
+    /// By default, only `\n` is treated as a line break in error positions.
+    /// In this mode, a lone `\r` is treated as a line break too.
+    /// Useful for documents with old Mac line endings.
+    ///
+    /// Tokens are not affected. Use [`StrSpan::to_normalized_str`]
+    /// to get a text with normalized line endings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut p = xmlparser::Tokenizer::from("<a>\r<b x/></a>");
+    /// p.enable_cr_line_breaks_mode();
+    /// let err = p.filter_map(|t| t.err()).next().unwrap();
+    /// assert_eq!(err.pos(), xmlparser::TextPos::new(2, 3));
+    /// ```
+    ///
+    /// [`StrSpan::to_normalized_str`]: struct.StrSpan.html#method.to_normalized_str
+    pub fn enable_cr_line_breaks_mode(&mut self) {
+        self.peeked.clear();
+        self.stream.enable_cr_line_breaks_mode();
+    }
+
+    /// Checks that the CR line breaks mode is enabled.
+    pub fn is_cr_line_breaks_mode(&self) -> bool {
+        self.stream.is_cr_line_breaks_mode()
+    }
+
+    /// Returns the current byte position in the original text.
+    ///
+    /// Unlike `Stream::pos`, the position is absolute,
+    /// even when the tokenizer was created from a `StrSpan`.
+    pub fn pos(&self) -> usize {
+        self.stream.span().start() + self.stream.pos()
+    }
+
+    fn parse_next_impl(s: &mut Stream<'a>, state: State, strict: bool)
+        -> Option<Result<Token<'a>>>
+    {
         if s.at_end() {
             return None;
         }
@@ -321,7 +551,7 @@ impl<'a> Tokenizer<'a> {
                     Ok(v) => v,
                     Err(_) => {
                         let pos = s.gen_text_pos_from(start);
-                        return Some(Err(Error::UnknownToken(pos)));
+                        return Some(Err(Error::UnknownToken(pos, error_range(s, start))));
                     }
                 }
             })
@@ -330,10 +560,11 @@ impl<'a> Tokenizer<'a> {
         macro_rules! gen_err {
             ($token_type:expr) => ({
                 let pos = s.gen_text_pos_from(start);
+                let range = error_range(s, start);
                 if $token_type == TokenType::Unknown {
-                    return Some(Err(Error::UnknownToken(pos)));
+                    return Some(Err(Error::UnknownToken(pos, range)));
                 } else {
-                    return Some(Err(Error::UnexpectedToken($token_type, pos)));
+                    return Some(Err(Error::UnexpectedToken($token_type, pos, range)));
                 }
             })
         }
@@ -345,16 +576,16 @@ impl<'a> Tokenizer<'a> {
                     TokenType::XMLDecl => {
                         // XML declaration allowed only at the start of the document.
                         if start == 0 {
-                            Self::parse_declaration(s)
+                            Self::parse_declaration(s, strict)
                         } else {
                             gen_err!(token_type);
                         }
                     }
                     TokenType::Comment => {
-                        Self::parse_comment(s)
+                        Self::parse_comment(s, strict)
                     }
                     TokenType::PI => {
-                        Self::parse_pi(s)
+                        Self::parse_pi(s, strict)
                     }
                     TokenType::DoctypeDecl => {
                         Self::parse_doctype(s)
@@ -364,7 +595,7 @@ impl<'a> Tokenizer<'a> {
                     }
                     TokenType::Whitespace => {
                         s.skip_spaces();
-                        return Self::parse_next_impl(s, state);
+                        return Self::parse_next_impl(s, state, strict);
                     }
                     _ => {
                         gen_err!(token_type);
@@ -381,23 +612,23 @@ impl<'a> Tokenizer<'a> {
                             gen_err!(token_type);
                         }
 
-                        return Self::parse_next_impl(s, state);
+                        return Self::parse_next_impl(s, state, strict);
                     }
                     TokenType::EntityDecl => {
                         Self::parse_entity_decl(s)
                     }
                     TokenType::Comment => {
-                        Self::parse_comment(s)
+                        Self::parse_comment(s, strict)
                     }
                     TokenType::PI => {
-                        Self::parse_pi(s)
+                        Self::parse_pi(s, strict)
                     }
                     TokenType::DoctypeEnd => {
                         Ok(Token::DtdEnd)
                     }
                     TokenType::Whitespace => {
                         s.skip_spaces();
-                        return Self::parse_next_impl(s, state);
+                        return Self::parse_next_impl(s, state, strict);
                     }
                     _ => {
                         gen_err!(token_type);
@@ -408,17 +639,17 @@ impl<'a> Tokenizer<'a> {
                 let token_type = parse_token_type!();
                 match token_type {
                     TokenType::Comment => {
-                        Self::parse_comment(s)
+                        Self::parse_comment(s, strict)
                     }
                     TokenType::PI => {
-                        Self::parse_pi(s)
+                        Self::parse_pi(s, strict)
                     }
                     TokenType::ElementStart => {
                         Self::parse_element_start(s)
                     }
                     TokenType::Whitespace => {
                         s.skip_spaces();
-                        return Self::parse_next_impl(s, state);
+                        return Self::parse_next_impl(s, state, strict);
                     }
                     _ => {
                         gen_err!(token_type);
@@ -435,16 +666,16 @@ impl<'a> Tokenizer<'a> {
                         Self::parse_close_element(s)
                     }
                     TokenType::CDSect => {
-                        Self::parse_cdata(s)
+                        Self::parse_cdata(s, strict)
                     }
                     TokenType::PI => {
-                        Self::parse_pi(s)
+                        Self::parse_pi(s, strict)
                     }
                     TokenType::Comment => {
-                        Self::parse_comment(s)
+                        Self::parse_comment(s, strict)
                     }
                     TokenType::CharData => {
-                        Self::parse_text(s)
+                        Self::parse_text(s, strict)
                     }
                     _ => {
                         gen_err!(token_type);
@@ -452,22 +683,22 @@ impl<'a> Tokenizer<'a> {
                 }
             }
             State::Attributes => {
-                Self::parse_attribute(s).map_err(|e|
-                    Error::InvalidToken(TokenType::Attribute,
-                                        s.gen_text_pos_from(start), Some(e)))
+                Self::parse_attribute(s, strict).map_err(|e|
+                    Error::InvalidToken(TokenType::Attribute, s.gen_text_pos_from(start),
+                                        Some(Box::new(e)), error_range(s, start)))
             }
             State::AfterElements => {
                 let token_type = parse_token_type!();
                 match token_type {
                     TokenType::Comment => {
-                        Self::parse_comment(s)
+                        Self::parse_comment(s, strict)
                     }
                     TokenType::PI => {
-                        Self::parse_pi(s)
+                        Self::parse_pi(s, strict)
                     }
                     TokenType::Whitespace => {
                         s.skip_spaces();
-                        return Self::parse_next_impl(s, state);
+                        return Self::parse_next_impl(s, state, strict);
                     }
                     _ => {
                         gen_err!(token_type);
@@ -492,8 +723,11 @@ impl<'a> Tokenizer<'a> {
                 let c2 = s.curr_byte()?;
                 match c2 {
                     b'?' => {
-                        // TODO: technically, we should check for any whitespace
-                        if s.starts_with(b"?xml ") {
+                        let is_decl = s.starts_with(b"?xml") && match s.span().as_bytes().get(s.pos() + 4) {
+                            Some(c) => c.is_xml_space(),
+                            None => false,
+                        };
+                        if is_decl {
                             s.advance(5);
                             TokenType::XMLDecl
                         } else {
@@ -574,14 +808,15 @@ impl<'a> Tokenizer<'a> {
         Ok(t)
     }
 
-    fn parse_declaration(s: &mut Stream<'a>) -> Result<Token<'a>> {
-        map_err_at!(Self::parse_declaration_impl(s), TokenType::XMLDecl, s, -6)
+    fn parse_declaration(s: &mut Stream<'a>, strict: bool) -> Result<Token<'a>> {
+        map_err_at!(Self::parse_declaration_impl(s, strict), TokenType::XMLDecl, s, -6)
     }
 
-    fn parse_declaration_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
-        let version = Self::parse_version_info(s)?;
-        let encoding = Self::parse_encoding_decl(s)?;
-        let standalone = Self::parse_standalone(s)?;
+    // XMLDecl ::= '<?xml' VersionInfo EncodingDecl? SDDecl? S? '?>'
+    fn parse_declaration_impl(s: &mut Stream<'a>, strict: bool) -> StreamResult<Token<'a>> {
+        let version = Self::parse_version_info(s, strict)?;
+        let encoding = Self::parse_encoding_decl(s, strict)?;
+        let standalone = Self::parse_standalone(s, strict)?;
 
         s.skip_ascii_spaces();
         s.skip_string(b"?>")?;
@@ -589,34 +824,78 @@ impl<'a> Tokenizer<'a> {
         Ok(Token::Declaration(version, encoding, standalone))
     }
 
-    fn parse_version_info(s: &mut Stream<'a>) -> StreamResult<StrSpan<'a>> {
+    // VersionInfo ::= S 'version' Eq ("'" VersionNum "'" | '"' VersionNum '"')
+    // VersionNum  ::= '1.' [0-9]+
+    fn parse_version_info(s: &mut Stream<'a>, strict: bool) -> StreamResult<StrSpan<'a>> {
         s.skip_ascii_spaces();
         s.skip_string(b"version")?;
         s.consume_eq()?;
-        s.consume_quote()?;
+        let quote = s.consume_quote()?;
 
         let start = s.pos();
         s.skip_string(b"1.")?;
-        s.skip_bytes(|_, c| c.is_xml_digit());
+        let digits = s.consume_bytes(|_, c| c.is_xml_digit());
+        if strict && digits.is_empty() {
+            let c = s.curr_char()?;
+            return Err(StreamError::InvalidCharMultiple(c, b"0123456789".to_vec(),
+                                                        s.gen_text_pos()));
+        }
         let ver = s.slice_back(start);
 
-        s.consume_quote()?;
+        Self::consume_closing_quote(s, quote, strict)?;
 
         Ok(ver)
     }
 
-    // S 'encoding' Eq ('"' EncName '"' | "'" EncName "'" )
-    fn parse_encoding_decl(s: &mut Stream<'a>) -> StreamResult<Option<StrSpan<'a>>> {
-        s.skip_ascii_spaces();
+    // Skips an optional pseudo-attribute name.
+    //
+    // In the strict mode, checks that it's preceded by a whitespace.
+    fn skip_pseudo_attribute_name(s: &mut Stream<'a>, name: &[u8], strict: bool)
+        -> StreamResult<bool>
+    {
+        let mut t = *s;
+        t.skip_ascii_spaces();
+
+        if !t.starts_with(name) {
+            return Ok(false);
+        }
 
-        if s.skip_string(b"encoding").is_err() {
+        if strict && t.pos() == s.pos() {
+            let c = t.curr_byte()? as char;
+            return Err(StreamError::InvalidSpace(c, t.gen_text_pos()));
+        }
+
+        t.advance(name.len());
+        *s = t;
+        Ok(true)
+    }
+
+    fn consume_closing_quote(s: &mut Stream<'a>, quote: u8, strict: bool) -> StreamResult<()> {
+        if strict {
+            s.consume_byte(quote)
+        } else {
+            s.consume_quote().map(|_| ())
+        }
+    }
+
+    // S 'encoding' Eq ('"' EncName '"' | "'" EncName "'" )
+    fn parse_encoding_decl(s: &mut Stream<'a>, strict: bool)
+        -> StreamResult<Option<StrSpan<'a>>>
+    {
+        if !Self::skip_pseudo_attribute_name(s, b"encoding", strict)? {
             return Ok(None);
         }
 
         s.consume_eq()?;
-        s.consume_quote()?;
+        let quote = s.consume_quote()?;
         // [A-Za-z] ([A-Za-z0-9._] | '-')*
-        // TODO: check that first byte is [A-Za-z]
+        if strict {
+            let c = s.curr_char()?;
+            if !c.is_ascii_alphabetic() {
+                return Err(StreamError::InvalidCharacter(c, s.gen_text_pos()));
+            }
+        }
+
         let name = s.consume_bytes(|_, c| {
             c.is_xml_letter()
                 || c.is_xml_digit()
@@ -624,21 +903,19 @@ impl<'a> Tokenizer<'a> {
                 || c == b'-'
                 || c == b'_'
         });
-        s.consume_quote()?;
+        Self::consume_closing_quote(s, quote, strict)?;
 
         Ok(Some(name))
     }
 
     // S 'standalone' Eq (("'" ('yes' | 'no') "'") | ('"' ('yes' | 'no') '"'))
-    fn parse_standalone(s: &mut Stream<'a>) -> StreamResult<Option<bool>> {
-        s.skip_ascii_spaces();
-
-        if s.skip_string(b"standalone").is_err() {
+    fn parse_standalone(s: &mut Stream<'a>, strict: bool) -> StreamResult<Option<bool>> {
+        if !Self::skip_pseudo_attribute_name(s, b"standalone", strict)? {
             return Ok(None);
         }
 
         s.consume_eq()?;
-        s.consume_quote()?;
+        let quote = s.consume_quote()?;
 
         let start = s.pos();
         let value = s.consume_name()?.to_str();
@@ -647,68 +924,54 @@ impl<'a> Tokenizer<'a> {
             "yes" => true,
             "no" => false,
             _ => {
-                let values = vec![value.into(), "yes".into(), "no".into()];
+                let expected = vec!["yes".to_string(), "no".to_string()];
                 let pos = s.gen_text_pos_from(start);
-                return Err(StreamError::InvalidString(values, pos));
+                return Err(StreamError::InvalidString(value.to_string(), expected, pos));
             }
         };
 
-        s.consume_quote()?;
+        Self::consume_closing_quote(s, quote, strict)?;
 
         Ok(Some(flag))
     }
 
     // '<!--' ((Char - '-') | ('-' (Char - '-')))* '-->'
-    fn parse_comment(s: &mut Stream<'a>) -> Result<Token<'a>> {
+    fn parse_comment(s: &mut Stream<'a>, strict: bool) -> Result<Token<'a>> {
         let start = s.pos() - 4;
 
-        let text = s.consume_chars(|s, c| {
-            if c == '-' && s.starts_with(b"-->") {
-                return false;
-            }
-
-            if !c.is_xml_char() {
-                return false;
-            }
+        let text = Self::consume_xml_chars_until(s, b"-->");
 
-            true
-        });
-
-        if text.to_str().contains("--") {
+        // A comment must not end with `--->`.
+        if text.to_str().contains("--") || (strict && text.to_str().ends_with('-')) {
             let pos = s.gen_text_pos_from(start);
-            return Err(Error::InvalidToken(TokenType::Comment, pos, None));
+            return Err(Error::InvalidToken(TokenType::Comment, pos, None, error_range(s, start)));
         }
 
         if s.skip_string(b"-->").is_err() {
             let pos = s.gen_text_pos_from(start);
-            return Err(Error::InvalidToken(TokenType::Comment, pos, None));
+            return Err(Error::InvalidToken(TokenType::Comment, pos, None, error_range(s, start)));
         }
 
         Ok(Token::Comment(text))
     }
 
-    fn parse_pi(s: &mut Stream<'a>) -> Result<Token<'a>> {
-        map_err_at!(Self::parse_pi_impl(s), TokenType::PI, s, -2)
+    fn parse_pi(s: &mut Stream<'a>, strict: bool) -> Result<Token<'a>> {
+        map_err_at!(Self::parse_pi_impl(s, strict), TokenType::PI, s, -2)
     }
 
     // PI       ::= '<?' PITarget (S (Char* - (Char* '?>' Char*)))? '?>'
     // PITarget ::= Name - (('X' | 'x') ('M' | 'm') ('L' | 'l'))
-    fn parse_pi_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
+    fn parse_pi_impl(s: &mut Stream<'a>, strict: bool) -> StreamResult<Token<'a>> {
+        let start = s.pos();
         let target = s.consume_name()?;
+        if strict && target.to_str().eq_ignore_ascii_case("xml") {
+            let pos = s.gen_text_pos_from(start);
+            return Err(StreamError::ReservedName(target.to_str().to_string(), pos));
+        }
 
         s.skip_spaces();
 
-        let content = s.consume_chars(|s, c| {
-            if c == '?' && s.starts_with(b"?>") {
-                return false;
-            }
-
-            if !c.is_xml_char() {
-                return false;
-            }
-
-            true
-        });
+        let content = Self::consume_xml_chars_until(s, b"?>");
 
         let content = if !content.is_empty() {
             Some(content)
@@ -834,9 +1097,9 @@ impl<'a> Tokenizer<'a> {
                 }
             }
             _ => {
-                let chars = vec![c, b'"', b'\'', b'S', b'P'];
+                let chars = vec![b'"', b'\'', b'S', b'P'];
                 let pos = s.gen_text_pos();
-                Err(StreamError::InvalidChar(chars, pos))
+                Err(StreamError::InvalidCharMultiple(s.curr_char()?, chars, pos))
             }
         }
     }
@@ -848,18 +1111,24 @@ impl<'a> Tokenizer<'a> {
         Ok(())
     }
 
-    fn parse_cdata(s: &mut Stream<'a>) -> Result<Token<'a>> {
-        map_err_at!(Self::parse_cdata_impl(s), TokenType::CDSect, s, -9)
+    fn parse_cdata(s: &mut Stream<'a>, strict: bool) -> Result<Token<'a>> {
+        map_err_at!(Self::parse_cdata_impl(s, strict), TokenType::CDSect, s, -9)
     }
 
     // CDSect  ::= CDStart CData CDEnd
     // CDStart ::= '<![CDATA['
     // CData   ::= (Char* - (Char* ']]>' Char*))
     // CDEnd   ::= ']]>'
-    fn parse_cdata_impl(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
-        let text = s.consume_bytes(|s, c| {
-            !(c == b']' && s.starts_with(b"]]>"))
-        });
+    fn parse_cdata_impl(s: &mut Stream<'a>, strict: bool) -> StreamResult<Token<'a>> {
+        let start = s.pos();
+        s.skip_until_string(b"]]>");
+        let text = s.slice_back(start);
+
+        if strict {
+            if let Some((i, c)) = Self::find_invalid_char(s, text, CharsKind::CData) {
+                return Err(StreamError::InvalidCharacter(c, s.gen_text_pos_from(start + i)));
+            }
+        }
 
         s.skip_string(b"]]>")?;
 
@@ -890,7 +1159,7 @@ impl<'a> Tokenizer<'a> {
     }
 
     // Name Eq AttValue
-    fn parse_attribute(s: &mut Stream<'a>) -> StreamResult<Token<'a>> {
+    fn parse_attribute(s: &mut Stream<'a>, strict: bool) -> StreamResult<Token<'a>> {
         s.skip_ascii_spaces();
 
         if let Some(c) = s.get_curr_byte() {
@@ -911,42 +1180,153 @@ impl<'a> Tokenizer<'a> {
         let (prefix, name) = s.consume_qname()?;
         s.consume_eq()?;
         let quote = s.consume_quote()?;
+        let value_start = s.pos();
         let value = s.consume_bytes(|_, c| c != quote);
 
         if value.to_str().contains('<') {
             return Err(StreamError::InvalidAttributeValue);
         }
 
+        if strict {
+            if let Some((i, c)) = Self::find_invalid_char(s, value, CharsKind::AttributeValue) {
+                return Err(StreamError::InvalidCharacter(c, s.gen_text_pos_from(value_start + i)));
+            }
+        }
+
         s.consume_byte(quote)?;
         s.skip_ascii_spaces();
 
         Ok(Token::Attribute((prefix, name), value))
     }
 
-    fn parse_text(s: &mut Stream<'a>) -> Result<Token<'a>> {
-        let text = s.consume_bytes(|_, c| c != b'<');
+    fn parse_text(s: &mut Stream<'a>, strict: bool) -> Result<Token<'a>> {
+        let mut ts = *s;
+        let start = s.pos();
+        s.skip_until_byte(b'<');
+        let text = s.slice_back(start);
+
+        if strict {
+            if let Some((i, c)) = Self::find_invalid_char(s, text, CharsKind::CharData) {
+                let e = StreamError::InvalidCharacter(c, s.gen_text_pos_from(start + i));
+                let pos = s.gen_text_pos_from(start);
+                let range = text.start()..(text.start() + i + c.len_utf8());
+                return Err(Error::InvalidToken(TokenType::CharData, pos, Some(Box::new(e)), range));
+            }
+        }
+
+        // Stop at the first non-space byte, which is usually the first one.
+        // Only a reference and XML 1.1 line ends require a full check,
+        // since they can be a whitespace too.
+        let bytes = text.to_str().as_bytes();
+        let is_spaces = match bytes.iter().position(|c| !c.is_xml_space()) {
+            Some(idx) if bytes[idx] == b'&' || (bytes[idx] >= 0x80 && s.is_xml11_mode()) => {
+                ts.advance(idx);
+                ts.skip_spaces();
+                ts.pos() == s.pos()
+            }
+            Some(_) => false,
+            None => true,
+        };
 
-        let mut ts = Stream::from(text);
-        // TODO: optimize
-        ts.skip_spaces();
-        if ts.at_end() {
+        if is_spaces {
             Ok(Token::Whitespaces(text))
         } else {
             Ok(Token::Text(text))
         }
     }
+
+    // Checks characters, references and the `]]>` sequence in the strict mode.
+    //
+    // Returns the offset of the first invalid char in the text and the char itself.
+    fn find_invalid_char(s: &Stream<'a>, text: StrSpan<'a>, kind: CharsKind)
+        -> Option<(usize, char)>
+    {
+        let text = text.to_str();
+        let xml11 = s.is_xml11_mode();
+        let bytes = text.as_bytes();
+
+        // The end of the valid prefix.
+        let mut end = scan::xml_chars_prefix_len(text, xml11);
+
+        if kind != CharsKind::CData {
+            // Each `&` must start a valid reference.
+            let mut idx = 0;
+            while let Some(i) = scan::find_byte(b'&', &bytes[idx..end]) {
+                idx += i;
+
+                let mut rs = Stream::from(&text[idx..]);
+                if xml11 {
+                    rs.enable_xml11_mode();
+                }
+
+                if rs.consume_reference().is_err() {
+                    end = idx;
+                    break;
+                }
+
+                idx += rs.pos();
+            }
+        }
+
+        if kind == CharsKind::CharData {
+            // Character data must not contain `]]>`. Points to the `>`.
+            if let Some(i) = scan::find_string(b"]]>", &bytes[..end]) {
+                end = i + 2;
+            }
+        }
+
+        text[end..].chars().next().map(|c| (end, c))
+    }
+
+    // Consumes `Char*` until the `end` string.
+    //
+    // Stops at the first invalid char, so the following `end` check will fail.
+    fn consume_xml_chars_until(s: &mut Stream<'a>, end: &[u8]) -> StrSpan<'a> {
+        let start = s.pos();
+        let tail = s.slice_tail().to_str();
+        // `end` starts with an ASCII char, so `len` is always at a char boundary.
+        let len = scan::find_string(end, tail.as_bytes()).unwrap_or(tail.len());
+        s.advance(scan::xml_chars_prefix_len(&tail[..len], s.is_xml11_mode()));
+        s.slice_back(start)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum CharsKind {
+    CharData,
+    CData,
+    AttributeValue,
+}
+
+// Returns an absolute byte range from `start` up to and including the current char.
+fn error_range(s: &Stream, start: usize) -> Range<usize> {
+    let mut end = s.pos();
+    if let Ok(c) = s.curr_char() {
+        end += c.len_utf8();
+    }
+
+    let offset = s.span().start();
+    (offset + start)..(offset + cmp::max(start, end))
 }
 
 impl<'a> Iterator for Tokenizer<'a> {
     type Item = Result<Token<'a>>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some((token, next)) = self.peeked.pop_front() {
+            let peeked = mem::take(&mut self.peeked);
+            *self = next;
+            self.peeked = peeked;
+            return Some(Ok(token));
+        }
+
         if self.stream.at_end() || self.state == State::End {
             self.state = State::End;
             return None;
         }
 
-        let t = Self::parse_next_impl(&mut self.stream, self.state);
+        let prev_stream = self.stream;
+        let t = Self::parse_next_impl(&mut self.stream, self.state, self.strict);
 
         if let Some(ref t) = t {
             match *t {
@@ -972,6 +1352,9 @@ impl<'a> Iterator for Tokenizer<'a> {
                         self.state = State::Elements;
                     }
                 }
+                Ok(Token::Declaration(version, ..)) if version.to_str() == "1.1" => {
+                    self.stream.enable_xml11_mode();
+                }
                 Ok(Token::DtdStart(..)) => {
                     self.state = State::Dtd;
                 }
@@ -979,8 +1362,13 @@ impl<'a> Iterator for Tokenizer<'a> {
                     self.state = State::AfterDtd;
                 }
                 Err(_) => {
-                    self.stream.jump_to_end();
-                    self.state = State::End;
+                    if self.recovery {
+                        self.stream = prev_stream;
+                        self.recover();
+                    } else {
+                        self.stream.jump_to_end();
+                        self.state = State::End;
+                    }
                 }
                 _ => {}
             }
@@ -989,3 +1377,30 @@ impl<'a> Iterator for Tokenizer<'a> {
         t
     }
 }
+
+impl<'a> Tokenizer<'a> {
+    // Skips the invalid data, starting from the beginning of the invalid token.
+    fn recover(&mut self) {
+        let s = &mut self.stream;
+        let start = s.pos();
+
+        match self.state {
+            State::Attributes => {
+                s.skip_bytes(|s, c| c != b'>' && !s.starts_with(b"/>"));
+            }
+            State::Dtd => {
+                s.advance(1);
+                s.skip_bytes(|s, c| c != b'<' && !s.starts_with(b"]>"));
+            }
+            _ => {
+                s.advance(1);
+                s.skip_bytes(|_, c| c != b'<');
+            }
+        }
+
+        // Just in case. The stream must always move forward.
+        if s.pos() == start {
+            s.jump_to_end();
+        }
+    }
+}
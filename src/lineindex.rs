@@ -0,0 +1,291 @@
+use std::cmp;
+
+use {
+    TextPos,
+    Tokenizer,
+};
+
+
+/// A column unit used by [`LineIndex`].
+///
+/// [`LineIndex`]: struct.LineIndex.html
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColumnUnit {
+    /// UTF-8 bytes.
+    Byte,
+    /// Unicode scalar values. The same as `TextPos` generated by the `Stream`.
+    Char,
+    /// UTF-16 code units. Used by the Language Server Protocol.
+    Utf16,
+}
+
+
+/// A precomputed index for fast byte offset to `TextPos` mapping.
+///
+/// Unlike [`Stream::gen_text_pos_from`], which rescans the text on each call,
+/// the index is built once per document and all lookups are `O(log n)`.
+///
+/// Rows and columns are starting from 1, just like in `TextPos`.
+///
+/// # Examples
+///
+/// ```
+/// use xmlparser::{ColumnUnit, LineIndex, TextPos};
+///
+/// let text = "<a>\n  <b>😀</b>\n</a>";
+/// let index = LineIndex::new(text);
+/// assert_eq!(index.text_pos(9), TextPos::new(2, 6));
+/// assert_eq!(index.text_pos(13), TextPos::new(2, 7));
+/// assert_eq!(index.text_pos_in(13, ColumnUnit::Utf16), TextPos::new(2, 8));
+/// assert_eq!(index.offset(TextPos::new(2, 7)), Some(13));
+/// ```
+///
+/// [`Stream::gen_text_pos_from`]: struct.Stream.html#method.gen_text_pos_from
+#[derive(Clone, Debug)]
+pub struct LineIndex {
+    len: usize,
+    // Byte offsets of lines starts.
+    lines: Vec<usize>,
+    // Byte offsets of all non-ASCII chars.
+    wide_chars: Vec<usize>,
+    // Amount of extra UTF-8 bytes before each wide char.
+    // That is, a byte length minus 1 of all previous wide chars.
+    extra_bytes: Vec<usize>,
+    // Amount of chars outside the BMP before each wide char,
+    // which take two UTF-16 code units.
+    surrogates: Vec<usize>,
+}
+
+impl LineIndex {
+    /// Builds an index for the text.
+    ///
+    /// Only `\n` is treated as a line break, just like in a `Stream` with default modes.
+    pub fn new(text: &str) -> Self {
+        Self::with_modes(text, false, false)
+    }
+
+    /// Builds an index for the tokenizer text.
+    ///
+    /// Line breaks are matching the tokenizer modes, so positions are the same
+    /// as in the tokenizer errors. That is, a lone `\r` is a line break
+    /// in the CR line breaks mode, and NEL and LINE SEPARATOR are line breaks
+    /// in the XML 1.1 mode.
+    ///
+    /// The XML 1.1 mode enabled by the XML declaration is taken into account
+    /// only when the tokenizer has already read it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{LineIndex, TextPos, Tokenizer};
+    ///
+    /// let mut p = Tokenizer::from("<a>\r<b/></a>");
+    /// p.enable_cr_line_breaks_mode();
+    /// assert_eq!(LineIndex::from_tokenizer(&p).text_pos(4), TextPos::new(2, 1));
+    /// ```
+    pub fn from_tokenizer(tokenizer: &Tokenizer) -> Self {
+        let stream = &tokenizer.stream;
+        Self::with_modes(stream.span().full_str(), stream.is_xml11_mode(),
+                         stream.is_cr_line_breaks_mode())
+    }
+
+    pub(crate) fn with_modes(text: &str, xml11: bool, cr_line_breaks: bool) -> Self {
+        let mut lines = vec![0];
+        let mut wide_chars = Vec::new();
+        let mut extra_bytes = Vec::new();
+        let mut surrogates = Vec::new();
+
+        let mut extra = 0;
+        let mut surrogate = 0;
+        let bytes = text.as_bytes();
+        for (i, c) in text.char_indices() {
+            let is_line_break = match c {
+                '\n' => true,
+                '\r' if cr_line_breaks => {
+                    let next = &bytes[i + 1..];
+                    !(next.starts_with(b"\n") || (xml11 && next.starts_with("\u{85}".as_bytes())))
+                }
+                '\u{85}' | '\u{2028}' => xml11,
+                _ => false,
+            };
+
+            if is_line_break {
+                lines.push(i + c.len_utf8());
+            }
+
+            if !c.is_ascii() {
+                wide_chars.push(i);
+                extra_bytes.push(extra);
+                surrogates.push(surrogate);
+
+                extra += c.len_utf8() - 1;
+                surrogate += c.len_utf16() - 1;
+            }
+        }
+
+        // A sentinel that simplifies lookups.
+        wide_chars.push(text.len());
+        extra_bytes.push(extra);
+        surrogates.push(surrogate);
+
+        LineIndex {
+            len: text.len(),
+            lines,
+            wide_chars,
+            extra_bytes,
+            surrogates,
+        }
+    }
+
+    /// Returns the amount of lines.
+    pub fn lines_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Returns the byte offset of the line start.
+    ///
+    /// `row` is starting from 1.
+    pub fn line_start(&self, row: u32) -> Option<usize> {
+        if row == 0 {
+            return None;
+        }
+
+        self.lines.get(row as usize - 1).cloned()
+    }
+
+    /// Converts a byte offset into a `TextPos` with columns in chars.
+    ///
+    /// Produces the same result as `Stream::gen_text_pos_from` with the same modes.
+    /// Offsets larger than the text length are clamped.
+    pub fn text_pos(&self, offset: usize) -> TextPos {
+        self.text_pos_in(offset, ColumnUnit::Char)
+    }
+
+    /// Converts a byte offset into a `TextPos` with columns in the specified units.
+    ///
+    /// Offsets larger than the text length are clamped.
+    /// Offsets inside a char are moved to the start of this char.
+    pub fn text_pos_in(&self, offset: usize, unit: ColumnUnit) -> TextPos {
+        let offset = self.floor_char_boundary(cmp::min(offset, self.len));
+        let row = match self.lines.binary_search(&offset) {
+            Ok(idx) => idx,
+            Err(idx) => idx - 1,
+        };
+
+        let start = self.lines[row];
+        let col = self.units_between(start, offset, unit);
+
+        TextPos::new(row as u32 + 1, col as u32 + 1)
+    }
+
+    /// Converts a `TextPos` with columns in chars into a byte offset.
+    ///
+    /// Returns `None` when the position is outside of the text.
+    pub fn offset(&self, pos: TextPos) -> Option<usize> {
+        self.offset_in(pos, ColumnUnit::Char)
+    }
+
+    /// Converts a `TextPos` with columns in the specified units into a byte offset.
+    ///
+    /// Returns `None` when the position is outside of the text
+    /// or points inside a char, like in the middle of a UTF-16 surrogate pair.
+    pub fn offset_in(&self, pos: TextPos, unit: ColumnUnit) -> Option<usize> {
+        let start = self.line_start(pos.row)?;
+        let end = match self.lines.get(pos.row as usize) {
+            // A line break can be longer than one byte in the XML 1.1 mode.
+            Some(next) => self.floor_char_boundary(next - 1),
+            None => self.len,
+        };
+
+        if pos.col == 0 {
+            return None;
+        }
+        let col = pos.col as usize - 1;
+
+        if unit == ColumnUnit::Byte {
+            return if start + col <= end { Some(start + col) } else { None };
+        }
+
+        // Find the last wide char on this line that is before the column.
+        let first = self.wide_char_idx(start);
+        let last = self.wide_char_idx(end);
+        let wide = &self.wide_chars[first..last];
+        let n = binary_search_by_key(wide, col, |o| self.units_between(start, o, unit));
+
+        let offset = if n == 0 {
+            start + col
+        } else {
+            let idx = first + n - 1;
+            let o = self.wide_chars[idx];
+            let len = self.char_len(idx);
+            let width = if unit == ColumnUnit::Utf16 { if len == 4 { 2 } else { 1 } } else { 1 };
+            let units = self.units_between(start, o, unit);
+            if col < units + width {
+                // Points inside a surrogate pair.
+                return None;
+            }
+
+            o + len + (col - units - width)
+        };
+
+        if offset <= end { Some(offset) } else { None }
+    }
+
+    fn floor_char_boundary(&self, offset: usize) -> usize {
+        let idx = self.wide_char_idx(offset);
+        if idx > 0 {
+            let prev = self.wide_chars[idx - 1];
+            if offset < prev + self.char_len(idx - 1) {
+                return prev;
+            }
+        }
+
+        offset
+    }
+
+    // Returns the index of the first wide char at or after the offset.
+    fn wide_char_idx(&self, offset: usize) -> usize {
+        match self.wide_chars.binary_search(&offset) {
+            Ok(idx) | Err(idx) => idx,
+        }
+    }
+
+    fn char_len(&self, idx: usize) -> usize {
+        self.extra_bytes[idx + 1] - self.extra_bytes[idx] + 1
+    }
+
+    fn units_between(&self, start: usize, end: usize, unit: ColumnUnit) -> usize {
+        let bytes = end - start;
+        if unit == ColumnUnit::Byte {
+            return bytes;
+        }
+
+        let a = self.wide_char_idx(start);
+        let b = self.wide_char_idx(end);
+        let chars = bytes - (self.extra_bytes[b] - self.extra_bytes[a]);
+
+        match unit {
+            ColumnUnit::Utf16 => chars + (self.surrogates[b] - self.surrogates[a]),
+            _ => chars,
+        }
+    }
+}
+
+// Returns the amount of items with a key less than `value`.
+// Keys must be sorted.
+fn binary_search_by_key<F>(items: &[usize], value: usize, f: F) -> usize
+    where F: Fn(usize) -> usize
+{
+    let mut lo = 0;
+    let mut hi = items.len();
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if f(items[mid]) < value {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+
+    lo
+}
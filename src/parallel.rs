@@ -0,0 +1,246 @@
+use std::cmp;
+use std::collections::VecDeque;
+use std::thread;
+
+use {
+    scan,
+    sax,
+    ElementEnd,
+    State,
+    Stream,
+    StrSpan,
+    Token,
+    Tokenizer,
+};
+
+
+impl<'a> Tokenizer<'a> {
+    /// Splits the document into parts that can be tokenized independently.
+    ///
+    /// The first part contains everything up to and including the root element start tag.
+    /// The following parts contain the root element children and are split
+    /// right before the start tags of the root element children with the same name
+    /// as the first one. That is, the document is expected to be a list of repeated records,
+    /// like `<records><record>...</record>...</records>`.
+    /// The last part also contains the root element close tag and everything after it.
+    /// So at most `max_parts + 1` tokenizers will be returned.
+    /// `max_parts` equal to `0` is treated as `1`.
+    ///
+    /// Tokens produced by all parts in order are exactly the same as produced by a single
+    /// `Tokenizer`, including absolute spans, as long as the document is well-formed.
+    ///
+    /// The document is never split after a comment, CDATA or a processing instruction
+    /// inside the root element, since they can contain anything that looks like a tag.
+    /// In the worst case, a single tokenizer for the whole document is returned.
+    ///
+    /// Records must not contain elements with the same name as the record itself,
+    /// otherwise a part can start inside a record.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Tokenizer;
+    ///
+    /// let text = "<list><item>1</item><item>2</item><item>3</item></list>";
+    /// let parts = Tokenizer::split(text, 4);
+    /// assert_eq!(parts.len(), 4);
+    ///
+    /// let tokens: Vec<_> = parts.into_iter().flatten().map(|t| t.unwrap()).collect();
+    /// let expected: Vec<_> = Tokenizer::from(text).map(|t| t.unwrap()).collect();
+    /// assert_eq!(tokens, expected);
+    /// ```
+    pub fn split(text: &'a str, max_parts: usize) -> Vec<Tokenizer<'a>> {
+        Tokenizer::from(text).into_parts(max_parts)
+    }
+
+    /// Splits a preconfigured tokenizer into parts that can be tokenized independently.
+    ///
+    /// The same as [`split`], but the recovery, strict, XML 1.1 and CR line breaks modes
+    /// are copied into each part.
+    ///
+    /// The tokenizer must be created from the whole document and must not be advanced yet.
+    /// A tokenizer in the fragment mode is never split.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Tokenizer;
+    ///
+    /// let mut p = Tokenizer::from("<list><item/><item/><item>&#x0;</item></list>");
+    /// p.enable_strict_mode();
+    /// let errors = p.into_parts(3).into_iter().flatten().filter(|t| t.is_err()).count();
+    /// assert_eq!(errors, 1);
+    /// ```
+    ///
+    /// [`split`]: #method.split
+    pub fn into_parts(self, max_parts: usize) -> Vec<Tokenizer<'a>> {
+        if self.fragment_parsing {
+            return vec![self];
+        }
+
+        match split_points(self.clone(), max_parts) {
+            Some((points, parent)) => make_parts(self, &parent, &points),
+            None => vec![self],
+        }
+    }
+
+    /// Splits the document using [`split`] and processes all parts on separate threads.
+    ///
+    /// Results are returned in the document order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Token, Tokenizer};
+    ///
+    /// let text = "<list><item>1</item><item>2</item><item>3</item></list>";
+    /// let counts = Tokenizer::parse_parallel(text, 2, |tokenizer| {
+    ///     tokenizer.filter(|t| match t { Ok(Token::Text(_)) => true, _ => false }).count()
+    /// });
+    /// assert_eq!(counts.iter().sum::<usize>(), 3);
+    /// ```
+    ///
+    /// [`split`]: #method.split
+    pub fn parse_parallel<F, R>(text: &'a str, threads: usize, f: F) -> Vec<R>
+        where F: Fn(Tokenizer<'a>) -> R + Sync, R: Send
+    {
+        let parts = Self::split(text, threads);
+        let f = &f;
+        thread::scope(|scope| {
+            let handles: Vec<_> = parts.into_iter()
+                .map(|part| scope.spawn(move || f(part)))
+                .collect();
+
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        })
+    }
+}
+
+// Returns the root element content start followed by records start positions
+// and the tokenizer at the root element content start.
+fn split_points(mut tokenizer: Tokenizer, max_parts: usize) -> Option<(Vec<usize>, Tokenizer)> {
+    let max_parts = cmp::max(max_parts, 1);
+    let text = tokenizer.stream.span().full_str();
+
+    // Find the end of the root element start tag.
+    let content_start = loop {
+        match tokenizer.next()? {
+            Ok(Token::ElementEnd(ElementEnd::Open)) => break tokenizer.pos(),
+            Ok(Token::ElementEnd(_)) | Err(_) => return None,
+            Ok(_) => {}
+        }
+    };
+
+    // Find the name of the first child element.
+    let name = loop {
+        match tokenizer.next()? {
+            Ok(Token::ElementStart(prefix, local)) => break sax::qname_to_string(prefix, local),
+            Ok(Token::Text(_)) | Ok(Token::Whitespaces(_)) => {}
+            _ => return None,
+        }
+    };
+
+    // Anything after a comment, CDATA or PI cannot be split,
+    // since they can contain tag-like text.
+    let bytes = text.as_bytes();
+    let content = &bytes[content_start..];
+    let content_end = content_start + [b"<!", b"<?"].iter()
+        .filter_map(|s| scan::find_string(*s, content))
+        .min()
+        .unwrap_or(content.len());
+
+    let mut pattern = Vec::with_capacity(name.len() + 1);
+    pattern.push(b'<');
+    pattern.extend_from_slice(name.as_bytes());
+
+    let mut points = vec![content_start];
+    let chunk_len = (content_end - content_start) / max_parts;
+    for i in 1..max_parts {
+        let from = cmp::max(content_start + i * chunk_len, *points.last().unwrap() + 1);
+        if from >= content_end {
+            break;
+        }
+
+        match find_record(&bytes[..content_end], from, &pattern) {
+            Some(pos) => points.push(pos),
+            None => break,
+        }
+    }
+
+    Some((points, tokenizer))
+}
+
+// Returns the start of the first record at or after `from`.
+//
+// A record must directly follow a close tag of the previous record or an empty element.
+fn find_record(text: &[u8], mut from: usize, pattern: &[u8]) -> Option<usize> {
+    let mut close = Vec::with_capacity(pattern.len() + 2);
+    close.extend_from_slice(b"</");
+    close.extend_from_slice(&pattern[1..]);
+    close.push(b'>');
+
+    while let Some(idx) = scan::find_string(pattern, &text[from..]) {
+        let pos = from + idx;
+        from = pos + 1;
+
+        match text.get(pos + pattern.len()) {
+            Some(&c) if c == b'>' || c == b'/' || c == b' ' || c == b'\t'
+                     || c == b'\n' || c == b'\r' => {}
+            _ => continue,
+        }
+
+        let prev = text[..pos].iter().rposition(|c| !matches!(*c, b' ' | b'\t' | b'\n' | b'\r'));
+        let prev = &text[..prev.map(|i| i + 1).unwrap_or(0)];
+
+        if prev.ends_with(&close) || prev.ends_with(b"/>") {
+            return Some(pos);
+        }
+    }
+
+    None
+}
+
+// The first part is the original tokenizer limited by the first point.
+// Other parts are copying modes from the tokenizer that has read the XML declaration.
+fn make_parts<'a>(first: Tokenizer<'a>, parent: &Tokenizer<'a>, points: &[usize])
+    -> Vec<Tokenizer<'a>>
+{
+    let text = first.stream.span().full_str();
+    let make_stream = |span: StrSpan<'a>, modes: &Stream| {
+        let mut stream = Stream::from(span);
+        if modes.is_xml11_mode() {
+            stream.enable_xml11_mode();
+        }
+        if modes.is_cr_line_breaks_mode() {
+            stream.enable_cr_line_breaks_mode();
+        }
+        stream
+    };
+
+    let mut parts = Vec::with_capacity(points.len() + 1);
+    parts.push(Tokenizer {
+        stream: make_stream(StrSpan::from_substr(text, 0, points[0]), &first.stream),
+        peeked: VecDeque::new(),
+        ..first
+    });
+
+    for (i, start) in points.iter().enumerate() {
+        let end = points.get(i + 1).cloned().unwrap_or(text.len());
+        let span = StrSpan::from_substr(text, *start, end);
+        let last = i + 1 == points.len();
+
+        // The last part must switch to the `AfterElements` state on the root element end,
+        // just like a single tokenizer would.
+        parts.push(Tokenizer {
+            stream: make_stream(span, &parent.stream),
+            state: State::Elements,
+            depth: if last { 1 } else { 0 },
+            fragment_parsing: !last,
+            recovery: parent.recovery,
+            strict: parent.strict,
+            peeked: VecDeque::new(),
+        });
+    }
+
+    parts
+}
@@ -0,0 +1,565 @@
+use std::collections::VecDeque;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use sax::{is_namespace_declaration, OpenTag, TagStack};
+use text::unescape;
+use {
+    Attribute,
+    CharsKind,
+    ElementEnd,
+    Error,
+    StrSpan,
+    Token,
+    Tokenizer,
+    XmlCharExt,
+};
+
+
+/// A [`Path`] or a [`Selector`] parsing error.
+///
+/// [`Path`]: struct.Path.html
+/// [`Selector`]: struct.Selector.html
+#[derive(Clone, PartialEq, Debug)]
+pub enum QueryError {
+    /// The string ended earlier than expected.
+    UnexpectedEnd,
+    /// An unexpected character at the byte position.
+    InvalidChar(char, usize),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            QueryError::UnexpectedEnd => {
+                write!(f, "unexpected end of query")
+            }
+            QueryError::InvalidChar(c, pos) => {
+                write!(f, "unexpected character '{}' at position {}", c, pos)
+            }
+        }
+    }
+}
+
+impl error::Error for QueryError {
+    fn description(&self) -> &str {
+        "a path or selector parsing error"
+    }
+}
+
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Axis {
+    Child,
+    Descendant,
+}
+
+// `None` matches any name.
+#[derive(Clone, PartialEq, Debug)]
+struct NameTest {
+    prefix: Option<String>,
+    local: Option<String>,
+}
+
+impl NameTest {
+    // Namespace declarations are not attributes in XPath.
+    fn matches_attribute(&self, attr: &Attribute) -> bool {
+        !is_namespace_declaration(attr) && self.matches(attr.prefix(), attr.local())
+    }
+
+    fn matches(&self, prefix: StrSpan, local: StrSpan) -> bool {
+        let is_prefix_eq = match self.prefix {
+            Some(ref p) => p == prefix.to_str(),
+            None => self.local.is_none() || prefix.is_empty(),
+        };
+
+        is_prefix_eq && self.local.as_ref().map_or(true, |l| l == local.to_str())
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Predicate {
+    // 1-based.
+    Position(usize),
+    Attribute(NameTest, Option<String>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Step {
+    axis: Axis,
+    is_attribute: bool,
+    test: NameTest,
+    // Indexes in `Path::predicates`.
+    predicates: (usize, usize),
+}
+
+
+/// A compiled path query.
+///
+/// Supports a forward-only [XPath 1.0](https://www.w3.org/TR/xpath/) subset:
+///
+/// - Absolute location paths with the child (`/`) and descendant (`//`) axes.
+/// - Name tests: `name`, `prefix:name`, `prefix:*` and `*`.
+///   An unprefixed name test matches only unprefixed names, unless it's `*`.
+///   Prefixes are compared as is, without namespace resolution.
+/// - An attribute step, like `@name` or `@*`, as the last step.
+/// - Positional predicates, like `[2]`.
+/// - Attribute predicates, like `[@id]` and `[@id='value']`.
+///   Attribute values are unescaped before comparison.
+///
+/// Predicates can be chained, like `item[@type='a'][2]`.
+///
+/// Paths are evaluated by [`Tokenizer::select`].
+///
+/// [`Tokenizer::select`]: struct.Tokenizer.html#method.select
+#[derive(Clone, PartialEq, Debug)]
+pub struct Path {
+    steps: Vec<Step>,
+    predicates: Vec<Predicate>,
+}
+
+impl Path {
+    /// Parses a path.
+    pub fn parse(text: &str) -> Result<Path, QueryError> {
+        PathParser { text, pos: 0 }.parse()
+    }
+
+    fn selects_attributes(&self) -> bool {
+        self.steps.last().map_or(false, |s| s.is_attribute)
+    }
+}
+
+impl FromStr for Path {
+    type Err = QueryError;
+
+    fn from_str(text: &str) -> Result<Path, QueryError> {
+        Path::parse(text)
+    }
+}
+
+
+struct PathParser<'t> {
+    text: &'t str,
+    pos: usize,
+}
+
+impl<'t> PathParser<'t> {
+    fn parse(mut self) -> Result<Path, QueryError> {
+        let mut path = Path {
+            steps: Vec::new(),
+            predicates: Vec::new(),
+        };
+
+        loop {
+            let axis = if self.consume_str("//") {
+                Axis::Descendant
+            } else if self.consume_str("/") {
+                Axis::Child
+            } else {
+                return Err(self.unexpected());
+            };
+
+            let is_attribute = self.consume_str("@");
+            let test = self.parse_name_test()?;
+
+            let start = path.predicates.len();
+            if !is_attribute {
+                while self.consume_str("[") {
+                    let predicate = self.parse_predicate()?;
+                    path.predicates.push(predicate);
+                }
+            }
+
+            path.steps.push(Step {
+                axis,
+                is_attribute,
+                test,
+                predicates: (start, path.predicates.len()),
+            });
+
+            // An attribute step must be the last one.
+            if is_attribute || self.at_end() {
+                break;
+            }
+        }
+
+        if !self.at_end() {
+            return Err(self.unexpected());
+        }
+
+        Ok(path)
+    }
+
+    fn parse_name_test(&mut self) -> Result<NameTest, QueryError> {
+        if self.consume_str("*") {
+            return Ok(NameTest { prefix: None, local: None });
+        }
+
+        let name = self.parse_ncname()?;
+        if self.consume_str(":") {
+            let local = if self.consume_str("*") {
+                None
+            } else {
+                Some(self.parse_ncname()?)
+            };
+
+            Ok(NameTest { prefix: Some(name), local })
+        } else {
+            Ok(NameTest { prefix: None, local: Some(name) })
+        }
+    }
+
+    fn parse_ncname(&mut self) -> Result<String, QueryError> {
+        let start = self.pos;
+        for c in self.text[self.pos..].chars() {
+            let is_valid = if self.pos == start { c.is_xml_name_start() } else { c.is_xml_name() };
+            if !is_valid || c == ':' {
+                break;
+            }
+
+            self.pos += c.len_utf8();
+        }
+
+        if self.pos == start {
+            return Err(self.unexpected());
+        }
+
+        Ok(self.text[start..self.pos].to_string())
+    }
+
+    fn parse_predicate(&mut self) -> Result<Predicate, QueryError> {
+        self.skip_spaces();
+
+        let predicate = if self.consume_str("@") {
+            let test = self.parse_name_test()?;
+            self.skip_spaces();
+
+            let value = if self.consume_str("=") {
+                self.skip_spaces();
+                Some(self.parse_literal()?)
+            } else {
+                None
+            };
+
+            Predicate::Attribute(test, value)
+        } else {
+            let start = self.pos;
+            self.pos += self.text[start..].bytes().take_while(|c| c.is_ascii_digit()).count();
+            match self.text[start..self.pos].parse() {
+                Ok(n) => Predicate::Position(n),
+                Err(_) => {
+                    self.pos = start;
+                    return Err(self.unexpected());
+                }
+            }
+        };
+
+        self.skip_spaces();
+        if !self.consume_str("]") {
+            return Err(self.unexpected());
+        }
+
+        Ok(predicate)
+    }
+
+    fn parse_literal(&mut self) -> Result<String, QueryError> {
+        let quote = match self.text[self.pos..].chars().next() {
+            Some(c) if c == '"' || c == '\'' => c,
+            _ => return Err(self.unexpected()),
+        };
+
+        self.pos += 1;
+        match self.text[self.pos..].find(quote) {
+            Some(len) => {
+                let value = self.text[self.pos..self.pos + len].to_string();
+                self.pos += len + 1;
+                Ok(value)
+            }
+            None => Err(QueryError::UnexpectedEnd),
+        }
+    }
+
+    fn consume_str(&mut self, s: &str) -> bool {
+        if self.text[self.pos..].starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        self.pos += self.text[self.pos..].bytes().take_while(|c| c.is_ascii_whitespace()).count();
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos == self.text.len()
+    }
+
+    fn unexpected(&self) -> QueryError {
+        match self.text[self.pos..].chars().next() {
+            Some(c) => QueryError::InvalidChar(c, self.pos),
+            None => QueryError::UnexpectedEnd,
+        }
+    }
+}
+
+
+impl<'a> Tokenizer<'a> {
+    /// Evaluates the path over the token stream and returns matched spans
+    /// in document order.
+    ///
+    /// Elements are returned as a whole, from `<` to the end of the close tag.
+    /// Attributes are returned as values.
+    ///
+    /// No tree is built. The memory usage depends only on the document depth
+    /// and the number of nested matches that are not closed yet.
+    ///
+    /// Like [`parse_with`], checks that close tags are matching the open ones.
+    /// The iterator stops after the first error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Path, Tokenizer};
+    ///
+    /// let text = "
+    /// <svg>
+    ///     <defs>
+    ///         <linearGradient id='lg1'/>
+    ///         <radialGradient id='rg1'/>
+    ///         <linearGradient id='lg2'/>
+    ///     </defs>
+    /// </svg>";
+    ///
+    /// let path = Path::parse("/svg/defs/linearGradient/@id").unwrap();
+    /// let ids: Vec<_> = Tokenizer::from(text).select(&path).map(|s| s.unwrap().to_str()).collect();
+    /// assert_eq!(ids, vec!["lg1", "lg2"]);
+    ///
+    /// let path = Path::parse("//*[@id='rg1']").unwrap();
+    /// let nodes: Vec<_> = Tokenizer::from(text).select(&path).map(|s| s.unwrap().to_str()).collect();
+    /// assert_eq!(nodes, vec!["<radialGradient id='rg1'/>"]);
+    /// ```
+    ///
+    /// [`parse_with`]: struct.Tokenizer.html#method.parse_with
+    pub fn select(self, path: &Path) -> Select<'a, '_> {
+        let root = Frame {
+            active: vec![0],
+            counters: vec![0; path.predicates.len()],
+            pending: None,
+        };
+
+        Select {
+            tokenizer: self,
+            path,
+            root,
+            stack: TagStack::new(),
+            element: None,
+            attributes: Vec::new(),
+            queue: VecDeque::new(),
+            dequeued: 0,
+            done: false,
+        }
+    }
+}
+
+
+// An open element or the document.
+struct Frame {
+    // Indexes of steps that can be matched by children.
+    active: Vec<usize>,
+    // Positional predicates counters of children.
+    counters: Vec<usize>,
+    // An absolute index of the element match in the queue.
+    pending: Option<usize>,
+}
+
+/// An iterator over spans matched by a [`Path`].
+///
+/// Created by [`Tokenizer::select`].
+///
+/// [`Path`]: struct.Path.html
+/// [`Tokenizer::select`]: struct.Tokenizer.html#method.select
+pub struct Select<'a, 'p> {
+    tokenizer: Tokenizer<'a>,
+    path: &'p Path,
+    root: Frame,
+    stack: TagStack<'a, Frame>,
+    // The current start tag name and its start position.
+    element: Option<(StrSpan<'a>, StrSpan<'a>, usize)>,
+    attributes: Vec<Attribute<'a>>,
+    // Matches in document order. Element matches are `None` until closed.
+    queue: VecDeque<Option<StrSpan<'a>>>,
+    // The number of matches removed from the queue.
+    dequeued: usize,
+    done: bool,
+}
+
+impl<'a, 'p> Select<'a, 'p> {
+    fn start_element(&mut self) {
+        let (prefix, local, start) = match self.element.take() {
+            Some(v) => v,
+            None => return,
+        };
+
+        let path = self.path;
+        let xml11 = self.tokenizer.is_xml11_mode();
+
+        let mut active = Vec::new();
+        let mut is_match = false;
+        {
+            let parent = match self.stack.last_mut() {
+                Some(tag) => &mut tag.data,
+                None => &mut self.root,
+            };
+
+            for &idx in &parent.active {
+                let step = &path.steps[idx];
+                if !step.is_attribute
+                    && step.test.matches(prefix, local)
+                    && check_predicates(path, step, &self.attributes, &mut parent.counters, xml11)
+                {
+                    if idx + 1 == path.steps.len() {
+                        is_match = true;
+                    } else if !active.contains(&(idx + 1)) {
+                        active.push(idx + 1);
+                    }
+                }
+
+                if step.axis == Axis::Descendant && !active.contains(&idx) {
+                    active.push(idx);
+                }
+            }
+        }
+
+        let attr_step = path.steps.len() - 1;
+        if path.selects_attributes() && active.contains(&attr_step) {
+            let test = &path.steps[attr_step].test;
+            for attr in &self.attributes {
+                if test.matches_attribute(attr) {
+                    self.queue.push_back(Some(attr.value()));
+                }
+            }
+
+            // Selects attributes of the current element only.
+            if path.steps[attr_step].axis == Axis::Child {
+                active.retain(|&idx| idx != attr_step);
+            }
+        }
+
+        let mut pending = None;
+        if is_match {
+            pending = Some(self.dequeued + self.queue.len());
+            self.queue.push_back(None);
+        }
+
+        let counters = if active.is_empty() { Vec::new() } else { vec![0; path.predicates.len()] };
+
+        self.stack.push(prefix, local, start, Frame { active, counters, pending });
+    }
+
+    fn end_element(&mut self, tag: OpenTag<'a, Frame>) {
+        if let Some(idx) = tag.data.pending {
+            let text = self.tokenizer.stream.span().full_str();
+            let span = StrSpan::from_substr(text, tag.start, self.tokenizer.pos());
+            self.queue[idx - self.dequeued] = Some(span);
+        }
+    }
+
+    fn process_token(&mut self, token: Token<'a>, token_start: usize) -> Result<(), Error> {
+        match token {
+            Token::ElementStart(prefix, local) => {
+                self.element = Some((prefix, local, self.tokenizer.markup_start(token_start)));
+                self.attributes.clear();
+            }
+            Token::Attribute((prefix, local), value) => {
+                self.attributes.push(Attribute::new(prefix, local, value));
+            }
+            Token::ElementEnd(ElementEnd::Open) => {
+                self.start_element();
+            }
+            Token::ElementEnd(ElementEnd::Empty) => {
+                self.start_element();
+                if let Some(tag) = self.stack.pop() {
+                    self.end_element(tag);
+                }
+            }
+            Token::ElementEnd(ElementEnd::Close(prefix, local)) => {
+                let tag = self.stack.close(&self.tokenizer, prefix, local, token_start)?;
+                self.end_element(tag);
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, 'p> Iterator for Select<'a, 'p> {
+    type Item = Result<StrSpan<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(&Some(span)) = self.queue.front() {
+                self.queue.pop_front();
+                self.dequeued += 1;
+                return Some(Ok(span));
+            }
+
+            if self.done {
+                return None;
+            }
+
+            let token_start = self.tokenizer.pos();
+            let res = match self.tokenizer.next() {
+                Some(Ok(token)) => self.process_token(token, token_start),
+                Some(Err(e)) => Err(e),
+                None => {
+                    self.done = true;
+                    self.stack.finish(&self.tokenizer)
+                }
+            };
+
+            if let Err(e) = res {
+                self.done = true;
+                self.queue.clear();
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+// Checks predicates in order. Positions are counted only for children
+// that passed the previous predicates.
+fn check_predicates(
+    path: &Path,
+    step: &Step,
+    attributes: &[Attribute],
+    counters: &mut [usize],
+    xml11: bool,
+) -> bool {
+    let (start, end) = step.predicates;
+    for (predicate, counter) in path.predicates[start..end].iter().zip(&mut counters[start..end]) {
+        let is_passed = match *predicate {
+            Predicate::Position(n) => {
+                *counter += 1;
+                *counter == n
+            }
+            Predicate::Attribute(ref test, ref value) => {
+                attributes.iter().any(|attr| {
+                    test.matches_attribute(attr)
+                        && value.as_ref().map_or(true, |v| {
+                            *v == unescape(attr.value().to_str(), CharsKind::AttributeValue, xml11)
+                        })
+                })
+            }
+        };
+
+        if !is_passed {
+            return false;
+        }
+    }
+
+    true
+}
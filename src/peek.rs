@@ -0,0 +1,221 @@
+use std::ops::Range;
+
+use sax::{is_qname_eq, qname_to_string};
+use {
+    ElementEnd,
+    Error,
+    StrSpan,
+    StreamError,
+    Token,
+    TokenType,
+    Tokenizer,
+    XmlByteExt,
+};
+
+
+impl<'a> Tokenizer<'a> {
+    /// Returns the next token without consuming it.
+    ///
+    /// The tokenizer position is not affected. The token is buffered,
+    /// so the following `next()` call will not parse it again.
+    /// Enabling any mode discards buffered tokens, so they are parsed again in that mode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Token, Tokenizer};
+    ///
+    /// let mut p = Tokenizer::from("<a/>");
+    /// assert_eq!(p.peek().unwrap().unwrap(), p.next().unwrap().unwrap());
+    /// assert_eq!(p.pos(), 2);
+    /// ```
+    pub fn peek(&mut self) -> Option<Result<Token<'a>, Error>> {
+        self.peek_nth(0)
+    }
+
+    /// Returns the `n`th token after the current one without consuming any.
+    ///
+    /// `peek_nth(0)` is the same as `peek()`. Like `Iterator::nth`, returns `None`
+    /// when there are not enough tokens.
+    ///
+    /// All tokens up to the `n`th one are buffered and parsed only once.
+    /// Errors are not buffered, so tokens after an error are parsed on each call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Token, Tokenizer};
+    ///
+    /// let mut p = Tokenizer::from("<a x='1'/>");
+    /// match p.peek_nth(1) {
+    ///     Some(Ok(Token::Attribute(_, value))) => assert_eq!(value.to_str(), "1"),
+    ///     _ => panic!(),
+    /// }
+    /// assert!(p.peek_nth(3).is_none());
+    /// ```
+    pub fn peek_nth(&mut self, n: usize) -> Option<Result<Token<'a>, Error>> {
+        if let Some(&(token, _)) = self.peeked.get(n) {
+            return Some(Ok(token));
+        }
+
+        let mut next = match self.peeked.back() {
+            Some((_, next)) => next.clone(),
+            None => self.clone(),
+        };
+
+        // Errors are not cloneable, so only tokens before the first one are buffered.
+        let mut is_buffered = true;
+        let mut i = self.peeked.len();
+        loop {
+            let token = next.next()?;
+            match token {
+                Ok(token) if is_buffered => self.peeked.push_back((token, next.clone())),
+                Ok(_) => {}
+                Err(_) => is_buffered = false,
+            }
+
+            if i == n {
+                return Some(token);
+            }
+
+            i += 1;
+        }
+    }
+
+    /// Consumes the next token if it's an element start with the specified name.
+    ///
+    /// `name` is a qualified name as written in the document, like `svg:rect`.
+    ///
+    /// # Errors
+    ///
+    /// - `UnexpectedToken` with the actual token type, when the next token is not
+    ///   an element start or has a different name.
+    /// - `InvalidToken` with `UnexpectedEndOfStream` cause, when there are no tokens left.
+    /// - A tokenizer error.
+    ///
+    /// On error, the token is not consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Tokenizer;
+    ///
+    /// let mut p = Tokenizer::from("<svg><rect x='1'/></svg>");
+    /// p.expect_element_start("svg").unwrap();
+    /// assert_eq!(p.expect_element_start("rect").unwrap_err().to_string(),
+    ///            "unexpected token 'Element Close' at 1:5");
+    /// ```
+    pub fn expect_element_start(&mut self, name: &str) -> Result<(), Error> {
+        let (token, range, next) = self.next_expected(TokenType::ElementStart)?;
+        match token {
+            Token::ElementStart(prefix, local) if is_qname_eq(prefix, local, name) => {
+                *self = next;
+                Ok(())
+            }
+            _ => Err(self.unexpected_token(&token, range)),
+        }
+    }
+
+    /// Consumes the next token if it's an attribute with the specified name
+    /// and returns its value.
+    ///
+    /// The same as [`expect_element_start`], but for attributes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Tokenizer;
+    ///
+    /// let mut p = Tokenizer::from("<rect x='1' y='2'/>");
+    /// p.expect_element_start("rect").unwrap();
+    /// assert_eq!(p.expect_attribute("x").unwrap().to_str(), "1");
+    /// assert_eq!(p.expect_attribute("x").unwrap_err().to_string(),
+    ///            "unexpected token 'Attribute' at 1:13");
+    /// ```
+    ///
+    /// [`expect_element_start`]: #method.expect_element_start
+    pub fn expect_attribute(&mut self, name: &str) -> Result<StrSpan<'a>, Error> {
+        let (token, range, next) = self.next_expected(TokenType::Attribute)?;
+        match token {
+            Token::Attribute((prefix, local), value) if is_qname_eq(prefix, local, name) => {
+                *self = next;
+                Ok(value)
+            }
+            _ => Err(self.unexpected_token(&token, range)),
+        }
+    }
+
+    /// Consumes the next token if it's a close tag with the specified name.
+    ///
+    /// The same as [`expect_element_start`], but for close tags.
+    /// A close tag with a different name produces `UnexpectedCloseTag`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Tokenizer;
+    ///
+    /// let mut p = Tokenizer::from("<g></svg>");
+    /// p.expect_element_start("g").unwrap();
+    /// p.next(); // >
+    /// assert_eq!(p.expect_close("g").unwrap_err().to_string(),
+    ///            "expected 'g' close tag not 'svg' at 1:4");
+    /// ```
+    ///
+    /// [`expect_element_start`]: #method.expect_element_start
+    pub fn expect_close(&mut self, name: &str) -> Result<(), Error> {
+        let (token, range, next) = self.next_expected(TokenType::ElementClose)?;
+        match token {
+            Token::ElementEnd(ElementEnd::Close(prefix, local)) => {
+                if !is_qname_eq(prefix, local, name) {
+                    let pos = self.gen_text_pos_from(range.start);
+                    let actual = qname_to_string(prefix, local);
+                    return Err(Error::UnexpectedCloseTag(name.into(), actual, pos, range));
+                }
+
+                *self = next;
+                Ok(())
+            }
+            _ => Err(self.unexpected_token(&token, range)),
+        }
+    }
+
+    // Returns the next token, its range and the tokenizer after it.
+    fn next_expected(&self, token_type: TokenType)
+        -> Result<(Token<'a>, Range<usize>, Tokenizer<'a>), Error>
+    {
+        let start = self.pos();
+        let mut next = self.clone();
+        match next.next() {
+            Some(Ok(token)) => {
+                let range = token_range(self.stream.span().full_str(), &token, start, next.pos());
+                Ok((token, range, next))
+            }
+            Some(Err(e)) => Err(e),
+            None => {
+                let pos = self.gen_text_pos_from(start);
+                let e = StreamError::UnexpectedEndOfStream;
+                Err(Error::InvalidToken(token_type, pos, Some(Box::new(e)), start..start))
+            }
+        }
+    }
+
+    fn unexpected_token(&self, token: &Token, range: Range<usize>) -> Error {
+        let pos = self.gen_text_pos_from(range.start);
+        Error::UnexpectedToken(token.token_type(), pos, range)
+    }
+}
+
+// Spaces around the token are skipped by the tokenizer,
+// but they are not a part of the token, unless it's a text.
+fn token_range(text: &str, token: &Token, start: usize, end: usize) -> Range<usize> {
+    match *token {
+        Token::Text(..) | Token::Whitespaces(..) => start..end,
+        _ => {
+            let bytes = &text.as_bytes()[start..end];
+            let from = bytes.iter().position(|c| !c.is_xml_space()).unwrap_or(0);
+            let to = bytes.iter().rposition(|c| !c.is_xml_space()).map(|i| i + 1).unwrap_or(0);
+            (start + from)..(start + to)
+        }
+    }
+}
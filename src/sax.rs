@@ -0,0 +1,346 @@
+use {
+    ElementEnd,
+    EntityDefinition,
+    Error,
+    ExternalId,
+    Stream,
+    StrSpan,
+    TextPos,
+    Token,
+    TokenType,
+    Tokenizer,
+};
+
+
+/// An element attribute.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Attribute<'a> {
+    prefix: StrSpan<'a>,
+    local: StrSpan<'a>,
+    value: StrSpan<'a>,
+}
+
+impl<'a> Attribute<'a> {
+    /// Constructs a new `Attribute`.
+    pub fn new(prefix: StrSpan<'a>, local: StrSpan<'a>, value: StrSpan<'a>) -> Self {
+        Attribute { prefix, local, value }
+    }
+
+    /// Returns the attribute name prefix.
+    ///
+    /// Empty when the name is not prefixed.
+    pub fn prefix(&self) -> StrSpan<'a> {
+        self.prefix
+    }
+
+    /// Returns the attribute name local part.
+    pub fn local(&self) -> StrSpan<'a> {
+        self.local
+    }
+
+    /// Returns the attribute value as is.
+    pub fn value(&self) -> StrSpan<'a> {
+        self.value
+    }
+
+    /// Checks that the attribute has the specified qualified name.
+    ///
+    /// `name` can be either `local` or `prefix:local`.
+    pub fn has_name(&self, name: &str) -> bool {
+        is_qname_eq(self.prefix, self.local, name)
+    }
+}
+
+
+/// A SAX-like events handler.
+///
+/// All methods have an empty default implementation,
+/// so only the required ones should be implemented.
+///
+/// Used by [`Tokenizer::parse_with`].
+///
+/// [`Tokenizer::parse_with`]: struct.Tokenizer.html#method.parse_with
+#[allow(unused_variables)]
+pub trait Handler<'a> {
+    /// Called on the XML declaration.
+    fn declaration(&mut self, version: StrSpan<'a>, encoding: Option<StrSpan<'a>>,
+                   standalone: Option<bool>) {}
+
+    /// Called on the DOCTYPE declaration.
+    fn doctype(&mut self, name: StrSpan<'a>, external_id: Option<ExternalId<'a>>) {}
+
+    /// Called on the ENTITY declaration inside the DTD.
+    fn entity_declaration(&mut self, name: StrSpan<'a>, definition: EntityDefinition<'a>) {}
+
+    /// Called on the element start, after all attributes were parsed.
+    fn start_element(&mut self, prefix: StrSpan<'a>, local: StrSpan<'a>,
+                     attributes: &[Attribute<'a>]) {}
+
+    /// Called on the element end.
+    ///
+    /// Always paired with `start_element`, even for empty elements.
+    fn end_element(&mut self, prefix: StrSpan<'a>, local: StrSpan<'a>) {}
+
+    /// Called on the character data, including whitespaces.
+    ///
+    /// Text is passed as is, without unescaping.
+    fn characters(&mut self, text: StrSpan<'a>) {}
+
+    /// Called on the CDATA section.
+    ///
+    /// By default, forwards the data to `characters`.
+    fn cdata(&mut self, text: StrSpan<'a>) {
+        self.characters(text);
+    }
+
+    /// Called on the comment.
+    fn comment(&mut self, text: StrSpan<'a>) {}
+
+    /// Called on the processing instruction.
+    fn processing_instruction(&mut self, target: StrSpan<'a>, content: Option<StrSpan<'a>>) {}
+}
+
+
+impl<'a> Tokenizer<'a> {
+    /// Parses the whole document and passes events to the `handler`.
+    ///
+    /// Unlike the iterator interface, checks that close tags are matching
+    /// the open ones.
+    ///
+    /// # Errors
+    ///
+    /// In addition to the tokenizer errors, can produce
+    /// `UnexpectedCloseTag` and `UnclosedTag` errors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Attribute, Handler, StrSpan, Tokenizer};
+    ///
+    /// struct Counter(usize);
+    ///
+    /// impl<'a> Handler<'a> for Counter {
+    ///     fn start_element(&mut self, _: StrSpan<'a>, _: StrSpan<'a>, _: &[Attribute<'a>]) {
+    ///         self.0 += 1;
+    ///     }
+    /// }
+    ///
+    /// let mut counter = Counter(0);
+    /// Tokenizer::from("<a><b/><c/></a>").parse_with(&mut counter).unwrap();
+    /// assert_eq!(counter.0, 3);
+    /// ```
+    pub fn parse_with<H: Handler<'a>>(mut self, handler: &mut H) -> Result<(), Error> {
+        let mut stack = TagStack::new();
+        let mut attributes = Vec::new();
+        let mut element = None;
+
+        loop {
+            let token_start = self.pos();
+            let token = match self.next() {
+                Some(token) => token?,
+                None => break,
+            };
+
+            match token {
+                Token::Declaration(version, encoding, standalone) => {
+                    handler.declaration(version, encoding, standalone);
+                }
+                Token::DtdStart(name, external_id) | Token::EmptyDtd(name, external_id) => {
+                    handler.doctype(name, external_id);
+                }
+                Token::EntityDeclaration(name, definition) => {
+                    handler.entity_declaration(name, definition);
+                }
+                Token::DtdEnd => {}
+                Token::ElementStart(prefix, local) => {
+                    element = Some((prefix, local, self.markup_start(token_start)));
+                    attributes.clear();
+                }
+                Token::Attribute((prefix, local), value) => {
+                    attributes.push(Attribute::new(prefix, local, value));
+                }
+                Token::ElementEnd(ElementEnd::Open) => {
+                    if let Some((prefix, local, start)) = element.take() {
+                        handler.start_element(prefix, local, &attributes);
+                        stack.push(prefix, local, start, ());
+                    }
+                }
+                Token::ElementEnd(ElementEnd::Empty) => {
+                    if let Some((prefix, local, _)) = element.take() {
+                        handler.start_element(prefix, local, &attributes);
+                        handler.end_element(prefix, local);
+                    }
+                }
+                Token::ElementEnd(ElementEnd::Close(prefix, local)) => {
+                    stack.close(&self, prefix, local, token_start)?;
+                    handler.end_element(prefix, local);
+                }
+                Token::Text(text) | Token::Whitespaces(text) => {
+                    handler.characters(text);
+                }
+                Token::Cdata(text) => {
+                    handler.cdata(text);
+                }
+                Token::Comment(text) => {
+                    handler.comment(text);
+                }
+                Token::ProcessingInstruction(target, content) => {
+                    handler.processing_instruction(target, content);
+                }
+            }
+        }
+
+        stack.finish(&self)?;
+
+        Ok(())
+    }
+}
+
+
+impl<'a> Tokenizer<'a> {
+    // Calculates a position at the absolute byte offset.
+    //
+    // `Tokenizer::stream` positions are relative to the span start,
+    // so a new stream for the whole text is used.
+    pub(crate) fn gen_text_pos_from(&self, pos: usize) -> TextPos {
+        let mut s = Stream::from(self.stream.span().full_str());
+        if self.stream.is_xml11_mode() {
+            s.enable_xml11_mode();
+        }
+        if self.stream.is_cr_line_breaks_mode() {
+            s.enable_cr_line_breaks_mode();
+        }
+
+        s.gen_text_pos_from(pos)
+    }
+
+    // Returns the markup start of the token that starts at `token_start`
+    // and was just parsed.
+    //
+    // Some states skip whitespaces before a token, e.g. before the root element.
+    pub(crate) fn markup_start(&self, token_start: usize) -> usize {
+        let text = self.stream.span().full_str();
+        match text[token_start..self.pos()].find('<') {
+            Some(idx) => token_start + idx,
+            None => token_start,
+        }
+    }
+}
+
+
+// An open element.
+pub(crate) struct OpenTag<'a, T> {
+    pub(crate) prefix: StrSpan<'a>,
+    pub(crate) local: StrSpan<'a>,
+    // The start tag position.
+    pub(crate) start: usize,
+    pub(crate) data: T,
+}
+
+// A stack of open elements that checks that close tags are matching the open ones.
+//
+// `T` is an additional data stored for each element.
+pub(crate) struct TagStack<'a, T> {
+    tags: Vec<OpenTag<'a, T>>,
+}
+
+impl<'a, T> TagStack<'a, T> {
+    pub(crate) fn new() -> Self {
+        TagStack { tags: Vec::new() }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    pub(crate) fn push(&mut self, prefix: StrSpan<'a>, local: StrSpan<'a>, start: usize, data: T) {
+        self.tags.push(OpenTag { prefix, local, start, data });
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<OpenTag<'a, T>> {
+        self.tags.pop()
+    }
+
+    pub(crate) fn last(&self) -> Option<&OpenTag<'a, T>> {
+        self.tags.last()
+    }
+
+    pub(crate) fn last_mut(&mut self) -> Option<&mut OpenTag<'a, T>> {
+        self.tags.last_mut()
+    }
+
+    // Pops the element closed by the close tag that starts at `start`.
+    //
+    // Must be called right after the close tag was parsed by the `tokenizer`.
+    pub(crate) fn close(
+        &mut self,
+        tokenizer: &Tokenizer,
+        prefix: StrSpan,
+        local: StrSpan,
+        start: usize,
+    ) -> Result<OpenTag<'a, T>, Error> {
+        let range = start..tokenizer.pos();
+        let tag = match self.tags.pop() {
+            Some(tag) => tag,
+            None => {
+                // Can happen only in the fragment mode.
+                let pos = tokenizer.gen_text_pos_from(start);
+                return Err(Error::UnexpectedToken(TokenType::ElementClose, pos, range));
+            }
+        };
+
+        if tag.prefix.to_str() != prefix.to_str() || tag.local.to_str() != local.to_str() {
+            let pos = tokenizer.gen_text_pos_from(start);
+            return Err(Error::UnexpectedCloseTag(
+                qname_to_string(tag.prefix, tag.local),
+                qname_to_string(prefix, local),
+                pos,
+                range,
+            ));
+        }
+
+        Ok(tag)
+    }
+
+    // Checks that all elements were closed at the end of the document.
+    pub(crate) fn finish(&self, tokenizer: &Tokenizer) -> Result<(), Error> {
+        if let Some(tag) = self.tags.last() {
+            let pos = tokenizer.gen_text_pos_from(tag.start);
+            let name = qname_to_string(tag.prefix, tag.local);
+            return Err(Error::UnclosedTag(name, pos, tag.start..tag.local.end()));
+        }
+
+        Ok(())
+    }
+}
+
+
+pub(crate) fn is_namespace_declaration(attr: &Attribute) -> bool {
+    if attr.prefix().is_empty() {
+        attr.local().to_str() == "xmlns"
+    } else {
+        attr.prefix().to_str() == "xmlns"
+    }
+}
+
+pub(crate) fn is_qname_eq(prefix: StrSpan, local: StrSpan, name: &str) -> bool {
+    let prefix = prefix.to_str();
+    let local = local.to_str();
+
+    if prefix.is_empty() {
+        return local == name;
+    }
+
+    name.len() == prefix.len() + 1 + local.len()
+        && name.starts_with(prefix)
+        && name[prefix.len()..].starts_with(':')
+        && name.ends_with(local)
+}
+
+pub(crate) fn qname_to_string(prefix: StrSpan, local: StrSpan) -> Box<str> {
+    if prefix.is_empty() {
+        local.to_str().into()
+    } else {
+        format!("{}:{}", prefix, local).into_boxed_str()
+    }
+}
@@ -0,0 +1,155 @@
+// Fast delimiters search.
+//
+// By default, a portable word-at-a-time (SWAR) implementation is used,
+// which doesn't require any unsafe code.
+// With the `memchr` build feature, the SIMD implementation from
+// the `memchr` crate will be used instead.
+
+#[cfg(feature = "memchr")]
+use memchr;
+
+/// Returns the index of the first `needle` byte in the `haystack`.
+#[cfg(feature = "memchr")]
+#[inline]
+pub fn find_byte(needle: u8, haystack: &[u8]) -> Option<usize> {
+    memchr::memchr(needle, haystack)
+}
+
+/// Returns the index of the first `needle` byte in the `haystack`.
+#[cfg(not(feature = "memchr"))]
+#[inline]
+pub fn find_byte(needle: u8, haystack: &[u8]) -> Option<usize> {
+    const LO: u64 = 0x0101_0101_0101_0101;
+    const HI: u64 = 0x8080_8080_8080_8080;
+
+    let pattern = LO * u64::from(needle);
+
+    // Skip whole words until one of them contains the needle.
+    // `(v - LO) & !v & HI` is non-zero only when `v` has a zero byte.
+    let mut idx = 0;
+    for chunk in haystack.chunks_exact(8) {
+        let word = u64::from_le_bytes([
+            chunk[0], chunk[1], chunk[2], chunk[3],
+            chunk[4], chunk[5], chunk[6], chunk[7],
+        ]) ^ pattern;
+
+        if word.wrapping_sub(LO) & !word & HI != 0 {
+            break;
+        }
+
+        idx += 8;
+    }
+
+    haystack[idx..].iter().position(|c| *c == needle).map(|i| idx + i)
+}
+
+/// Returns the index of the first `needle` string in the `haystack`.
+///
+/// `needle` must not be empty.
+#[inline]
+pub fn find_string(needle: &[u8], haystack: &[u8]) -> Option<usize> {
+    debug_assert!(!needle.is_empty());
+
+    let mut idx = 0;
+    while let Some(i) = find_byte(needle[0], &haystack[idx..]) {
+        idx += i;
+        if haystack[idx..].starts_with(needle) {
+            return Some(idx);
+        }
+
+        idx += 1;
+    }
+
+    None
+}
+
+/// Returns the byte length of the longest prefix that contains only
+/// [Char](https://www.w3.org/TR/xml/#NT-Char) chars.
+///
+/// In the XML 1.1 mode, [RestrictedChar]s are not allowed too.
+///
+/// [RestrictedChar]: https://www.w3.org/TR/xml11/#NT-RestrictedChar
+#[inline]
+pub fn xml_chars_prefix_len(text: &str, xml11: bool) -> usize {
+    use XmlCharExt;
+    use xmlchar::{is_xml11_char, is_xml11_restricted_char};
+
+    // Most of the text is ASCII, so check bytes first
+    // and decode only non-ASCII chars.
+    // DEL is a restricted char in XML 1.1.
+    let ascii_end = if xml11 { 0x7F } else { 0x80 };
+    let bytes = text.as_bytes();
+    let mut len = 0;
+    loop {
+        len += bytes[len..].iter()
+            .position(|&c| (c < 0x20 && !matches!(c, b'\t' | b'\n' | b'\r')) || c >= ascii_end)
+            .unwrap_or(bytes.len() - len);
+
+        let c = match text[len..].chars().next() {
+            Some(c) => c,
+            None => break,
+        };
+
+        let is_valid = if c.is_ascii() {
+            // Control chars and DEL are already rejected.
+            false
+        } else if xml11 {
+            is_xml11_char(c) && !is_xml11_restricted_char(c)
+        } else {
+            c.is_xml_char()
+        };
+
+        if !is_valid {
+            break;
+        }
+
+        len += c.len_utf8();
+    }
+
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_byte_1() {
+        let text = b"0123456789abcdefghijklmnopqrstuv<";
+        for i in 0..text.len() {
+            assert_eq!(find_byte(text[i], text), Some(i));
+            assert_eq!(find_byte(text[i], &text[i..]), Some(0));
+        }
+
+        assert_eq!(find_byte(b'<', b""), None);
+        assert_eq!(find_byte(b'x', text), None);
+        assert_eq!(find_byte(0x80, "ЁЁЁЁЁЁЁЁЁЁ".as_bytes()), None);
+    }
+
+    #[test]
+    fn find_string_1() {
+        assert_eq!(find_string(b"]]>", b"text ] ]] ]]]> ]]>"), Some(11));
+        assert_eq!(find_string(b"-->", b"--->"), Some(1));
+        assert_eq!(find_string(b"-->", b"--"), None);
+    }
+
+    #[test]
+    fn xml_chars_prefix_len_1() {
+        assert_eq!(xml_chars_prefix_len("text\n\tтекст", false), 16);
+        assert_eq!(xml_chars_prefix_len("te\u{1}xt", false), 2);
+        assert_eq!(xml_chars_prefix_len("текст\u{FFFE}", false), 10);
+        assert_eq!(xml_chars_prefix_len("te\u{7F}\u{85}\u{86}", false), 7);
+        assert_eq!(xml_chars_prefix_len("a\r\n\tb\nc\u{1}", false), 7);
+        assert_eq!(xml_chars_prefix_len("т\nт\u{7F}\nт\u{1}", false), 9);
+    }
+
+    #[test]
+    fn xml_chars_prefix_len_2() {
+        assert_eq!(xml_chars_prefix_len("text\u{85}\u{2028}", true), 9);
+        assert_eq!(xml_chars_prefix_len("te\u{1}xt", true), 2);
+        assert_eq!(xml_chars_prefix_len("te\u{7F}xt", true), 2);
+        assert_eq!(xml_chars_prefix_len("te\u{85}\u{86}", true), 4);
+        assert_eq!(xml_chars_prefix_len("a\r\n\tb\nc\u{7F}", true), 7);
+        assert_eq!(xml_chars_prefix_len("т\u{85}\nт\u{80}", true), 7);
+    }
+}
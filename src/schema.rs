@@ -0,0 +1,1623 @@
+//! An [XML Schema](https://www.w3.org/TR/xmlschema-1/) subset validator.
+//!
+//! A schema is loaded with the [`tree`] and compiled into a [`Schema`],
+//! which is then used to validate a token stream with [`Tokenizer::validate_schema`].
+//! No tree is built for the validated document.
+//!
+//! Supported:
+//!
+//! - Global and local element declarations, including `ref`, `minOccurs` and `maxOccurs`.
+//! - Named and anonymous complex types with `sequence`, `choice`, `all`, `any`,
+//!   `mixed` content, `simpleContent` extensions, attributes and `anyAttribute`.
+//! - Named and anonymous simple types with restrictions and lists.
+//!   Supported facets: `enumeration`, `length`, `minLength`, `maxLength`,
+//!   `minInclusive`, `maxInclusive`, `minExclusive`, `maxExclusive`,
+//!   `totalDigits`, `fractionDigits` and `whiteSpace`, which is ignored.
+//! - Built-in types from [`string`] to [`anyURI`], parsed by the [`Stream`] datatype parsers.
+//! - Namespaces: `targetNamespace`, `elementFormDefault`, `attributeFormDefault` and `form`.
+//!
+//! Everything else, like `pattern`, `group`, `import` or `complexContent`,
+//! produces a [`SchemaError::Unsupported`] error on load, so a schema
+//! is never validated partially.
+//!
+//! Wildcards and `anyType` are processed laxly: an element is validated
+//! only when it has a global declaration.
+//!
+//! # Example
+//!
+//! ```
+//! use xmlparser::Tokenizer;
+//! use xmlparser::schema::Schema;
+//!
+//! let schema = Schema::parse("
+//! <xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema'>
+//!     <xs:element name='order'>
+//!         <xs:complexType>
+//!             <xs:sequence>
+//!                 <xs:element name='item' type='xs:string' maxOccurs='unbounded'/>
+//!             </xs:sequence>
+//!             <xs:attribute name='id' type='xs:positiveInteger' use='required'/>
+//!         </xs:complexType>
+//!     </xs:element>
+//! </xs:schema>").unwrap();
+//!
+//! let text = "<order id='0'><item>Book</item></order>";
+//! let errors: Vec<_> = Tokenizer::from(text).validate_schema(&schema)
+//!     .filter_map(|t| t.err())
+//!     .map(|e| e.to_string())
+//!     .collect();
+//! assert_eq!(errors, vec!["attribute 'id' value must be a valid xs:positiveInteger at 1:8"]);
+//! ```
+//!
+//! [`tree`]: ../tree/index.html
+//! [`Schema`]: struct.Schema.html
+//! [`Tokenizer::validate_schema`]: ../struct.Tokenizer.html#method.validate_schema
+//! [`SchemaError::Unsupported`]: enum.SchemaError.html#variant.Unsupported
+//! [`string`]: https://www.w3.org/TR/xmlschema-2/#string
+//! [`anyURI`]: https://www.w3.org/TR/xmlschema-2/#anyURI
+//! [`Stream`]: ../struct.Stream.html
+
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::iter;
+use std::mem;
+use std::ops::Range;
+
+use automaton::{Automaton, Particle};
+use sax::{is_namespace_declaration, OpenTag};
+use text::unescape;
+use tree::{Document, Node};
+use validate::{qname_str, Context, Rules, Validator};
+use {
+    Attribute,
+    CharsKind,
+    ElementEnd,
+    Error,
+    Stream,
+    StreamError,
+    StrSpan,
+    TextPos,
+    Token,
+    Tokenizer,
+    ValidityError,
+    ValueList,
+    XmlByteExt,
+    XmlCharExt,
+};
+
+const XS_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema";
+const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+// The maximum number of particles in a content model after `minOccurs` and `maxOccurs` expansion.
+const MAX_PARTICLES: usize = 1000;
+
+
+/// A schema loading error.
+#[derive(Debug)]
+pub enum SchemaError {
+    /// The schema document is not a well-formed XML.
+    Xml(Error),
+
+    /// An unsupported schema component or attribute.
+    ///
+    /// Contains its qualified name as written in the schema.
+    Unsupported(String, TextPos),
+
+    /// A schema element in an unexpected place.
+    UnexpectedElement(String, TextPos),
+
+    /// A required attribute is missing.
+    ///
+    /// Contains the element and the attribute names.
+    MissingAttribute(String, String, TextPos),
+
+    /// An attribute has an invalid value.
+    InvalidAttribute(String, TextPos),
+
+    /// A reference to an undefined type or element.
+    ///
+    /// Contains the reference as written in the schema.
+    UnknownReference(String, TextPos),
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SchemaError::Xml(ref e) => {
+                write!(f, "{}", e)
+            }
+            SchemaError::Unsupported(ref name, pos) => {
+                write!(f, "'{}' is not supported at {}", name, pos)
+            }
+            SchemaError::UnexpectedElement(ref name, pos) => {
+                write!(f, "unexpected schema element '{}' at {}", name, pos)
+            }
+            SchemaError::MissingAttribute(ref element, ref name, pos) => {
+                write!(f, "'{}' must have the '{}' attribute at {}", element, name, pos)
+            }
+            SchemaError::InvalidAttribute(ref name, pos) => {
+                write!(f, "invalid '{}' attribute value at {}", name, pos)
+            }
+            SchemaError::UnknownReference(ref name, pos) => {
+                write!(f, "unknown reference '{}' at {}", name, pos)
+            }
+        }
+    }
+}
+
+impl error::Error for SchemaError {
+    fn description(&self) -> &str {
+        "a schema loading error"
+    }
+}
+
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Builtin {
+    AnySimpleType,
+    String,
+    NormalizedString,
+    Token,
+    Language,
+    Name,
+    NcName,
+    NmToken,
+    Id,
+    IdRef,
+    Boolean,
+    Decimal,
+    Float,
+    Double,
+    Integer,
+    Long,
+    Int,
+    Short,
+    Byte,
+    NonNegativeInteger,
+    PositiveInteger,
+    NonPositiveInteger,
+    NegativeInteger,
+    UnsignedLong,
+    UnsignedInt,
+    UnsignedShort,
+    UnsignedByte,
+    Date,
+    DateTime,
+    Duration,
+    QName,
+    AnyUri,
+}
+
+const BUILTINS: &[(&str, Builtin)] = &[
+    ("anySimpleType", Builtin::AnySimpleType),
+    ("string", Builtin::String),
+    ("normalizedString", Builtin::NormalizedString),
+    ("token", Builtin::Token),
+    ("language", Builtin::Language),
+    ("Name", Builtin::Name),
+    ("NCName", Builtin::NcName),
+    ("NMTOKEN", Builtin::NmToken),
+    ("ID", Builtin::Id),
+    ("IDREF", Builtin::IdRef),
+    ("boolean", Builtin::Boolean),
+    ("decimal", Builtin::Decimal),
+    ("float", Builtin::Float),
+    ("double", Builtin::Double),
+    ("integer", Builtin::Integer),
+    ("long", Builtin::Long),
+    ("int", Builtin::Int),
+    ("short", Builtin::Short),
+    ("byte", Builtin::Byte),
+    ("nonNegativeInteger", Builtin::NonNegativeInteger),
+    ("positiveInteger", Builtin::PositiveInteger),
+    ("nonPositiveInteger", Builtin::NonPositiveInteger),
+    ("negativeInteger", Builtin::NegativeInteger),
+    ("unsignedLong", Builtin::UnsignedLong),
+    ("unsignedInt", Builtin::UnsignedInt),
+    ("unsignedShort", Builtin::UnsignedShort),
+    ("unsignedByte", Builtin::UnsignedByte),
+    ("date", Builtin::Date),
+    ("dateTime", Builtin::DateTime),
+    ("duration", Builtin::Duration),
+    ("QName", Builtin::QName),
+    ("anyURI", Builtin::AnyUri),
+];
+
+impl Builtin {
+    fn from_name(name: &str) -> Option<Builtin> {
+        BUILTINS.iter().find(|&&(n, _)| n == name).map(|&(_, b)| b)
+    }
+
+    fn name(self) -> &'static str {
+        BUILTINS.iter().find(|&&(_, b)| b == self).map(|&(n, _)| n).unwrap_or("")
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, Builtin::Decimal | Builtin::Float | Builtin::Double) || self.integer_range().is_some()
+    }
+
+    // `unsignedLong` and `nonNegativeInteger` are limited by `i64`.
+    fn integer_range(self) -> Option<(i64, i64)> {
+        let range = match self {
+            Builtin::Integer | Builtin::Long => (i64::MIN, i64::MAX),
+            Builtin::Int => (i32::MIN as i64, i32::MAX as i64),
+            Builtin::Short => (i16::MIN as i64, i16::MAX as i64),
+            Builtin::Byte => (i8::MIN as i64, i8::MAX as i64),
+            Builtin::NonNegativeInteger | Builtin::UnsignedLong => (0, i64::MAX),
+            Builtin::PositiveInteger => (1, i64::MAX),
+            Builtin::NonPositiveInteger => (i64::MIN, 0),
+            Builtin::NegativeInteger => (i64::MIN, -1),
+            Builtin::UnsignedInt => (0, u32::MAX as i64),
+            Builtin::UnsignedShort => (0, u16::MAX as i64),
+            Builtin::UnsignedByte => (0, u8::MAX as i64),
+            _ => return None,
+        };
+
+        Some(range)
+    }
+
+    // Returns a numeric value for numeric types.
+    fn parse(self, value: &str) -> Result<Option<f64>, StreamError> {
+        let span = StrSpan::from(value);
+        if let Some((min, max)) = self.integer_range() {
+            let n = Stream::parse_value(span, Stream::consume_integer)?;
+            if n < min || n > max {
+                return Err(StreamError::InvalidValue(self.name(), TextPos::new(1, 1)));
+            }
+
+            return Ok(Some(n as f64));
+        }
+
+        match self {
+            Builtin::AnySimpleType
+            | Builtin::String
+            | Builtin::NormalizedString
+            | Builtin::Token => {}
+            Builtin::Language => {
+                let is_valid = !value.is_empty()
+                    && value.split('-').all(|s| !s.is_empty() && s.bytes().all(|c| c.is_ascii_alphanumeric()));
+                if !is_valid {
+                    return Err(StreamError::InvalidValue(self.name(), TextPos::new(1, 1)));
+                }
+            }
+            Builtin::Name | Builtin::NcName | Builtin::Id | Builtin::IdRef => {
+                let mut chars = value.chars();
+                let is_valid = chars.next().map_or(false, |c| c.is_xml_name_start())
+                    && chars.all(|c| c.is_xml_name())
+                    && (self == Builtin::Name || !value.contains(':'));
+                if !is_valid {
+                    return Err(StreamError::InvalidName);
+                }
+            }
+            Builtin::NmToken if value.is_empty() || !value.chars().all(|c| c.is_xml_name()) => {
+                return Err(StreamError::InvalidName);
+            }
+            Builtin::Boolean => {
+                Stream::parse_value(span, Stream::consume_boolean).map(|_| ())?;
+            }
+            Builtin::Decimal => {
+                return Stream::parse_value(span, Stream::consume_decimal).map(Some);
+            }
+            Builtin::Float | Builtin::Double => {
+                return Stream::parse_value(span, Stream::consume_double).map(Some);
+            }
+            Builtin::Date => {
+                Stream::parse_value(span, Stream::consume_date).map(|_| ())?;
+            }
+            Builtin::DateTime => {
+                Stream::parse_value(span, Stream::consume_date_time).map(|_| ())?;
+            }
+            Builtin::Duration => {
+                Stream::parse_value(span, Stream::consume_duration).map(|_| ())?;
+            }
+            Builtin::QName => {
+                Stream::parse_value(span, Stream::consume_xs_qname).map(|_| ())?;
+            }
+            Builtin::AnyUri => {
+                Stream::parse_value(span, Stream::consume_any_uri).map(|_| ())?;
+            }
+            _ => {}
+        }
+
+        Ok(None)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Facet {
+    Enumeration(Vec<String>),
+    Length(usize),
+    MinLength(usize),
+    MaxLength(usize),
+    MinInclusive(f64),
+    MaxInclusive(f64),
+    MinExclusive(f64),
+    MaxExclusive(f64),
+    TotalDigits(usize),
+    FractionDigits(usize),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Variety {
+    Atomic(Builtin),
+    List(Box<SimpleType>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct SimpleType {
+    variety: Variety,
+    facets: Vec<Facet>,
+}
+
+impl SimpleType {
+    fn builtin(builtin: Builtin) -> Self {
+        SimpleType { variety: Variety::Atomic(builtin), facets: Vec::new() }
+    }
+
+    fn base(&self) -> Option<Builtin> {
+        match self.variety {
+            Variety::Atomic(b) => Some(b),
+            Variety::List(_) => None,
+        }
+    }
+
+    // https://www.w3.org/TR/xmlschema-2/#rf-whiteSpace
+    fn normalize<'v>(&self, value: &'v str) -> Cow<'v, str> {
+        match self.base() {
+            Some(Builtin::AnySimpleType) | Some(Builtin::String) => Cow::Borrowed(value),
+            Some(Builtin::NormalizedString) => {
+                if value.bytes().any(|c| c.is_xml_space() && c != b' ') {
+                    Cow::Owned(value.replace(['\t', '\n', '\r'], " "))
+                } else {
+                    Cow::Borrowed(value)
+                }
+            }
+            _ => {
+                let list: Vec<&str> = ValueList::from(value).map(|s| s.to_str()).collect();
+                if list.len() == 1 && list[0].len() == value.len() {
+                    Cow::Borrowed(value)
+                } else {
+                    Cow::Owned(list.join(" "))
+                }
+            }
+        }
+    }
+
+    // Returns a description of the expected value on error.
+    fn check(&self, value: &str) -> Result<(), Box<str>> {
+        let value = self.normalize(value);
+        let (len, number) = match self.variety {
+            Variety::Atomic(b) => {
+                let number = b.parse(&value).map_err(|_| self.expected())?;
+                (value.chars().count(), number)
+            }
+            Variety::List(ref item) => {
+                let mut len = 0;
+                for v in ValueList::from(value.as_ref()).map(|s| s.to_str()) {
+                    item.check(v)?;
+                    len += 1;
+                }
+
+                (len, None)
+            }
+        };
+
+        for facet in &self.facets {
+            let is_valid = match *facet {
+                Facet::Enumeration(ref list) => list.iter().any(|v| *v == value),
+                Facet::Length(n) => len == n,
+                Facet::MinLength(n) => len >= n,
+                Facet::MaxLength(n) => len <= n,
+                Facet::MinInclusive(n) => number.map_or(true, |v| v >= n),
+                Facet::MaxInclusive(n) => number.map_or(true, |v| v <= n),
+                Facet::MinExclusive(n) => number.map_or(true, |v| v > n),
+                Facet::MaxExclusive(n) => number.map_or(true, |v| v < n),
+                Facet::TotalDigits(n) => count_digits(&value).0 <= n,
+                Facet::FractionDigits(n) => count_digits(&value).1 <= n,
+            };
+
+            if !is_valid {
+                return Err(self.facet_expected(facet));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn expected(&self) -> Box<str> {
+        match self.variety {
+            Variety::Atomic(b) => format!("a valid xs:{}", b.name()).into(),
+            Variety::List(_) => "a list of values".into(),
+        }
+    }
+
+    fn facet_expected(&self, facet: &Facet) -> Box<str> {
+        let unit = if self.base().is_some() { "characters" } else { "items" };
+        let s = match *facet {
+            Facet::Enumeration(ref list) => {
+                let list: Vec<String> = list.iter().map(|s| format!("'{}'", s)).collect();
+                format!("one of {}", list.join(", "))
+            }
+            Facet::Length(n) => format!("exactly {} {} long", n, unit),
+            Facet::MinLength(n) => format!("at least {} {} long", n, unit),
+            Facet::MaxLength(n) => format!("at most {} {} long", n, unit),
+            Facet::MinInclusive(n) => format!("greater than or equal to {}", n),
+            Facet::MaxInclusive(n) => format!("less than or equal to {}", n),
+            Facet::MinExclusive(n) => format!("greater than {}", n),
+            Facet::MaxExclusive(n) => format!("less than {}", n),
+            Facet::TotalDigits(n) => format!("at most {} digits long", n),
+            Facet::FractionDigits(n) => format!("at most {} fraction digits long", n),
+        };
+
+        s.into()
+    }
+}
+
+// Returns the number of significant digits and fraction digits.
+fn count_digits(value: &str) -> (usize, usize) {
+    let value = value.trim_start_matches(['+', '-']);
+    let (int, frac) = match value.find('.') {
+        Some(idx) => (&value[..idx], &value[idx + 1..]),
+        None => (value, ""),
+    };
+
+    let int = int.trim_start_matches('0');
+    let frac = frac.trim_end_matches('0');
+    (int.len() + frac.len(), frac.len())
+}
+
+
+#[derive(Clone, PartialEq, Debug)]
+struct Name {
+    namespace: Option<String>,
+    local: String,
+}
+
+impl Name {
+    fn matches(&self, namespace: Option<&str>, local: &str) -> bool {
+        self.namespace.as_deref() == namespace && self.local == local
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum TypeRef {
+    // `xs:anyType`.
+    Any,
+    Simple(SimpleType),
+    // An index in the schema complex types.
+    Complex(usize),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct ElementDecl {
+    name: Name,
+    kind: TypeRef,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct AttributeDecl {
+    name: Name,
+    kind: SimpleType,
+    required: bool,
+    fixed: Option<String>,
+}
+
+// An `xs:any` namespace constraint.
+#[derive(Clone, PartialEq, Debug)]
+enum Wildcard {
+    Any,
+    // Any namespace except the target one and no namespace.
+    Other(Option<String>),
+    List(Vec<Option<String>>),
+}
+
+impl Wildcard {
+    fn matches(&self, namespace: Option<&str>) -> bool {
+        match *self {
+            Wildcard::Any => true,
+            Wildcard::Other(ref target) => namespace.is_some() && namespace != target.as_deref(),
+            Wildcard::List(ref list) => list.iter().any(|ns| ns.as_deref() == namespace),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Symbol {
+    // An index in the schema elements.
+    Element(usize),
+    Any(Wildcard),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Content {
+    Empty,
+    Simple(SimpleType),
+    Elements(Automaton<Symbol>),
+    // Element declarations and whether they are required.
+    All(Vec<(usize, bool)>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct ComplexType {
+    content: Content,
+    mixed: bool,
+    attributes: Vec<AttributeDecl>,
+    any_attribute: bool,
+}
+
+impl ComplexType {
+    fn new() -> Self {
+        ComplexType {
+            content: Content::Empty,
+            mixed: false,
+            attributes: Vec::new(),
+            any_attribute: false,
+        }
+    }
+}
+
+
+/// A compiled XML Schema.
+///
+/// See the [module documentation](index.html) for the supported subset.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Schema {
+    elements: Vec<ElementDecl>,
+    globals: Vec<usize>,
+    types: Vec<ComplexType>,
+}
+
+impl Schema {
+    /// Parses and compiles a schema document.
+    ///
+    /// `xs:include` and `xs:import` are not supported, so a schema must be
+    /// a single document.
+    pub fn parse(text: &str) -> Result<Schema, SchemaError> {
+        let doc = Document::parse(text).map_err(SchemaError::Xml)?;
+        let root = match doc.root_element() {
+            Some(root) => root,
+            None => return Err(SchemaError::UnexpectedElement(String::new(), TextPos::new(1, 1))),
+        };
+
+        if !is_xs_element(root, "schema") {
+            return Err(SchemaError::UnexpectedElement(node_name(root), root.text_pos()));
+        }
+
+        let is_qualified = |name| root.attribute(name).map_or(false, |s| s.to_str() == "qualified");
+        let mut compiler = Compiler {
+            target: root.attribute("targetNamespace").map(|s| s.to_str().to_string()),
+            qualified_elements: is_qualified("elementFormDefault"),
+            qualified_attributes: is_qualified("attributeFormDefault"),
+            element_nodes: HashMap::new(),
+            complex_nodes: HashMap::new(),
+            simple_nodes: HashMap::new(),
+            global_ids: HashMap::new(),
+            complex_ids: HashMap::new(),
+            local_ids: HashMap::new(),
+            simple_stack: Vec::new(),
+            particles: 0,
+            schema: Schema { elements: Vec::new(), globals: Vec::new(), types: Vec::new() },
+        };
+        compiler.compile(root)?;
+
+        Ok(compiler.schema)
+    }
+
+    fn global(&self, namespace: Option<&str>, local: &str) -> Option<usize> {
+        self.globals.iter().cloned().find(|&id| self.elements[id].name.matches(namespace, local))
+    }
+}
+
+
+struct Compiler<'a, 'd> {
+    target: Option<String>,
+    qualified_elements: bool,
+    qualified_attributes: bool,
+    element_nodes: HashMap<&'a str, Node<'a, 'd>>,
+    complex_nodes: HashMap<&'a str, Node<'a, 'd>>,
+    simple_nodes: HashMap<&'a str, Node<'a, 'd>>,
+    global_ids: HashMap<&'a str, usize>,
+    complex_ids: HashMap<&'a str, usize>,
+    // Local element declarations by the node position.
+    // Particles with `maxOccurs` are compiled more than once.
+    local_ids: HashMap<usize, usize>,
+    // Simple types being compiled, to detect circular references.
+    simple_stack: Vec<&'a str>,
+    // The number of particles in the content model being compiled.
+    particles: usize,
+    schema: Schema,
+}
+
+impl<'a, 'd> Compiler<'a, 'd> {
+    fn compile(&mut self, root: Node<'a, 'd>) -> Result<(), SchemaError> {
+        for node in xs_children(root)? {
+            let map = match local_name(node) {
+                "element" => &mut self.element_nodes,
+                "complexType" => &mut self.complex_nodes,
+                "simpleType" => &mut self.simple_nodes,
+                _ => return Err(SchemaError::Unsupported(node_name(node), node.text_pos())),
+            };
+
+            map.insert(required_attribute(node, "name")?, node);
+        }
+
+        // Reserve global declarations first, since they can be referenced before definition.
+        let mut globals: Vec<_> = self.element_nodes.iter().map(|(&name, &node)| (name, node)).collect();
+        globals.sort_by_key(|&(_, node)| node.span().start());
+        for &(name, _) in &globals {
+            let id = self.schema.elements.len();
+            self.schema.elements.push(ElementDecl {
+                name: Name { namespace: self.target.clone(), local: name.to_string() },
+                kind: TypeRef::Any,
+            });
+            self.schema.globals.push(id);
+            self.global_ids.insert(name, id);
+        }
+
+        for (id, &(_, node)) in globals.iter().enumerate() {
+            check_attributes(node, &["name", "type", "id"])?;
+            self.schema.elements[id].kind = self.element_type(node)?;
+        }
+
+        // Check unused types too.
+        let mut names: Vec<_> = self.complex_nodes.iter().map(|(&name, &node)| (name, node)).collect();
+        names.sort_by_key(|&(_, node)| node.span().start());
+        for (name, _) in names {
+            self.complex_type_id(name)?;
+        }
+
+        let mut names: Vec<_> = self.simple_nodes.iter().map(|(&name, &node)| (name, node)).collect();
+        names.sort_by_key(|&(_, node)| node.span().start());
+        for (_, node) in names {
+            self.simple_type(node)?;
+        }
+
+        Ok(())
+    }
+
+    fn element_type(&mut self, node: Node<'a, 'd>) -> Result<TypeRef, SchemaError> {
+        let children = xs_children(node)?;
+        if let Some(value) = node.attribute("type") {
+            if let Some(child) = children.first() {
+                return Err(SchemaError::UnexpectedElement(node_name(*child), child.text_pos()));
+            }
+
+            return self.resolve_type(node, value.to_str());
+        }
+
+        let mut kind = TypeRef::Any;
+        for (i, child) in children.into_iter().enumerate() {
+            kind = match local_name(child) {
+                "complexType" if i == 0 => {
+                    check_attributes(child, &["mixed", "id"])?;
+                    let id = self.schema.types.len();
+                    self.schema.types.push(ComplexType::new());
+                    self.schema.types[id] = self.complex_type(child)?;
+                    TypeRef::Complex(id)
+                }
+                "simpleType" if i == 0 => {
+                    check_attributes(child, &["id"])?;
+                    TypeRef::Simple(self.simple_type(child)?)
+                }
+                "complexType" | "simpleType" => {
+                    return Err(SchemaError::UnexpectedElement(node_name(child), child.text_pos()));
+                }
+                _ => return Err(SchemaError::Unsupported(node_name(child), child.text_pos())),
+            };
+        }
+
+        Ok(kind)
+    }
+
+    fn resolve_type(&mut self, node: Node<'a, 'd>, qname: &'a str) -> Result<TypeRef, SchemaError> {
+        let unknown = || SchemaError::UnknownReference(qname.to_string(), node.text_pos());
+        let (namespace, local) = resolve_qname(node, qname).ok_or_else(unknown)?;
+
+        if namespace == Some(XS_NAMESPACE) {
+            if local == "anyType" {
+                return Ok(TypeRef::Any);
+            }
+
+            return Builtin::from_name(local).map(|b| TypeRef::Simple(SimpleType::builtin(b))).ok_or_else(unknown);
+        }
+
+        if namespace != self.target.as_deref() {
+            return Err(unknown());
+        }
+
+        if self.complex_nodes.contains_key(local) {
+            return Ok(TypeRef::Complex(self.complex_type_id(local)?));
+        }
+
+        match self.simple_nodes.get(local).cloned() {
+            Some(child) => {
+                if self.simple_stack.contains(&local) {
+                    return Err(unknown());
+                }
+
+                self.simple_stack.push(local);
+                let kind = self.simple_type(child);
+                self.simple_stack.pop();
+                Ok(TypeRef::Simple(kind?))
+            }
+            None => Err(unknown()),
+        }
+    }
+
+    fn resolve_simple_type(&mut self, node: Node<'a, 'd>, attr: &str) -> Result<SimpleType, SchemaError> {
+        let value = required_attribute(node, attr)?;
+        match self.resolve_type(node, value)? {
+            TypeRef::Simple(kind) => Ok(kind),
+            _ => Err(SchemaError::InvalidAttribute(attr.to_string(), node.text_pos())),
+        }
+    }
+
+    fn complex_type_id(&mut self, name: &'a str) -> Result<usize, SchemaError> {
+        if let Some(&id) = self.complex_ids.get(name) {
+            return Ok(id);
+        }
+
+        // Reserve an ID first, since a type can reference itself via a child element.
+        let id = self.schema.types.len();
+        self.schema.types.push(ComplexType::new());
+        self.complex_ids.insert(name, id);
+
+        let node = self.complex_nodes[name];
+        check_attributes(node, &["name", "mixed", "id"])?;
+        self.schema.types[id] = self.complex_type(node)?;
+        Ok(id)
+    }
+
+    fn complex_type(&mut self, node: Node<'a, 'd>) -> Result<ComplexType, SchemaError> {
+        let mut kind = ComplexType::new();
+        kind.mixed = parse_bool(node, "mixed")?;
+
+        let children = xs_children(node)?;
+        let mut children = children.into_iter().peekable();
+        if let Some(&child) = children.peek() {
+            match local_name(child) {
+                "sequence" | "choice" => {
+                    // Content models of local complex types are compiled in between.
+                    let particles = mem::replace(&mut self.particles, 0);
+                    let mut symbols = Vec::new();
+                    let particle = self.particle(child, &mut symbols);
+                    self.particles = particles;
+                    let particle = particle?;
+                    kind.content = Content::Elements(Automaton::new(&particle, symbols));
+                    children.next();
+                }
+                "all" => {
+                    kind.content = self.all(child)?;
+                    children.next();
+                }
+                "simpleContent" => {
+                    check_attributes(child, &["id"])?;
+                    let ext = match xs_children(child)?.first() {
+                        Some(&ext) if local_name(ext) == "extension" => ext,
+                        Some(&ext) => return Err(SchemaError::Unsupported(node_name(ext), ext.text_pos())),
+                        None => return Err(SchemaError::UnexpectedElement(node_name(child), child.text_pos())),
+                    };
+
+                    check_attributes(ext, &["base", "id"])?;
+                    kind.content = Content::Simple(self.resolve_simple_type(ext, "base")?);
+                    self.attributes(xs_children(ext)?, &mut kind)?;
+                    children.next();
+                }
+                _ => {}
+            }
+        }
+
+        self.attributes(children.collect(), &mut kind)?;
+        Ok(kind)
+    }
+
+    fn attributes(&mut self, nodes: Vec<Node<'a, 'd>>, kind: &mut ComplexType) -> Result<(), SchemaError> {
+        for node in nodes {
+            match local_name(node) {
+                "attribute" if !kind.any_attribute => {
+                    check_attributes(node, &["name", "type", "use", "fixed", "default", "form", "id"])?;
+                    if let Some(decl) = self.attribute(node)? {
+                        if kind.attributes.iter().any(|d| d.name == decl.name) {
+                            return Err(SchemaError::InvalidAttribute("name".to_string(), node.text_pos()));
+                        }
+
+                        kind.attributes.push(decl);
+                    }
+                }
+                "anyAttribute" if !kind.any_attribute => {
+                    // Namespace constraints are not checked.
+                    check_attributes(node, &["namespace", "processContents", "id"])?;
+                    kind.any_attribute = true;
+                }
+                "attribute" | "anyAttribute" | "sequence" | "choice" | "all" | "simpleContent" => {
+                    return Err(SchemaError::UnexpectedElement(node_name(node), node.text_pos()));
+                }
+                _ => return Err(SchemaError::Unsupported(node_name(node), node.text_pos())),
+            }
+        }
+
+        Ok(())
+    }
+
+    // Returns `None` for prohibited attributes.
+    fn attribute(&mut self, node: Node<'a, 'd>) -> Result<Option<AttributeDecl>, SchemaError> {
+        let name = required_attribute(node, "name")?;
+        let required = match node.attribute("use").map(|s| s.to_str()) {
+            None | Some("optional") => false,
+            Some("required") => true,
+            Some("prohibited") => return Ok(None),
+            Some(_) => return Err(SchemaError::InvalidAttribute("use".to_string(), node.text_pos())),
+        };
+
+        let children = xs_children(node)?;
+        let kind = match children.first() {
+            Some(&child) if local_name(child) == "simpleType" && node.attribute("type").is_none() => {
+                check_attributes(child, &["id"])?;
+                self.simple_type(child)?
+            }
+            Some(&child) => return Err(SchemaError::UnexpectedElement(node_name(child), child.text_pos())),
+            None if node.attribute("type").is_some() => self.resolve_simple_type(node, "type")?,
+            None => SimpleType::builtin(Builtin::AnySimpleType),
+        };
+
+        let namespace = if self.is_qualified(node, self.qualified_attributes)? {
+            self.target.clone()
+        } else {
+            None
+        };
+
+        let fixed = node.attribute("fixed").map(|s| s.to_str().to_string());
+        if let Some(ref value) = fixed {
+            if kind.check(value).is_err() {
+                return Err(SchemaError::InvalidAttribute("fixed".to_string(), node.text_pos()));
+            }
+        }
+
+        Ok(Some(AttributeDecl {
+            name: Name { namespace, local: name.to_string() },
+            kind,
+            required,
+            fixed,
+        }))
+    }
+
+    fn is_qualified(&self, node: Node, default: bool) -> Result<bool, SchemaError> {
+        match node.attribute("form").map(|s| s.to_str()) {
+            None => Ok(default),
+            Some("qualified") => Ok(true),
+            Some("unqualified") => Ok(false),
+            Some(_) => Err(SchemaError::InvalidAttribute("form".to_string(), node.text_pos())),
+        }
+    }
+
+    fn simple_type(&mut self, node: Node<'a, 'd>) -> Result<SimpleType, SchemaError> {
+        let child = match xs_children(node)?.first() {
+            Some(&child) => child,
+            None => return Err(SchemaError::UnexpectedElement(node_name(node), node.text_pos())),
+        };
+
+        match local_name(child) {
+            "restriction" => {
+                check_attributes(child, &["base", "id"])?;
+                let mut kind = self.resolve_simple_type(child, "base")?;
+                let mut enumeration = Vec::new();
+                for facet in xs_children(child)? {
+                    check_attributes(facet, &["value", "fixed", "id"])?;
+                    let value = required_attribute(facet, "value")?;
+                    let facet = match local_name(facet) {
+                        "enumeration" => {
+                            enumeration.push(kind.normalize(value).into_owned());
+                            continue;
+                        }
+                        "whiteSpace" => continue,
+                        "length" => Facet::Length(parse_number(facet, value)?),
+                        "minLength" => Facet::MinLength(parse_number(facet, value)?),
+                        "maxLength" => Facet::MaxLength(parse_number(facet, value)?),
+                        "totalDigits" => Facet::TotalDigits(parse_number(facet, value)?),
+                        "fractionDigits" => Facet::FractionDigits(parse_number(facet, value)?),
+                        "minInclusive" => Facet::MinInclusive(parse_bound(&kind, facet, value)?),
+                        "maxInclusive" => Facet::MaxInclusive(parse_bound(&kind, facet, value)?),
+                        "minExclusive" => Facet::MinExclusive(parse_bound(&kind, facet, value)?),
+                        "maxExclusive" => Facet::MaxExclusive(parse_bound(&kind, facet, value)?),
+                        _ => return Err(SchemaError::Unsupported(node_name(facet), facet.text_pos())),
+                    };
+
+                    kind.facets.push(facet);
+                }
+
+                if !enumeration.is_empty() {
+                    kind.facets.push(Facet::Enumeration(enumeration));
+                }
+
+                Ok(kind)
+            }
+            "list" => {
+                check_attributes(child, &["itemType", "id"])?;
+                let item = self.resolve_simple_type(child, "itemType")?;
+                if item.base().is_none() {
+                    return Err(SchemaError::InvalidAttribute("itemType".to_string(), child.text_pos()));
+                }
+
+                Ok(SimpleType { variety: Variety::List(Box::new(item)), facets: Vec::new() })
+            }
+            _ => Err(SchemaError::Unsupported(node_name(child), child.text_pos())),
+        }
+    }
+
+    fn all(&mut self, node: Node<'a, 'd>) -> Result<Content, SchemaError> {
+        check_attributes(node, &["minOccurs", "maxOccurs", "id"])?;
+        let (min, max) = parse_occurs(node)?;
+        if max != Some(1) || min > 1 {
+            return Err(SchemaError::InvalidAttribute("maxOccurs".to_string(), node.text_pos()));
+        }
+
+        let mut list = Vec::new();
+        for child in xs_children(node)? {
+            if local_name(child) != "element" {
+                return Err(SchemaError::UnexpectedElement(node_name(child), child.text_pos()));
+            }
+
+            let (child_min, child_max) = parse_occurs(child)?;
+            if child_min > 1 || child_max.map_or(true, |n| n > 1) {
+                return Err(SchemaError::InvalidAttribute("maxOccurs".to_string(), child.text_pos()));
+            }
+
+            if child_max != Some(0) {
+                list.push((self.element(child)?, min == 1 && child_min == 1));
+            }
+        }
+
+        Ok(Content::All(list))
+    }
+
+    // Compiles a particle with its `minOccurs` and `maxOccurs`.
+    fn particle(&mut self, node: Node<'a, 'd>, symbols: &mut Vec<Symbol>) -> Result<Particle, SchemaError> {
+        let (min, max) = parse_occurs(node)?;
+
+        // Nested particles are counted for each copy of the outer one,
+        // so this limits the total amount of work too.
+        self.particles += cmp::max(max.unwrap_or(min), 1);
+        if self.particles > MAX_PARTICLES {
+            return Err(SchemaError::Unsupported("maxOccurs".to_string(), node.text_pos()));
+        }
+
+        // Each copy of the particle must have its own positions in the automaton.
+        let mut items = Vec::new();
+        match max {
+            Some(max) => {
+                for i in 0..max {
+                    let term = self.term(node, symbols)?;
+                    items.push(if i < min { term } else { Particle::Optional(Box::new(term)) });
+                }
+            }
+            None => {
+                for _ in 1..min {
+                    items.push(self.term(node, symbols)?);
+                }
+
+                let term = Box::new(self.term(node, symbols)?);
+                items.push(if min == 0 { Particle::ZeroOrMore(term) } else { Particle::OneOrMore(term) });
+            }
+        }
+
+        if items.len() == 1 {
+            Ok(items.pop().unwrap())
+        } else {
+            Ok(Particle::Seq(items))
+        }
+    }
+
+    fn term(&mut self, node: Node<'a, 'd>, symbols: &mut Vec<Symbol>) -> Result<Particle, SchemaError> {
+        match local_name(node) {
+            "element" => {
+                symbols.push(Symbol::Element(self.element(node)?));
+                Ok(Particle::Symbol(symbols.len() - 1))
+            }
+            "any" => {
+                check_attributes(node, &["namespace", "processContents", "minOccurs", "maxOccurs", "id"])?;
+                symbols.push(Symbol::Any(self.wildcard(node)));
+                Ok(Particle::Symbol(symbols.len() - 1))
+            }
+            "sequence" | "choice" => {
+                check_attributes(node, &["minOccurs", "maxOccurs", "id"])?;
+                let mut items = Vec::new();
+                for child in xs_children(node)? {
+                    match local_name(child) {
+                        "element" | "any" | "sequence" | "choice" => items.push(self.particle(child, symbols)?),
+                        "all" => return Err(SchemaError::UnexpectedElement(node_name(child), child.text_pos())),
+                        _ => return Err(SchemaError::Unsupported(node_name(child), child.text_pos())),
+                    }
+                }
+
+                if local_name(node) == "sequence" {
+                    Ok(Particle::Seq(items))
+                } else {
+                    Ok(Particle::Choice(items))
+                }
+            }
+            _ => Err(SchemaError::Unsupported(node_name(node), node.text_pos())),
+        }
+    }
+
+    // Returns a local element declaration or a referenced global one.
+    fn element(&mut self, node: Node<'a, 'd>) -> Result<usize, SchemaError> {
+        if let Some(&id) = self.local_ids.get(&node.span().start()) {
+            return Ok(id);
+        }
+
+        if let Some(qname) = node.attribute("ref") {
+            check_attributes(node, &["ref", "minOccurs", "maxOccurs", "id"])?;
+            let qname = qname.to_str();
+            let unknown = || SchemaError::UnknownReference(qname.to_string(), node.text_pos());
+            let (namespace, local) = resolve_qname(node, qname).ok_or_else(unknown)?;
+            if namespace != self.target.as_deref() {
+                return Err(unknown());
+            }
+
+            return self.global_ids.get(local).cloned().ok_or_else(unknown);
+        }
+
+        check_attributes(node, &["name", "type", "minOccurs", "maxOccurs", "form", "id"])?;
+        let name = required_attribute(node, "name")?;
+        let namespace = if self.is_qualified(node, self.qualified_elements)? {
+            self.target.clone()
+        } else {
+            None
+        };
+
+        let id = self.schema.elements.len();
+        self.schema.elements.push(ElementDecl {
+            name: Name { namespace, local: name.to_string() },
+            kind: TypeRef::Any,
+        });
+        self.local_ids.insert(node.span().start(), id);
+        self.schema.elements[id].kind = self.element_type(node)?;
+        Ok(id)
+    }
+
+    fn wildcard(&self, node: Node) -> Wildcard {
+        let value = node.attribute("namespace").map(|s| s.to_str()).unwrap_or("##any");
+        match value.trim() {
+            "##any" => Wildcard::Any,
+            "##other" => Wildcard::Other(self.target.clone()),
+            _ => {
+                let list = ValueList::from(value).map(|ns| match ns.to_str() {
+                    "##targetNamespace" => self.target.clone(),
+                    "##local" => None,
+                    _ => Some(ns.to_string()),
+                });
+                Wildcard::List(list.collect())
+            }
+        }
+    }
+}
+
+fn local_name<'a>(node: Node<'a, '_>) -> &'a str {
+    node.tag_name().map(|(_, local)| local.to_str()).unwrap_or("")
+}
+
+fn node_name(node: Node) -> String {
+    match node.tag_name() {
+        Some((prefix, local)) if !prefix.is_empty() => format!("{}:{}", prefix, local),
+        Some((_, local)) => local.to_string(),
+        None => String::new(),
+    }
+}
+
+fn is_xs_element(node: Node, local: &str) -> bool {
+    match node.tag_name() {
+        Some((prefix, l)) => {
+            l.to_str() == local && resolve_namespace(node, prefix.to_str()) == Some(XS_NAMESPACE)
+        }
+        None => false,
+    }
+}
+
+// Returns child elements, except annotations.
+fn xs_children<'a, 'd>(node: Node<'a, 'd>) -> Result<Vec<Node<'a, 'd>>, SchemaError> {
+    let mut list = Vec::new();
+    for child in node.children().filter(|n| n.is_element()) {
+        let (prefix, _) = child.tag_name().unwrap();
+        if resolve_namespace(child, prefix.to_str()) != Some(XS_NAMESPACE) {
+            return Err(SchemaError::UnexpectedElement(node_name(child), child.text_pos()));
+        }
+
+        if local_name(child) != "annotation" {
+            list.push(child);
+        }
+    }
+
+    Ok(list)
+}
+
+// Checks that an element has only the known attributes.
+fn check_attributes(node: Node, known: &[&str]) -> Result<(), SchemaError> {
+    for attr in node.attributes() {
+        // Attributes in other namespaces are allowed by the specification.
+        if attr.prefix().is_empty() && !known.contains(&attr.local().to_str()) {
+            let name = attr.local().to_string();
+            return Err(SchemaError::Unsupported(name, node.text_pos()));
+        }
+    }
+
+    Ok(())
+}
+
+fn required_attribute<'a>(node: Node<'a, '_>, name: &str) -> Result<&'a str, SchemaError> {
+    match node.attribute(name) {
+        Some(value) => Ok(value.to_str().trim()),
+        None => Err(SchemaError::MissingAttribute(node_name(node), name.to_string(), node.text_pos())),
+    }
+}
+
+fn parse_bool(node: Node, name: &str) -> Result<bool, SchemaError> {
+    match node.attribute(name) {
+        Some(value) => {
+            Stream::parse_value(value, Stream::consume_boolean)
+                .map_err(|_| SchemaError::InvalidAttribute(name.to_string(), node.text_pos()))
+        }
+        None => Ok(false),
+    }
+}
+
+fn parse_number(node: Node, value: &str) -> Result<usize, SchemaError> {
+    value.parse().map_err(|_| SchemaError::InvalidAttribute("value".to_string(), node.text_pos()))
+}
+
+fn parse_bound(kind: &SimpleType, node: Node, value: &str) -> Result<f64, SchemaError> {
+    match kind.base() {
+        Some(b) if b.is_numeric() => {
+            match b.parse(value) {
+                Ok(Some(n)) => Ok(n),
+                _ => Err(SchemaError::InvalidAttribute("value".to_string(), node.text_pos())),
+            }
+        }
+        _ => Err(SchemaError::Unsupported(node_name(node), node.text_pos())),
+    }
+}
+
+// Returns `minOccurs` and `maxOccurs`. `None` is for `unbounded`.
+fn parse_occurs(node: Node) -> Result<(usize, Option<usize>), SchemaError> {
+    let min = match node.attribute("minOccurs") {
+        Some(value) => parse_number(node, value.to_str().trim())
+            .map_err(|_| SchemaError::InvalidAttribute("minOccurs".to_string(), node.text_pos()))?,
+        None => 1,
+    };
+
+    let max = match node.attribute("maxOccurs").map(|s| s.to_str().trim()) {
+        Some("unbounded") => None,
+        Some(value) => Some(parse_number(node, value)
+            .map_err(|_| SchemaError::InvalidAttribute("maxOccurs".to_string(), node.text_pos()))?),
+        None => Some(1),
+    };
+
+    if max.map_or(false, |max| max < min) {
+        return Err(SchemaError::InvalidAttribute("maxOccurs".to_string(), node.text_pos()));
+    }
+
+    Ok((min, max))
+}
+
+// Returns a non-empty namespace URI bound to the prefix.
+fn resolve_namespace<'a>(node: Node<'a, '_>, prefix: &str) -> Option<&'a str> {
+    if prefix == "xml" {
+        return Some(XML_NAMESPACE);
+    }
+
+    for node in iter::once(node).chain(node.ancestors()) {
+        for attr in node.attributes() {
+            let is_binding = if prefix.is_empty() {
+                attr.prefix().is_empty() && attr.local().to_str() == "xmlns"
+            } else {
+                attr.prefix().to_str() == "xmlns" && attr.local().to_str() == prefix
+            };
+
+            if is_binding {
+                let uri = attr.value().to_str();
+                return if uri.is_empty() { None } else { Some(uri) };
+            }
+        }
+    }
+
+    None
+}
+
+// Resolves a QName attribute value. Returns `None` when the prefix is not declared.
+fn resolve_qname<'a>(node: Node<'a, '_>, qname: &'a str) -> Option<(Option<&'a str>, &'a str)> {
+    let qname = qname.trim();
+    match qname.find(':') {
+        Some(idx) => {
+            let namespace = resolve_namespace(node, &qname[..idx])?;
+            Some((Some(namespace), &qname[idx + 1..]))
+        }
+        None => Some((resolve_namespace(node, ""), qname)),
+    }
+}
+
+
+impl<'a> Tokenizer<'a> {
+    /// Validates the token stream against the XML Schema.
+    ///
+    /// Requires the `schema` build feature.
+    ///
+    /// Returns all tokens as is, interleaved with validity errors,
+    /// which are returned as `Error::InvalidDocument`.
+    /// Errors caused by a start tag and its attributes are returned before
+    /// the `ElementStart` token. Other errors are returned right before the token
+    /// that caused them.
+    ///
+    /// Like [`validate`], continues after validity errors and stops after
+    /// the first well-formedness error. Elements that don't match a declaration
+    /// are reported once and their content is not validated.
+    ///
+    /// Namespace declarations and attributes in the
+    /// `http://www.w3.org/2001/XMLSchema-instance` namespace are not validated.
+    ///
+    /// See the [`schema`] module for an example.
+    ///
+    /// [`validate`]: struct.Tokenizer.html#method.validate
+    /// [`schema`]: schema/index.html
+    pub fn validate_schema(self, schema: &Schema) -> ValidateSchema<'a, '_> {
+        let rules = SchemaRules {
+            schema,
+            namespaces: Vec::new(),
+            tag: None,
+        };
+
+        ValidateSchema(Validator::new(self, rules))
+    }
+}
+
+
+// A start tag being parsed.
+struct Tag<'a> {
+    prefix: StrSpan<'a>,
+    local: StrSpan<'a>,
+    start: usize,
+    attributes: Vec<Attribute<'a>>,
+    tokens: Vec<Token<'a>>,
+}
+
+enum State<'s> {
+    // The element content is not validated.
+    Skip,
+    Simple(&'s SimpleType),
+    Complex(&'s ComplexType, Vec<usize>),
+}
+
+// An open element.
+struct Frame<'a, 's> {
+    name: &'a str,
+    // The namespaces stack length before the element.
+    namespaces: usize,
+    state: State<'s>,
+    text: String,
+    text_range: Option<Range<usize>>,
+}
+
+/// An iterator over tokens validated by a [`Schema`].
+///
+/// Created by [`Tokenizer::validate_schema`].
+///
+/// [`Schema`]: struct.Schema.html
+/// [`Tokenizer::validate_schema`]: ../struct.Tokenizer.html#method.validate_schema
+pub struct ValidateSchema<'a, 's>(Validator<'a, SchemaRules<'a, 's>>);
+
+impl<'a, 's> Iterator for ValidateSchema<'a, 's> {
+    type Item = Result<Token<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+// The XML Schema validation rules.
+struct SchemaRules<'a, 's> {
+    schema: &'s Schema,
+    // Prefixes and namespaces. An empty namespace undeclares the prefix.
+    namespaces: Vec<(&'a str, &'a str)>,
+    tag: Option<Tag<'a>>,
+}
+
+type SchemaContext<'a, 's> = Context<'a, Frame<'a, 's>>;
+
+impl<'a, 's> Rules<'a> for SchemaRules<'a, 's> {
+    type Frame = Frame<'a, 's>;
+
+    fn process_token(&mut self, cx: &mut SchemaContext<'a, 's>, token: Token<'a>,
+                     token_start: usize) -> Result<(), Error> {
+        match token {
+            Token::ElementStart(prefix, local) => {
+                let start = cx.tokenizer.markup_start(token_start);
+                self.tag = Some(Tag { prefix, local, start, attributes: Vec::new(), tokens: vec![token] });
+                return Ok(());
+            }
+            Token::Attribute((prefix, local), value) => {
+                if let Some(ref mut tag) = self.tag {
+                    tag.attributes.push(Attribute::new(prefix, local, value));
+                    tag.tokens.push(token);
+                    return Ok(());
+                }
+            }
+            Token::ElementEnd(ElementEnd::Open) | Token::ElementEnd(ElementEnd::Empty) => {
+                if let Some(tag) = self.tag.take() {
+                    self.start_element(cx, &tag);
+                    if token == Token::ElementEnd(ElementEnd::Empty) {
+                        if let Some(open) = cx.stack.pop() {
+                            let pos = cx.tokenizer.pos();
+                            self.end_element(cx, open, pos - 2..pos);
+                        }
+                    }
+
+                    cx.queue.extend(tag.tokens.into_iter().map(Ok));
+                }
+            }
+            Token::Text(text) | Token::Whitespaces(text) => {
+                let xml11 = cx.tokenizer.is_xml11_mode();
+                let value = unescape(text.to_str(), CharsKind::CharData, xml11);
+                self.process_text(cx, &value, token_start..cx.tokenizer.pos());
+            }
+            Token::Cdata(text) => {
+                self.process_text(cx, text.to_str(), token_start..cx.tokenizer.pos());
+            }
+            _ => {}
+        }
+
+        cx.queue.push_back(Ok(token));
+        Ok(())
+    }
+
+    fn end_element(&mut self, cx: &mut SchemaContext<'a, 's>, tag: OpenTag<'a, Frame<'a, 's>>,
+                   range: Range<usize>) {
+        let frame = tag.data;
+        self.namespaces.truncate(frame.namespaces);
+
+        let e = match frame.state {
+            State::Skip => None,
+            State::Simple(kind) => {
+                kind.check(&frame.text).err().map(|e| ValidityError::InvalidElementValue(frame.name.into(), e))
+            }
+            State::Complex(kind, ref states) => {
+                match kind.content {
+                    Content::Simple(ref kind) => {
+                        kind.check(&frame.text).err()
+                            .map(|e| ValidityError::InvalidElementValue(frame.name.into(), e))
+                    }
+                    Content::Elements(ref automaton) if !automaton.accepts(states) => {
+                        Some(ValidityError::IncompleteContent(frame.name.into()))
+                    }
+                    Content::All(ref list) if list.iter().zip(states).any(|(&(_, req), &n)| req && n == 0) => {
+                        Some(ValidityError::IncompleteContent(frame.name.into()))
+                    }
+                    _ => None,
+                }
+            }
+        };
+
+        if let Some(e) = e {
+            let range = match (&e, frame.text_range) {
+                (&ValidityError::InvalidElementValue(..), Some(r)) => r,
+                _ => range,
+            };
+            cx.push_error(e, range);
+        }
+    }
+}
+
+impl<'a, 's> SchemaRules<'a, 's> {
+    fn resolve(&self, prefix: &str) -> Option<&'a str> {
+        if prefix == "xml" {
+            return Some(XML_NAMESPACE);
+        }
+
+        self.namespaces.iter().rev().find(|&&(p, _)| p == prefix).and_then(|&(_, ns)| {
+            if ns.is_empty() { None } else { Some(ns) }
+        })
+    }
+
+    fn start_element(&mut self, cx: &mut SchemaContext<'a, 's>, tag: &Tag<'a>) {
+        let text = cx.text();
+        let name = qname_str(text, tag.prefix, tag.local);
+        let range = tag.start..tag.local.end();
+
+        let namespaces = self.namespaces.len();
+        for attr in tag.attributes.iter().filter(|a| is_namespace_declaration(a)) {
+            let prefix = if attr.prefix().is_empty() { "" } else { attr.local().to_str() };
+            self.namespaces.push((prefix, attr.value().to_str()));
+        }
+
+        let namespace = self.resolve(tag.prefix.to_str());
+        let local = tag.local.to_str();
+
+        let mut errors = Vec::new();
+        let decl = match cx.stack.last_mut().map(|tag| &mut tag.data) {
+            None => {
+                let decl = self.schema.global(namespace, local);
+                if decl.is_none() {
+                    errors.push(ValidityError::UndeclaredElement(name.into()));
+                }
+
+                decl
+            }
+            Some(parent) => {
+                let matched = match parent.state {
+                    State::Skip => Ok(None),
+                    State::Complex(kind, ref mut states) => {
+                        match_child(self.schema, kind, states, namespace, local)
+                    }
+                    State::Simple(_) => Err(()),
+                };
+
+                match matched {
+                    Ok(decl) => decl,
+                    Err(()) => {
+                        errors.push(ValidityError::UnexpectedElement(parent.name.into(), name.into()));
+                        None
+                    }
+                }
+            }
+        };
+
+        let state = match decl.map(|id| &self.schema.elements[id].kind) {
+            Some(TypeRef::Simple(kind)) => State::Simple(kind),
+            Some(&TypeRef::Complex(id)) => {
+                let kind = &self.schema.types[id];
+                let states = match kind.content {
+                    Content::Elements(ref automaton) => automaton.start(),
+                    Content::All(ref list) => vec![0; list.len()],
+                    _ => Vec::new(),
+                };
+
+                State::Complex(kind, states)
+            }
+            Some(TypeRef::Any) | None => State::Skip,
+        };
+
+        for e in errors {
+            cx.push_error(e, range.clone());
+        }
+
+        self.check_attributes(cx, tag, name, &state);
+
+        cx.stack.push(tag.prefix, tag.local, tag.start, Frame {
+            name,
+            namespaces,
+            state,
+            text: String::new(),
+            text_range: None,
+        });
+    }
+
+    fn check_attributes(&mut self, cx: &mut SchemaContext<'a, 's>, tag: &Tag<'a>, element: &'a str, state: &State<'s>) {
+        let attributes: &[AttributeDecl] = match *state {
+            State::Skip => return,
+            State::Simple(_) => &[],
+            State::Complex(kind, _) => &kind.attributes,
+        };
+        let any_attribute = matches!(*state, State::Complex(kind, _) if kind.any_attribute);
+
+        let text = cx.text();
+        let xml11 = cx.tokenizer.is_xml11_mode();
+        let mut errors = Vec::new();
+        let mut found = Vec::new();
+        for attr in tag.attributes.iter().filter(|a| !is_namespace_declaration(a)) {
+            let namespace = if attr.prefix().is_empty() { None } else { self.resolve(attr.prefix().to_str()) };
+            if namespace == Some(XSI_NAMESPACE) {
+                continue;
+            }
+
+            let name = qname_str(text, attr.prefix(), attr.local());
+            let start = if attr.prefix().is_empty() { attr.local().start() } else { attr.prefix().start() };
+            let range = start..attr.value().end() + 1;
+
+            let decl = match attributes.iter().position(|d| d.name.matches(namespace, attr.local().to_str())) {
+                Some(idx) => {
+                    found.push(idx);
+                    &attributes[idx]
+                }
+                None => {
+                    if !any_attribute {
+                        errors.push((ValidityError::UndeclaredAttribute(element.into(), name.into()), range));
+                    }
+                    continue;
+                }
+            };
+
+            let value = unescape(attr.value().to_str(), CharsKind::AttributeValue, xml11);
+            if let Err(expected) = decl.kind.check(&value) {
+                errors.push((ValidityError::InvalidAttributeValue(name.into(), expected), range));
+            } else if let Some(ref fixed) = decl.fixed {
+                if decl.kind.normalize(fixed) != decl.kind.normalize(&value) {
+                    errors.push((ValidityError::FixedAttributeValue(name.into(), fixed.as_str().into()), range));
+                }
+            }
+        }
+
+        let range = tag.start..tag.local.end();
+        for (i, decl) in attributes.iter().enumerate() {
+            if decl.required && !found.contains(&i) {
+                let e = ValidityError::MissingAttribute(element.into(), decl.name.local.as_str().into());
+                errors.push((e, range.clone()));
+            }
+        }
+
+        for (e, range) in errors {
+            cx.push_error(e, range);
+        }
+    }
+
+    fn process_text(&mut self, cx: &mut SchemaContext<'a, 's>, value: &str, range: Range<usize>) {
+        let frame = match cx.stack.last_mut() {
+            Some(tag) => &mut tag.data,
+            None => return,
+        };
+
+        let is_simple = match frame.state {
+            State::Skip => return,
+            State::Simple(_) => true,
+            State::Complex(kind, _) => {
+                if let Content::Simple(_) = kind.content {
+                    true
+                } else if kind.mixed || value.bytes().all(|c| c.is_xml_space()) {
+                    return;
+                } else {
+                    false
+                }
+            }
+        };
+
+        if is_simple {
+            frame.text.push_str(value);
+            frame.text_range = match frame.text_range.take() {
+                Some(r) => Some(r.start..range.end),
+                None => Some(range),
+            };
+        } else {
+            let e = ValidityError::UnexpectedText(frame.name.into());
+            cx.push_error(e, range);
+        }
+    }
+}
+
+// Matches a child element against the parent content.
+//
+// Returns a matched declaration, `None` for a wildcard without a global declaration
+// and an error when the element is not allowed.
+fn match_child(schema: &Schema, kind: &ComplexType, states: &mut Vec<usize>,
+               namespace: Option<&str>, local: &str) -> Result<Option<usize>, ()> {
+    let matches = |symbol: &Symbol| match *symbol {
+        Symbol::Element(id) => schema.elements[id].name.matches(namespace, local),
+        Symbol::Any(ref wildcard) => wildcard.matches(namespace),
+    };
+
+    match kind.content {
+        Content::Elements(ref automaton) => {
+            let next = automaton.step(states, matches);
+            if next.is_empty() {
+                // Keep the current states, so the next siblings are still checked.
+                return Err(());
+            }
+
+            *states = next;
+            match states.first().and_then(|&s| automaton.symbol(s)) {
+                Some(&Symbol::Element(id)) => Ok(Some(id)),
+                // Wildcards are processed laxly.
+                _ => Ok(schema.global(namespace, local)),
+            }
+        }
+        Content::All(ref list) => {
+            let idx = list.iter().position(|&(id, _)| matches(&Symbol::Element(id))).ok_or(())?;
+            if states[idx] != 0 {
+                return Err(());
+            }
+
+            states[idx] += 1;
+            Ok(Some(list[idx].0))
+        }
+        Content::Empty | Content::Simple(_) => Err(()),
+    }
+}
@@ -0,0 +1,558 @@
+use std::borrow::Cow;
+use std::str::FromStr;
+
+use sax::TagStack;
+use text::unescape;
+use {
+    Attribute,
+    CharsKind,
+    ElementEnd,
+    Error,
+    StrSpan,
+    QueryError,
+    Token,
+    Tokenizer,
+    XmlCharExt,
+};
+
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+// `prefix|local`. `None` prefix matches any prefix and an empty one matches unprefixed names.
+#[derive(Clone, PartialEq, Debug)]
+struct Name {
+    prefix: Option<String>,
+    local: String,
+}
+
+impl Name {
+    fn matches(&self, prefix: StrSpan, local: StrSpan) -> bool {
+        self.prefix.as_ref().map_or(true, |p| p == prefix.to_str()) && self.local == local.to_str()
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Condition {
+    Attribute(Name, Option<String>),
+    Class(String),
+    FirstChild,
+}
+
+// A compound selector, like `rect.a#b`.
+#[derive(Clone, PartialEq, Debug)]
+struct Compound {
+    // The combinator with the previous compound.
+    combinator: Combinator,
+    // `None` for `*`.
+    name: Option<Name>,
+    conditions: Vec<Condition>,
+}
+
+impl Compound {
+    fn matches(&self, element: &Element, xml11: bool) -> bool {
+        if let Some(ref name) = self.name {
+            if !name.matches(element.prefix, element.local) {
+                return false;
+            }
+        }
+
+        self.conditions.iter().all(|c| match *c {
+            Condition::Attribute(ref name, ref value) => {
+                element.attributes.iter().any(|attr| {
+                    name.matches(attr.prefix(), attr.local())
+                        && value.as_ref().map_or(true, |v| *v == attribute_value(attr, xml11))
+                })
+            }
+            Condition::Class(ref class) => {
+                element.attributes.iter()
+                    .filter(|attr| attr.prefix().is_empty() && attr.local().to_str() == "class")
+                    .any(|attr| attribute_value(attr, xml11).split_whitespace().any(|c| c == class))
+            }
+            Condition::FirstChild => element.is_first_child,
+        })
+    }
+}
+
+
+/// A compiled CSS selector.
+///
+/// Supports a [Selectors Level 3](https://www.w3.org/TR/selectors-3/) subset:
+///
+/// - Type selectors: `name`, `prefix|name`, `|name` and `*`.
+///   A name without a prefix matches elements with any prefix.
+///   `|name` matches only unprefixed elements.
+///   Prefixes are compared as is, without namespace resolution.
+/// - ID selectors: `#id`.
+/// - Class selectors: `.class`.
+/// - Attribute selectors: `[name]` and `[name=value]`, where the value
+///   is an identifier or a quoted string. Attribute values are unescaped before comparison.
+/// - The `:first-child` pseudo-class.
+/// - Descendant (` `) and child (`>`) combinators.
+/// - Selector lists: `a, b`.
+///
+/// Selectors are evaluated by [`Tokenizer::select_elements`].
+///
+/// [`Tokenizer::select_elements`]: struct.Tokenizer.html#method.select_elements
+#[derive(Clone, PartialEq, Debug)]
+pub struct Selector {
+    list: Vec<Vec<Compound>>,
+}
+
+impl Selector {
+    /// Parses a selector.
+    pub fn parse(text: &str) -> Result<Selector, QueryError> {
+        SelectorParser { text, pos: 0 }.parse()
+    }
+}
+
+impl FromStr for Selector {
+    type Err = QueryError;
+
+    fn from_str(text: &str) -> Result<Selector, QueryError> {
+        Selector::parse(text)
+    }
+}
+
+
+struct SelectorParser<'t> {
+    text: &'t str,
+    pos: usize,
+}
+
+impl<'t> SelectorParser<'t> {
+    fn parse(mut self) -> Result<Selector, QueryError> {
+        let mut list = Vec::new();
+
+        loop {
+            self.skip_spaces();
+            list.push(self.parse_complex()?);
+
+            if self.at_end() {
+                break;
+            }
+
+            if !self.consume_char(',') {
+                return Err(self.unexpected());
+            }
+        }
+
+        Ok(Selector { list })
+    }
+
+    // Compound selectors separated by combinators.
+    fn parse_complex(&mut self) -> Result<Vec<Compound>, QueryError> {
+        let mut compounds = Vec::new();
+        let mut combinator = Combinator::Descendant;
+
+        loop {
+            compounds.push(self.parse_compound(combinator)?);
+
+            let had_spaces = self.skip_spaces();
+            if self.consume_char('>') {
+                self.skip_spaces();
+                combinator = Combinator::Child;
+            } else if self.at_end() || self.curr_char() == Some(',') {
+                break;
+            } else if had_spaces {
+                combinator = Combinator::Descendant;
+            } else {
+                return Err(self.unexpected());
+            }
+        }
+
+        Ok(compounds)
+    }
+
+    fn parse_compound(&mut self, combinator: Combinator) -> Result<Compound, QueryError> {
+        let start = self.pos;
+
+        let name = if self.consume_char('*') {
+            None
+        } else if self.curr_char() == Some('|') || self.curr_char().map_or(false, is_ident_start) {
+            Some(self.parse_name()?)
+        } else {
+            None
+        };
+
+        let mut conditions = Vec::new();
+        loop {
+            if self.consume_char('#') {
+                let id = self.parse_ident()?;
+                let name = Name { prefix: Some(String::new()), local: "id".to_string() };
+                conditions.push(Condition::Attribute(name, Some(id)));
+            } else if self.consume_char('.') {
+                conditions.push(Condition::Class(self.parse_ident()?));
+            } else if self.consume_char('[') {
+                conditions.push(self.parse_attribute()?);
+            } else if self.consume_char(':') {
+                let pos = self.pos;
+                if self.parse_ident()? != "first-child" {
+                    self.pos = pos;
+                    return Err(self.unexpected());
+                }
+
+                conditions.push(Condition::FirstChild);
+            } else {
+                break;
+            }
+        }
+
+        // An empty compound selector.
+        if self.pos == start {
+            return Err(self.unexpected());
+        }
+
+        Ok(Compound { combinator, name, conditions })
+    }
+
+    fn parse_attribute(&mut self) -> Result<Condition, QueryError> {
+        self.skip_spaces();
+        let name = self.parse_name()?;
+        self.skip_spaces();
+
+        let value = if self.consume_char('=') {
+            self.skip_spaces();
+            let value = match self.curr_char() {
+                Some('"') | Some('\'') => self.parse_string()?,
+                _ => self.parse_ident()?,
+            };
+            self.skip_spaces();
+            Some(value)
+        } else {
+            None
+        };
+
+        if !self.consume_char(']') {
+            return Err(self.unexpected());
+        }
+
+        // Attributes without a prefix are not in a namespace.
+        let name = Name { prefix: Some(name.prefix.unwrap_or_default()), local: name.local };
+        Ok(Condition::Attribute(name, value))
+    }
+
+    // `name`, `prefix|name` or `|name`.
+    fn parse_name(&mut self) -> Result<Name, QueryError> {
+        if self.consume_char('|') {
+            let local = self.parse_ident()?;
+            return Ok(Name { prefix: Some(String::new()), local });
+        }
+
+        let name = self.parse_ident()?;
+        if self.consume_char('|') {
+            let local = self.parse_ident()?;
+            Ok(Name { prefix: Some(name), local })
+        } else {
+            Ok(Name { prefix: None, local: name })
+        }
+    }
+
+    fn parse_ident(&mut self) -> Result<String, QueryError> {
+        let start = self.pos;
+        for c in self.text[self.pos..].chars() {
+            let is_valid = if self.pos == start { is_ident_start(c) } else { is_ident_char(c) };
+            if !is_valid {
+                break;
+            }
+
+            self.pos += c.len_utf8();
+        }
+
+        if self.pos == start {
+            return Err(self.unexpected());
+        }
+
+        Ok(self.text[start..self.pos].to_string())
+    }
+
+    fn parse_string(&mut self) -> Result<String, QueryError> {
+        let quote = self.curr_char().unwrap();
+        self.pos += 1;
+        match self.text[self.pos..].find(quote) {
+            Some(len) => {
+                let value = self.text[self.pos..self.pos + len].to_string();
+                self.pos += len + 1;
+                Ok(value)
+            }
+            None => Err(QueryError::UnexpectedEnd),
+        }
+    }
+
+    fn curr_char(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn consume_char(&mut self, c: char) -> bool {
+        if self.curr_char() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_spaces(&mut self) -> bool {
+        let len = self.text[self.pos..].bytes().take_while(|c| c.is_ascii_whitespace()).count();
+        self.pos += len;
+        len != 0
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos == self.text.len()
+    }
+
+    fn unexpected(&self) -> QueryError {
+        match self.curr_char() {
+            Some(c) => QueryError::InvalidChar(c, self.pos),
+            None => QueryError::UnexpectedEnd,
+        }
+    }
+}
+
+// XML names without `:` and `.`, which are pseudo-class and class separators.
+fn is_ident_start(c: char) -> bool {
+    c != ':' && c.is_xml_name_start()
+}
+
+fn is_ident_char(c: char) -> bool {
+    c != ':' && c != '.' && c.is_xml_name()
+}
+
+fn attribute_value<'a>(attr: &Attribute<'a>, xml11: bool) -> Cow<'a, str> {
+    unescape(attr.value().to_str(), CharsKind::AttributeValue, xml11)
+}
+
+
+/// An element matched by a [`Selector`].
+///
+/// [`Selector`]: struct.Selector.html
+#[derive(Clone, PartialEq, Debug)]
+pub struct ElementMatch<'a> {
+    span: StrSpan<'a>,
+    prefix: StrSpan<'a>,
+    local: StrSpan<'a>,
+    attributes: Vec<Attribute<'a>>,
+}
+
+impl<'a> ElementMatch<'a> {
+    /// Returns the start tag span, from `<` to `>` or `/>`.
+    pub fn span(&self) -> StrSpan<'a> {
+        self.span
+    }
+
+    /// Returns the element name prefix.
+    ///
+    /// Empty when the name is not prefixed.
+    pub fn prefix(&self) -> StrSpan<'a> {
+        self.prefix
+    }
+
+    /// Returns the element name local part.
+    pub fn local(&self) -> StrSpan<'a> {
+        self.local
+    }
+
+    /// Returns the element attributes.
+    pub fn attributes(&self) -> &[Attribute<'a>] {
+        &self.attributes
+    }
+}
+
+
+impl<'a> Tokenizer<'a> {
+    /// Evaluates the selector over the token stream and returns matched elements
+    /// in document order.
+    ///
+    /// Matches are returned as soon as the start tag is parsed.
+    /// No tree is built. The memory usage depends only on the document depth.
+    ///
+    /// Like [`parse_with`], checks that close tags are matching the open ones.
+    /// The iterator stops after the first error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Selector, Tokenizer};
+    ///
+    /// let text = "
+    /// <html>
+    ///     <body>
+    ///         <p class='intro note'>Hello</p>
+    ///         <div><p id='p2'>World</p></div>
+    ///     </body>
+    /// </html>";
+    ///
+    /// let selector = Selector::parse("body > p.note, div #p2").unwrap();
+    /// let tags: Vec<_> = Tokenizer::from(text).select_elements(&selector)
+    ///     .map(|m| m.unwrap().span().to_str())
+    ///     .collect();
+    ///
+    /// assert_eq!(tags, vec!["<p class='intro note'>", "<p id='p2'>"]);
+    /// ```
+    ///
+    /// [`parse_with`]: struct.Tokenizer.html#method.parse_with
+    pub fn select_elements(self, selector: &Selector) -> SelectElements<'a, '_> {
+        let root = Frame {
+            active: (0..selector.list.len()).map(|i| (i, 0)).collect(),
+            children: 0,
+        };
+
+        SelectElements {
+            tokenizer: self,
+            selector,
+            root,
+            stack: TagStack::new(),
+            element: None,
+            attributes: Vec::new(),
+            done: false,
+        }
+    }
+}
+
+
+// An element being matched.
+struct Element<'a, 'e> {
+    prefix: StrSpan<'a>,
+    local: StrSpan<'a>,
+    attributes: &'e [Attribute<'a>],
+    is_first_child: bool,
+}
+
+// An open element or the document.
+struct Frame {
+    // Selectors and indexes of compounds that can be matched by children.
+    active: Vec<(usize, usize)>,
+    // The number of child elements.
+    children: usize,
+}
+
+/// An iterator over elements matched by a [`Selector`].
+///
+/// Created by [`Tokenizer::select_elements`].
+///
+/// [`Selector`]: struct.Selector.html
+/// [`Tokenizer::select_elements`]: struct.Tokenizer.html#method.select_elements
+pub struct SelectElements<'a, 's> {
+    tokenizer: Tokenizer<'a>,
+    selector: &'s Selector,
+    root: Frame,
+    stack: TagStack<'a, Frame>,
+    // The current start tag name and its start position.
+    element: Option<(StrSpan<'a>, StrSpan<'a>, usize)>,
+    attributes: Vec<Attribute<'a>>,
+    done: bool,
+}
+
+impl<'a, 's> SelectElements<'a, 's> {
+    // Returns a match when the element is matched by any selector.
+    fn start_element(&mut self) -> Option<ElementMatch<'a>> {
+        let (prefix, local, start) = self.element.take()?;
+        let xml11 = self.tokenizer.is_xml11_mode();
+
+        let parent = match self.stack.last_mut() {
+            Some(tag) => &mut tag.data,
+            None => &mut self.root,
+        };
+
+        let element = Element {
+            prefix,
+            local,
+            attributes: &self.attributes,
+            is_first_child: parent.children == 0,
+        };
+        parent.children += 1;
+
+        let mut active = Vec::new();
+        let mut is_match = false;
+        for &(sel, idx) in &parent.active {
+            let compounds = &self.selector.list[sel];
+            if compounds[idx].matches(&element, xml11) {
+                if idx + 1 == compounds.len() {
+                    is_match = true;
+                } else if !active.contains(&(sel, idx + 1)) {
+                    active.push((sel, idx + 1));
+                }
+            }
+
+            // The descendant combinator allows matching deeper.
+            if compounds[idx].combinator == Combinator::Descendant && !active.contains(&(sel, idx)) {
+                active.push((sel, idx));
+            }
+        }
+
+        self.stack.push(prefix, local, start, Frame { active, children: 0 });
+
+        if is_match {
+            let text = self.tokenizer.stream.span().full_str();
+            Some(ElementMatch {
+                span: StrSpan::from_substr(text, start, self.tokenizer.pos()),
+                prefix,
+                local,
+                attributes: self.attributes.clone(),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn process_token(&mut self, token: Token<'a>, token_start: usize)
+        -> Result<Option<ElementMatch<'a>>, Error>
+    {
+        match token {
+            Token::ElementStart(prefix, local) => {
+                self.element = Some((prefix, local, self.tokenizer.markup_start(token_start)));
+                self.attributes.clear();
+            }
+            Token::Attribute((prefix, local), value) => {
+                self.attributes.push(Attribute::new(prefix, local, value));
+            }
+            Token::ElementEnd(ElementEnd::Open) => {
+                return Ok(self.start_element());
+            }
+            Token::ElementEnd(ElementEnd::Empty) => {
+                let m = self.start_element();
+                self.stack.pop();
+                return Ok(m);
+            }
+            Token::ElementEnd(ElementEnd::Close(prefix, local)) => {
+                self.stack.close(&self.tokenizer, prefix, local, token_start)?;
+            }
+            _ => {}
+        }
+
+        Ok(None)
+    }
+}
+
+impl<'a, 's> Iterator for SelectElements<'a, 's> {
+    type Item = Result<ElementMatch<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let token_start = self.tokenizer.pos();
+            let res = match self.tokenizer.next() {
+                Some(Ok(token)) => self.process_token(token, token_start),
+                Some(Err(e)) => Err(e),
+                None => {
+                    self.done = true;
+                    self.stack.finish(&self.tokenizer).map(|_| None)
+                }
+            };
+
+            match res {
+                Ok(Some(m)) => return Some(Ok(m)),
+                Ok(None) => {}
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,990 @@
+//! A [serde](https://serde.rs) serializer that writes XML.
+//!
+//! The output follows the [`de`] module mapping, so it can be deserialized back:
+//!
+//! - Structs and maps are written as elements.
+//! - Fields with the `@` prefix, like `@id`, are written as attributes.
+//! - The [`TEXT_FIELD`] field is written as the element text.
+//! - Any other field is written as a child element. Sequences are written
+//!   as repeated child elements with the same name.
+//! - Strings, numbers, booleans and unit enum variants are written as text.
+//!   Sequences of them are written as whitespace-separated values
+//!   when used as an attribute value or the element text.
+//! - Any other enum variant is written as a child element with the variant name.
+//! - `None` fields are omitted.
+//!
+//! The root element is named after the serialized struct or enum,
+//! unless a name is set via [`Serializer::set_root_name`].
+//!
+//! Names in the `{uri}local` form are written with the prefix of a namespace
+//! declared via [`Serializer::add_namespace`]. Namespace declarations are added
+//! to the root element.
+//!
+//! Text and attribute values are escaped, so the output is always well-formed.
+//! Characters that are not allowed in XML produce an error.
+//!
+//! # Example
+//!
+//! ```
+//! #[macro_use] extern crate serde_derive;
+//! extern crate xmlparser;
+//!
+//! #[derive(Serialize)]
+//! struct Config {
+//!     #[serde(rename = "@version")]
+//!     version: u32,
+//!     name: String,
+//!     #[serde(rename = "server")]
+//!     servers: Vec<Server>,
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct Server {
+//!     #[serde(rename = "@port")]
+//!     port: u16,
+//!     #[serde(rename = "$text")]
+//!     host: String,
+//! }
+//!
+//! # fn main() {
+//! let config = Config {
+//!     version: 2,
+//!     name: "Tom & Jerry".to_string(),
+//!     servers: vec![
+//!         Server { port: 80, host: "example.com".to_string() },
+//!         Server { port: 8080, host: "localhost".to_string() },
+//!     ],
+//! };
+//!
+//! assert_eq!(
+//!     xmlparser::ser::to_string(&config).unwrap(),
+//!     "<Config version=\"2\"><name>Tom &amp; Jerry</name>\
+//!      <server port=\"80\">example.com</server>\
+//!      <server port=\"8080\">localhost</server></Config>"
+//! );
+//! # }
+//! ```
+//!
+//! [`de`]: ../de/index.html
+//! [`TEXT_FIELD`]: ../de/constant.TEXT_FIELD.html
+//! [`Serializer::set_root_name`]: struct.Serializer.html#method.set_root_name
+//! [`Serializer::add_namespace`]: struct.Serializer.html#method.add_namespace
+
+use std::error;
+use std::fmt;
+
+use serde::ser::{
+    self,
+    Impossible,
+    Serialize,
+    SerializeMap,
+    SerializeSeq,
+    SerializeStruct,
+    SerializeStructVariant,
+    SerializeTuple,
+    SerializeTupleStruct,
+    SerializeTupleVariant,
+};
+
+use de::{
+    TEXT_FIELD,
+    XML_NAMESPACE,
+};
+use XmlCharExt;
+
+
+/// A serialization error.
+#[derive(Debug)]
+pub enum Error {
+    /// An error produced by a serialized type.
+    Custom(String),
+    /// A value that cannot be represented in XML, like bytes
+    /// or a map as an attribute value.
+    Unsupported(&'static str),
+    /// A root element name is not set and cannot be taken from the value.
+    MissingRootName,
+    /// An invalid element or attribute name.
+    InvalidName(String),
+    /// A name in a namespace that was not declared.
+    UnknownNamespace(String),
+    /// A character that is not allowed in XML.
+    InvalidCharacter(char),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Custom(ref msg) => write!(f, "{}", msg),
+            Error::Unsupported(what) => write!(f, "cannot serialize {}", what),
+            Error::MissingRootName => write!(f, "the root element name is not set"),
+            Error::InvalidName(ref name) => write!(f, "invalid name '{}'", name),
+            Error::UnknownNamespace(ref uri) => write!(f, "undeclared namespace '{}'", uri),
+            Error::InvalidCharacter(c) => write!(f, "a character U+{:04X} is not allowed", c as u32),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        "an XML serialization error"
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Custom(msg.to_string())
+    }
+}
+
+
+/// Serializes the value as an XML document without the XML declaration.
+pub fn to_string<T: ?Sized + Serialize>(value: &T) -> Result<String, Error> {
+    let mut serializer = Serializer::new();
+    value.serialize(&mut serializer)?;
+    Ok(serializer.into_string())
+}
+
+
+/// An XML serializer.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// # extern crate xmlparser;
+///
+/// use serde::Serialize;
+/// use xmlparser::ser::Serializer;
+///
+/// # fn main() {
+/// let mut serializer = Serializer::new();
+/// serializer.set_root_name("{urn:x}size");
+/// serializer.add_namespace("x", "urn:x");
+/// serializer.enable_declaration();
+/// 10.serialize(&mut serializer).unwrap();
+///
+/// assert_eq!(serializer.into_string(),
+///            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+///             <x:size xmlns:x=\"urn:x\">10</x:size>");
+/// # }
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct Serializer {
+    output: String,
+    root_name: Option<String>,
+    namespaces: Vec<(String, String)>,
+    declaration: bool,
+}
+
+impl Serializer {
+    /// Creates a new serializer.
+    pub fn new() -> Self {
+        Serializer::default()
+    }
+
+    /// Sets the root element name.
+    ///
+    /// Required for values without a name, like maps and strings.
+    pub fn set_root_name(&mut self, name: &str) {
+        self.root_name = Some(name.to_string());
+    }
+
+    /// Declares a namespace on the root element.
+    ///
+    /// An empty prefix declares the default namespace.
+    pub fn add_namespace(&mut self, prefix: &str, uri: &str) {
+        self.namespaces.push((prefix.to_string(), uri.to_string()));
+    }
+
+    /// Enables the XML declaration writing.
+    pub fn enable_declaration(&mut self) {
+        self.declaration = true;
+    }
+
+    /// Returns the written XML.
+    pub fn into_string(self) -> String {
+        self.output
+    }
+
+    fn root(&mut self) -> ElementSerializer<'_> {
+        if self.declaration && self.output.is_empty() {
+            self.output.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        }
+
+        ElementSerializer {
+            output: &mut self.output,
+            name: self.root_name.clone(),
+            namespaces: &self.namespaces,
+            is_root: true,
+        }
+    }
+}
+
+macro_rules! forward_to_root {
+    ($($method:ident($($arg:ident: $ty:ty),*) -> $ret:ty;)*) => {
+        $(
+            fn $method(self, $($arg: $ty),*) -> Result<$ret, Error> {
+                self.root().$method($($arg),*)
+            }
+        )*
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = ElementSeq<'a>;
+    type SerializeTuple = ElementSeq<'a>;
+    type SerializeTupleStruct = ElementSeq<'a>;
+    type SerializeTupleVariant = TupleVariant<'a>;
+    type SerializeMap = ElementMap<'a>;
+    type SerializeStruct = ElementMap<'a>;
+    type SerializeStructVariant = ElementMap<'a>;
+
+    forward_to_root! {
+        serialize_bool(v: bool) -> ();
+        serialize_i8(v: i8) -> ();
+        serialize_i16(v: i16) -> ();
+        serialize_i32(v: i32) -> ();
+        serialize_i64(v: i64) -> ();
+        serialize_u8(v: u8) -> ();
+        serialize_u16(v: u16) -> ();
+        serialize_u32(v: u32) -> ();
+        serialize_u64(v: u64) -> ();
+        serialize_f32(v: f32) -> ();
+        serialize_f64(v: f64) -> ();
+        serialize_char(v: char) -> ();
+        serialize_str(v: &str) -> ();
+        serialize_bytes(v: &[u8]) -> ();
+        serialize_none() -> ();
+        serialize_unit() -> ();
+        serialize_unit_struct(name: &'static str) -> ();
+        serialize_unit_variant(name: &'static str, index: u32, variant: &'static str) -> ();
+        serialize_seq(len: Option<usize>) -> ElementSeq<'a>;
+        serialize_tuple(len: usize) -> ElementSeq<'a>;
+        serialize_tuple_struct(name: &'static str, len: usize) -> ElementSeq<'a>;
+        serialize_tuple_variant(name: &'static str, index: u32, variant: &'static str, len: usize)
+            -> TupleVariant<'a>;
+        serialize_map(len: Option<usize>) -> ElementMap<'a>;
+        serialize_struct(name: &'static str, len: usize) -> ElementMap<'a>;
+        serialize_struct_variant(name: &'static str, index: u32, variant: &'static str, len: usize)
+            -> ElementMap<'a>;
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), Error> {
+        self.root().serialize_some(value)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, name: &'static str, value: &T)
+        -> Result<(), Error>
+    {
+        self.root().serialize_newtype_struct(name, value)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.root().serialize_newtype_variant(name, index, variant, value)
+    }
+}
+
+
+// Writes a value as an element.
+struct ElementSerializer<'a> {
+    output: &'a mut String,
+    name: Option<String>,
+    namespaces: &'a [(String, String)],
+    is_root: bool,
+}
+
+impl<'a> ElementSerializer<'a> {
+    // Returns the element name, falling back to the type name.
+    fn element_name(&self, fallback: Option<&str>) -> Result<String, Error> {
+        match self.name.as_deref().or(fallback) {
+            Some(name) => resolve_name(name, self.namespaces, false),
+            None => Err(Error::MissingRootName),
+        }
+    }
+
+    fn child(&mut self, name: &str) -> ElementSerializer<'_> {
+        ElementSerializer {
+            output: &mut *self.output,
+            name: Some(name.to_string()),
+            namespaces: self.namespaces,
+            is_root: false,
+        }
+    }
+
+    fn write_start(&mut self, name: &str) -> Result<(), Error> {
+        write_start(self.output, name, self.namespaces, self.is_root)
+    }
+
+    fn write_text_element(mut self, fallback: Option<&str>, text: &str) -> Result<(), Error> {
+        let name = self.element_name(fallback)?;
+        self.write_start(&name)?;
+        if text.is_empty() {
+            self.output.push_str("/>");
+        } else {
+            self.output.push('>');
+            escape_text(text, self.output)?;
+            write_end(self.output, &name);
+        }
+
+        Ok(())
+    }
+
+    fn element_map(self, fallback: Option<&str>, closing: Option<String>) -> Result<ElementMap<'a>, Error> {
+        let name = self.element_name(fallback)?;
+        Ok(ElementMap {
+            output: self.output,
+            name,
+            namespaces: self.namespaces,
+            is_root: self.is_root,
+            attributes: String::new(),
+            content: String::new(),
+            closing,
+            key: None,
+        })
+    }
+}
+
+macro_rules! serialize_display {
+    ($($method:ident($ty:ty))*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<Self::Ok, Error> {
+                self.serialize_str(&v.to_string())
+            }
+        )*
+    }
+}
+
+impl<'a> ser::Serializer for ElementSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = ElementSeq<'a>;
+    type SerializeTuple = ElementSeq<'a>;
+    type SerializeTupleStruct = ElementSeq<'a>;
+    type SerializeTupleVariant = TupleVariant<'a>;
+    type SerializeMap = ElementMap<'a>;
+    type SerializeStruct = ElementMap<'a>;
+    type SerializeStructVariant = ElementMap<'a>;
+
+    serialize_display! {
+        serialize_bool(bool)
+        serialize_i8(i8) serialize_i16(i16) serialize_i32(i32) serialize_i64(i64)
+        serialize_u8(u8) serialize_u16(u16) serialize_u32(u32) serialize_u64(u64)
+        serialize_f32(f32) serialize_f64(f64)
+        serialize_char(char)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.write_text_element(None, v)
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<(), Error> {
+        Err(Error::Unsupported("bytes"))
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.write_text_element(None, "")
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<(), Error> {
+        self.write_text_element(Some(name), "")
+    }
+
+    fn serialize_unit_variant(self, name: &'static str, _: u32, variant: &'static str)
+        -> Result<(), Error>
+    {
+        self.write_text_element(Some(name), variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(mut self, name: &'static str, value: &T)
+        -> Result<(), Error>
+    {
+        if self.name.is_none() {
+            self.name = Some(name.to_string());
+        }
+
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        mut self,
+        name: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        let name = self.element_name(Some(name))?;
+        self.write_start(&name)?;
+        self.output.push('>');
+        value.serialize(self.child(variant))?;
+        write_end(self.output, &name);
+        Ok(())
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<ElementSeq<'a>, Error> {
+        if self.is_root {
+            return Err(Error::Unsupported("a sequence as the root element"));
+        }
+
+        // Child elements always have a name.
+        let name = self.name.unwrap_or_default();
+        Ok(ElementSeq {
+            output: self.output,
+            name,
+            namespaces: self.namespaces,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ElementSeq<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, len: usize) -> Result<ElementSeq<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        mut self,
+        name: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<TupleVariant<'a>, Error> {
+        let name = self.element_name(Some(name))?;
+        let variant = resolve_name(variant, self.namespaces, false)?;
+        self.write_start(&name)?;
+        self.output.push('>');
+        write_start(self.output, &variant, self.namespaces, false)?;
+        self.output.push('>');
+
+        Ok(TupleVariant {
+            output: self.output,
+            closing: format!("</{}></{}>", variant, name),
+            items: ValueList { items: Vec::new() },
+        })
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<ElementMap<'a>, Error> {
+        self.element_map(None, None)
+    }
+
+    fn serialize_struct(self, name: &'static str, _: usize) -> Result<ElementMap<'a>, Error> {
+        self.element_map(Some(name), None)
+    }
+
+    fn serialize_struct_variant(
+        mut self,
+        name: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<ElementMap<'a>, Error> {
+        let name = self.element_name(Some(name))?;
+        self.write_start(&name)?;
+        self.output.push('>');
+
+        let child = ElementSerializer {
+            output: self.output,
+            name: Some(variant.to_string()),
+            namespaces: self.namespaces,
+            is_root: false,
+        };
+        child.element_map(None, Some(format!("</{}>", name)))
+    }
+}
+
+
+/// Writes each item as an element with the same name.
+///
+/// Created by [`Serializer`](struct.Serializer.html) for sequences and tuples.
+pub struct ElementSeq<'a> {
+    output: &'a mut String,
+    name: String,
+    namespaces: &'a [(String, String)],
+}
+
+impl<'a> ElementSeq<'a> {
+    fn item<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(ElementSerializer {
+            output: self.output,
+            name: Some(self.name.clone()),
+            namespaces: self.namespaces,
+            is_root: false,
+        })
+    }
+}
+
+impl<'a> SerializeSeq for ElementSeq<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> SerializeTuple for ElementSeq<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> SerializeTupleStruct for ElementSeq<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+
+/// Writes tuple variant items as the variant element text.
+///
+/// Created by [`Serializer`](struct.Serializer.html) for tuple variants.
+pub struct TupleVariant<'a> {
+    output: &'a mut String,
+    closing: String,
+    items: ValueList,
+}
+
+impl<'a> SerializeTupleVariant for TupleVariant<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        SerializeSeq::serialize_element(&mut self.items, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        if let Some(text) = SerializeSeq::end(self.items)? {
+            escape_text(&text, self.output)?;
+        }
+
+        self.output.push_str(&self.closing);
+        Ok(())
+    }
+}
+
+
+/// Writes fields as attributes, child elements and text.
+///
+/// Created by [`Serializer`](struct.Serializer.html) for structs, maps and struct variants.
+//
+// Child elements are written into a separate buffer, since attributes
+// can follow them.
+pub struct ElementMap<'a> {
+    output: &'a mut String,
+    name: String,
+    namespaces: &'a [(String, String)],
+    is_root: bool,
+    attributes: String,
+    content: String,
+    closing: Option<String>,
+    key: Option<String>,
+}
+
+impl<'a> ElementMap<'a> {
+    fn field<T: ?Sized + Serialize>(&mut self, key: &str, value: &T) -> Result<(), Error> {
+        if key == TEXT_FIELD {
+            if let Some(text) = value.serialize(ValueSerializer)? {
+                escape_text(&text, &mut self.content)?;
+            }
+        } else if let Some(attr) = key.strip_prefix('@') {
+            if let Some(text) = value.serialize(ValueSerializer)? {
+                let name = resolve_name(attr, self.namespaces, true)?;
+                self.attributes.push(' ');
+                self.attributes.push_str(&name);
+                self.attributes.push_str("=\"");
+                escape_attribute(&text, &mut self.attributes)?;
+                self.attributes.push('"');
+            }
+        } else {
+            value.serialize(ElementSerializer {
+                output: &mut self.content,
+                name: Some(key.to_string()),
+                namespaces: self.namespaces,
+                is_root: false,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        write_start(self.output, &self.name, self.namespaces, self.is_root)?;
+        self.output.push_str(&self.attributes);
+        if self.content.is_empty() {
+            self.output.push_str("/>");
+        } else {
+            self.output.push('>');
+            self.output.push_str(&self.content);
+            write_end(self.output, &self.name);
+        }
+
+        if let Some(ref closing) = self.closing {
+            self.output.push_str(closing);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> SerializeMap for ElementMap<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        match key.serialize(ValueSerializer)? {
+            Some(key) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            None => Err(Error::Unsupported("an empty map key")),
+        }
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self.key.take().expect("serialize_value called before serialize_key");
+        self.field(&key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl<'a> SerializeStruct for ElementMap<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T)
+        -> Result<(), Error>
+    {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl<'a> SerializeStructVariant for ElementMap<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T)
+        -> Result<(), Error>
+    {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+
+// Converts a value into an attribute value or a text.
+//
+// Returns `None` for `None`.
+struct ValueSerializer;
+
+macro_rules! serialize_value_display {
+    ($($method:ident($ty:ty))*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<Self::Ok, Error> {
+                Ok(Some(v.to_string()))
+            }
+        )*
+    }
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Option<String>;
+    type Error = Error;
+    type SerializeSeq = ValueList;
+    type SerializeTuple = ValueList;
+    type SerializeTupleStruct = ValueList;
+    type SerializeTupleVariant = Impossible<Option<String>, Error>;
+    type SerializeMap = Impossible<Option<String>, Error>;
+    type SerializeStruct = Impossible<Option<String>, Error>;
+    type SerializeStructVariant = Impossible<Option<String>, Error>;
+
+    serialize_value_display! {
+        serialize_bool(bool)
+        serialize_i8(i8) serialize_i16(i16) serialize_i32(i32) serialize_i64(i64)
+        serialize_u8(u8) serialize_u16(u16) serialize_u32(u32) serialize_u64(u64)
+        serialize_f32(f32) serialize_f64(f64)
+        serialize_char(char) serialize_str(&str)
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<Option<String>, Error> {
+        Err(Error::Unsupported("bytes"))
+    }
+
+    fn serialize_none(self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Option<String>, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Option<String>, Error> {
+        Ok(Some(String::new()))
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Option<String>, Error> {
+        Ok(Some(String::new()))
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, _: u32, variant: &'static str)
+        -> Result<Option<String>, Error>
+    {
+        Ok(Some(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _: &'static str, value: &T)
+        -> Result<Option<String>, Error>
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Option<String>, Error> {
+        Err(Error::Unsupported("an enum variant with data as a text"))
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<ValueList, Error> {
+        Ok(ValueList { items: Vec::new() })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ValueList, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, len: usize) -> Result<ValueList, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _: &'static str, _: u32, _: &'static str, _: usize)
+        -> Result<Self::SerializeTupleVariant, Error>
+    {
+        Err(Error::Unsupported("an enum variant with data as a text"))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(Error::Unsupported("a map as a text"))
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct, Error> {
+        Err(Error::Unsupported("a struct as a text"))
+    }
+
+    fn serialize_struct_variant(self, _: &'static str, _: u32, _: &'static str, _: usize)
+        -> Result<Self::SerializeStructVariant, Error>
+    {
+        Err(Error::Unsupported("an enum variant with data as a text"))
+    }
+}
+
+// Whitespace-separated values, like `xs:list`.
+struct ValueList {
+    items: Vec<String>,
+}
+
+impl SerializeSeq for ValueList {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        if let Some(item) = value.serialize(ValueSerializer)? {
+            self.items.push(item);
+        }
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<String>, Error> {
+        Ok(Some(self.items.join(" ")))
+    }
+}
+
+impl SerializeTuple for ValueList {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Option<String>, Error> {
+        SerializeSeq::end(self)
+    }
+}
+
+impl SerializeTupleStruct for ValueList {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Option<String>, Error> {
+        SerializeSeq::end(self)
+    }
+}
+
+
+fn write_start(output: &mut String, name: &str, namespaces: &[(String, String)], is_root: bool)
+    -> Result<(), Error>
+{
+    output.push('<');
+    output.push_str(name);
+
+    if is_root {
+        for (prefix, uri) in namespaces {
+            if prefix.is_empty() {
+                output.push_str(" xmlns=\"");
+            } else if is_qname(prefix) && !prefix.contains(':') {
+                output.push_str(" xmlns:");
+                output.push_str(prefix);
+                output.push_str("=\"");
+            } else {
+                return Err(Error::InvalidName(prefix.clone()));
+            }
+
+            escape_attribute(uri, output)?;
+            output.push('"');
+        }
+    }
+
+    Ok(())
+}
+
+fn write_end(output: &mut String, name: &str) {
+    output.push_str("</");
+    output.push_str(name);
+    output.push('>');
+}
+
+// Resolves the `{uri}local` form and checks that the name is a valid QName.
+fn resolve_name(name: &str, namespaces: &[(String, String)], is_attribute: bool) -> Result<String, Error> {
+    let name = if name.starts_with('{') {
+        let end = match name.find('}') {
+            Some(end) => end,
+            None => return Err(Error::InvalidName(name.to_string())),
+        };
+
+        let (uri, local) = (&name[1..end], &name[end + 1..]);
+
+        // Unprefixed attributes are not in a namespace.
+        let prefix = if uri == XML_NAMESPACE {
+            Some("xml")
+        } else {
+            namespaces.iter()
+                .filter(|ns| !(is_attribute && ns.0.is_empty()))
+                .find(|ns| ns.1 == uri)
+                .map(|ns| ns.0.as_str())
+        };
+
+        match prefix {
+            Some("") => local.to_string(),
+            Some(prefix) => format!("{}:{}", prefix, local),
+            None => return Err(Error::UnknownNamespace(uri.to_string())),
+        }
+    } else {
+        name.to_string()
+    };
+
+    if !is_qname(&name) {
+        return Err(Error::InvalidName(name));
+    }
+
+    Ok(name)
+}
+
+fn is_qname(name: &str) -> bool {
+    let mut parts = name.splitn(2, ':');
+    parts.all(|part| {
+        let mut chars = part.chars();
+        match chars.next() {
+            Some(c) if c != ':' && c.is_xml_name_start() => {
+                chars.all(|c| c != ':' && c.is_xml_name())
+            }
+            _ => false,
+        }
+    })
+}
+
+fn escape_text(text: &str, output: &mut String) -> Result<(), Error> {
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            // Prevents `]]>`.
+            '>' => output.push_str("&gt;"),
+            // Otherwise will be normalized into `\n`.
+            '\r' => output.push_str("&#xD;"),
+            _ if c.is_xml_char() => output.push(c),
+            _ => return Err(Error::InvalidCharacter(c)),
+        }
+    }
+
+    Ok(())
+}
+
+fn escape_attribute(text: &str, output: &mut String) -> Result<(), Error> {
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '"' => output.push_str("&quot;"),
+            // Otherwise will be normalized into spaces.
+            '\t' => output.push_str("&#x9;"),
+            '\n' => output.push_str("&#xA;"),
+            '\r' => output.push_str("&#xD;"),
+            _ if c.is_xml_char() => output.push(c),
+            _ => return Err(Error::InvalidCharacter(c)),
+        }
+    }
+
+    Ok(())
+}
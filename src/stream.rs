@@ -2,7 +2,9 @@ use std::char;
 use std::str;
 use std::cmp;
 
+use xmlchar::is_xml11_char;
 use {
+    scan,
     TextPos,
     StreamError,
     StrSpan,
@@ -34,6 +36,8 @@ pub struct Stream<'a> {
     pos: usize,
     end: usize,
     span: StrSpan<'a>,
+    xml11: bool,
+    cr_line_breaks: bool,
 }
 
 impl<'a> From<&'a str> for Stream<'a> {
@@ -43,6 +47,8 @@ impl<'a> From<&'a str> for Stream<'a> {
             pos: 0,
             end: text.len(),
             span: text.into(),
+            xml11: false,
+            cr_line_breaks: false,
         }
     }
 }
@@ -54,6 +60,8 @@ impl<'a> From<StrSpan<'a>> for Stream<'a> {
             pos: 0,
             end: span.len(),
             span,
+            xml11: false,
+            cr_line_breaks: false,
         }
     }
 }
@@ -69,6 +77,52 @@ impl<'a> Stream<'a> {
         self.pos
     }
 
+    /// Enables the [XML 1.1](https://www.w3.org/TR/xml11/) mode.
+    ///
+    /// In this mode, NEL (`#x85`) and LINE SEPARATOR (`#x2028`) are treated as line ends,
+    /// and therefore as whitespaces. And character references to control characters,
+    /// like `&#x1;`, are allowed.
+    pub fn enable_xml11_mode(&mut self) {
+        self.xml11 = true;
+    }
+
+    /// Checks that the XML 1.1 mode is enabled.
+    pub fn is_xml11_mode(&self) -> bool {
+        self.xml11
+    }
+
+    /// Enables the CR line breaks mode.
+    ///
+    /// By default, only `\n` is treated as a line break by [`gen_text_pos`].
+    /// In this mode, a lone `\r` is treated as a line break too,
+    /// just like after the [line endings normalization].
+    /// `\r\n` is still a single line break.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Stream, TextPos};
+    ///
+    /// let mut s = Stream::from("a\rb\r\nc");
+    /// assert_eq!(s.gen_text_pos_from(2), TextPos::new(1, 3));
+    /// assert_eq!(s.gen_text_pos_from(5), TextPos::new(2, 1));
+    ///
+    /// s.enable_cr_line_breaks_mode();
+    /// assert_eq!(s.gen_text_pos_from(2), TextPos::new(2, 1));
+    /// assert_eq!(s.gen_text_pos_from(5), TextPos::new(3, 1));
+    /// ```
+    ///
+    /// [`gen_text_pos`]: #method.gen_text_pos
+    /// [line endings normalization]: https://www.w3.org/TR/xml/#sec-line-ends
+    pub fn enable_cr_line_breaks_mode(&mut self) {
+        self.cr_line_breaks = true;
+    }
+
+    /// Checks that the CR line breaks mode is enabled.
+    pub fn is_cr_line_breaks_mode(&self) -> bool {
+        self.cr_line_breaks
+    }
+
     /// Sets current position equal to the end.
     ///
     /// Used to indicate end of parsing on error.
@@ -219,7 +273,10 @@ impl<'a> Stream<'a> {
                     break;
                 }
             } else {
-                break;
+                match self.xml11_line_end_len() {
+                    0 => break,
+                    n => self.advance(n),
+                }
             }
         }
     }
@@ -227,16 +284,37 @@ impl<'a> Stream<'a> {
     /// Skips ASCII whitespaces.
     ///
     /// Accepted values: `' ' \n \r \t`.
+    /// In the XML 1.1 mode, NEL and LINE SEPARATOR are accepted too.
     pub fn skip_ascii_spaces(&mut self) {
         while !self.at_end() {
             if self.curr_byte_unchecked().is_xml_space() {
                 self.advance(1);
             } else {
-                break;
+                match self.xml11_line_end_len() {
+                    0 => break,
+                    n => self.advance(n),
+                }
             }
         }
     }
 
+    // Returns the byte length of the XML 1.1 specific line end
+    // at the current position or 0.
+    #[inline]
+    fn xml11_line_end_len(&self) -> usize {
+        if !self.xml11 {
+            return 0;
+        }
+
+        if self.starts_with("\u{85}".as_bytes()) {
+            2
+        } else if self.starts_with("\u{2028}".as_bytes()) {
+            3
+        } else {
+            0
+        }
+    }
+
     /// Checks that the stream starts with a selected text.
     ///
     /// We are using `&[u8]` instead of `&str` for performance reasons.
@@ -278,6 +356,8 @@ impl<'a> Stream<'a> {
                     is_space = true;
                 }
             }
+        } else if self.xml11_line_end_len() != 0 {
+            is_space = true;
         }
 
         is_space
@@ -325,7 +405,8 @@ impl<'a> Stream<'a> {
         if self.curr_byte()? != c {
             return Err(
                 StreamError::InvalidChar(
-                    vec![self.curr_byte_unchecked(), c],
+                    self.curr_char_unchecked(),
+                    c,
                     self.gen_text_pos(),
                 )
             );
@@ -341,16 +422,15 @@ impl<'a> Stream<'a> {
     ///
     /// # Errors
     ///
-    /// - `InvalidChar`
+    /// - `InvalidCharMultiple`
     /// - `UnexpectedEndOfStream`
     pub fn consume_either(&mut self, list: &[u8]) -> Result<u8> {
         assert!(!list.is_empty());
 
         let c = self.curr_byte()?;
         if !list.contains(&c) {
-            let mut v = list.to_vec();
-            v.insert(0, c);
-            return Err(StreamError::InvalidChar(v, self.gen_text_pos()));
+            let c = self.curr_char_unchecked();
+            return Err(StreamError::InvalidCharMultiple(c, list.to_vec(), self.gen_text_pos()));
         }
 
         self.advance(1);
@@ -375,7 +455,7 @@ impl<'a> Stream<'a> {
 
             let pos = self.gen_text_pos();
 
-            return Err(StreamError::InvalidString(vec![actual, expected], pos));
+            return Err(StreamError::InvalidString(actual, vec![expected], pos));
         }
 
         self.advance(text.len());
@@ -554,6 +634,42 @@ impl<'a> Stream<'a> {
         }
     }
 
+    /// Skips bytes until the specified one or until the end.
+    ///
+    /// Much faster than `skip_bytes`, since it checks a whole machine word at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Stream;
+    ///
+    /// let mut s = Stream::from("some text<");
+    /// s.skip_until_byte(b'<');
+    /// assert_eq!(s.pos(), 9);
+    /// ```
+    pub fn skip_until_byte(&mut self, c: u8) {
+        let t = &self.span.to_str().as_bytes()[self.pos..self.end];
+        let n = scan::find_byte(c, t).unwrap_or(t.len());
+        self.advance(n);
+    }
+
+    /// Skips bytes until the specified string or until the end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::Stream;
+    ///
+    /// let mut s = Stream::from("data ]] ]]>");
+    /// s.skip_until_string(b"]]>");
+    /// assert_eq!(s.pos(), 8);
+    /// ```
+    pub fn skip_until_string(&mut self, text: &[u8]) {
+        let t = &self.span.to_str().as_bytes()[self.pos..self.end];
+        let n = scan::find_string(text, t).unwrap_or(t.len());
+        self.advance(n);
+    }
+
     /// Consumes an XML character reference if there is one.
     ///
     /// On error will reset the position to the original.
@@ -599,7 +715,8 @@ impl<'a> Stream<'a> {
             }?;
 
             let c = char::from_u32(n).unwrap_or('\u{FFFD}');
-            if !c.is_xml_char() {
+            let is_valid = if self.xml11 { is_xml11_char(c) } else { c.is_xml_char() };
+            if !is_valid {
                 return Err(StreamError::InvalidReference);
             }
 
@@ -644,6 +761,9 @@ impl<'a> Stream<'a> {
     /// Calculates an absolute position at `pos`.
     ///
     /// This operation is very expensive. Use only for errors.
+    /// If you need positions for a lot of tokens, use a [`LineIndex`] instead.
+    ///
+    /// [`LineIndex`]: struct.LineIndex.html
     ///
     /// # Examples
     ///
@@ -669,8 +789,8 @@ impl<'a> Stream<'a> {
         let text = self.span.full_str();
         let end = self.pos + self.span.start();
         let mut row = 1;
-        for c in &text.as_bytes()[..end] {
-            if *c == b'\n' {
+        for (i, c) in text[..end].char_indices() {
+            if self.is_line_break(i, c) {
                 row += 1;
             }
         }
@@ -682,8 +802,8 @@ impl<'a> Stream<'a> {
         let text = self.span.full_str();
         let end = self.pos + self.span.start();
         let mut col = 1;
-        for c in text[..end].chars().rev() {
-            if c == '\n' {
+        for (i, c) in text[..end].char_indices().rev() {
+            if self.is_line_break(i, c) {
                 break;
             } else {
                 col += 1;
@@ -692,6 +812,28 @@ impl<'a> Stream<'a> {
 
         col
     }
+
+    // Checks that `c` at the absolute position `i` is a line break.
+    fn is_line_break(&self, i: usize, c: char) -> bool {
+        match c {
+            '\n' => true,
+            '\r' => self.is_lone_cr(i),
+            '\u{85}' | '\u{2028}' => self.xml11,
+            _ => false,
+        }
+    }
+
+    // Checks that `\r` at the absolute position `i` is a line break.
+    //
+    // In the XML 1.1 mode, `\r` followed by NEL is a single line end.
+    fn is_lone_cr(&self, i: usize) -> bool {
+        if !self.cr_line_breaks {
+            return false;
+        }
+
+        let next = &self.span.full_str().as_bytes()[i + 1..];
+        !(next.starts_with(b"\n") || (self.xml11 && next.starts_with("\u{85}".as_bytes())))
+    }
 }
 
 #[cfg(test)]
@@ -718,4 +860,18 @@ mod tests {
         s.advance(15);
         assert_eq!(s.gen_text_pos(), TextPos::new(2, 3));
     }
+
+    #[test]
+    fn text_pos_4() {
+        let mut s = Stream::from("a\r\nb\rc\r");
+        assert_eq!(s.gen_text_pos_from(2), TextPos::new(1, 3));
+        assert_eq!(s.gen_text_pos_from(5), TextPos::new(2, 3));
+
+        s.enable_cr_line_breaks_mode();
+        assert_eq!(s.gen_text_pos_from(1), TextPos::new(1, 2));
+        assert_eq!(s.gen_text_pos_from(2), TextPos::new(1, 3));
+        assert_eq!(s.gen_text_pos_from(3), TextPos::new(2, 1));
+        assert_eq!(s.gen_text_pos_from(5), TextPos::new(3, 1));
+        assert_eq!(s.gen_text_pos_from(7), TextPos::new(4, 1));
+    }
 }
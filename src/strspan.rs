@@ -1,6 +1,8 @@
+use std::borrow::Cow;
 use std::fmt;
 
 use {
+    scan,
     Stream,
 };
 
@@ -69,6 +71,50 @@ impl<'a> StrSpan<'a> {
         &self.text[self.start..self.end]
     }
 
+    /// Returns a span slice with [normalized] line endings.
+    ///
+    /// `\r\n` and a lone `\r` are replaced with `\n`.
+    /// Allocates only when the span contains `\r`.
+    ///
+    /// NEL and LINE SEPARATOR from XML 1.1 are not normalized.
+    /// Use [`to_normalized_str_xml11`] for XML 1.1 documents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    /// use xmlparser::StrSpan;
+    ///
+    /// assert_eq!(StrSpan::from("a\r\nb\rc").to_normalized_str(), "a\nb\nc");
+    /// assert!(matches!(StrSpan::from("a\nb").to_normalized_str(), Cow::Borrowed(_)));
+    /// ```
+    ///
+    /// [normalized]: https://www.w3.org/TR/xml/#sec-line-ends
+    /// [`to_normalized_str_xml11`]: #method.to_normalized_str_xml11
+    pub fn to_normalized_str(&self) -> Cow<'a, str> {
+        normalize_line_ends(self.to_str(), false)
+    }
+
+    /// Returns a span slice with line endings [normalized] according to XML 1.1.
+    ///
+    /// Like [`to_normalized_str`], but NEL (`#x85`), LINE SEPARATOR (`#x2028`)
+    /// and `\r` followed by NEL are replaced with `\n` too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::StrSpan;
+    ///
+    /// let span = StrSpan::from("a\r\u{85}b\u{85}c\u{2028}d");
+    /// assert_eq!(span.to_normalized_str_xml11(), "a\nb\nc\nd");
+    /// ```
+    ///
+    /// [normalized]: https://www.w3.org/TR/xml11/#sec-line-ends
+    /// [`to_normalized_str`]: #method.to_normalized_str
+    pub fn to_normalized_str_xml11(&self) -> Cow<'a, str> {
+        normalize_line_ends(self.to_str(), true)
+    }
+
     /// Returns a span slice as bytes.
     ///
     /// The same as `to_str` but does not involve char boundary checking.
@@ -126,6 +172,48 @@ impl<'a> fmt::Display for StrSpan<'a> {
     }
 }
 
+fn normalize_line_ends(text: &str, xml11: bool) -> Cow<'_, str> {
+    let mut idx = match find_line_end(text, xml11) {
+        Some(idx) => idx,
+        None => return Cow::Borrowed(text),
+    };
+
+    let mut s = String::with_capacity(text.len());
+    let mut start = 0;
+    loop {
+        s.push_str(&text[start..idx]);
+        s.push('\n');
+
+        let c = text[idx..].chars().next().unwrap();
+        start = idx + c.len_utf8();
+        if c == '\r' {
+            let rest = &text[start..];
+            if rest.starts_with('\n') {
+                start += 1;
+            } else if xml11 && rest.starts_with('\u{85}') {
+                start += 2;
+            }
+        }
+
+        match find_line_end(&text[start..], xml11) {
+            Some(i) => idx = start + i,
+            None => break,
+        }
+    }
+
+    s.push_str(&text[start..]);
+    Cow::Owned(s)
+}
+
+// Returns a position of the first char that has to be normalized.
+fn find_line_end(text: &str, xml11: bool) -> Option<usize> {
+    if xml11 {
+        text.find(&['\r', '\u{85}', '\u{2028}'][..])
+    } else {
+        scan::find_byte(b'\r', text.as_bytes())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,6 +238,29 @@ mod tests {
         assert_eq!(StrSpan::from("&#x20;text&#x20;text&#x20;").trim().to_str(), "text&#x20;text");
     }
 
+    #[test]
+    fn normalized_str_1() {
+        let text = "<a>\r\r\n\n\r</a>";
+        let span = StrSpan::from_substr(text, 3, 8);
+        assert_eq!(span.to_normalized_str(), "\n\n\n\n");
+    }
+
+    #[test]
+    fn normalized_str_2() {
+        assert_eq!(StrSpan::from("\r").to_normalized_str(), "\n");
+        assert_eq!(StrSpan::from("text\r").to_normalized_str(), "text\n");
+        assert_eq!(StrSpan::from("\r\ntext").to_normalized_str(), "\ntext");
+        assert_eq!(StrSpan::from("текст").to_normalized_str(), "текст");
+    }
+
+    #[test]
+    fn normalized_str_3() {
+        let text = "\r\u{85}\u{85}\u{2028}\r\n\r";
+        assert_eq!(StrSpan::from(text).to_normalized_str_xml11(), "\n\n\n\n\n");
+        assert_eq!(StrSpan::from(text).to_normalized_str(), "\n\u{85}\u{85}\u{2028}\n\n");
+        assert_eq!(StrSpan::from("текст").to_normalized_str_xml11(), "текст");
+    }
+
     #[test]
     fn do_not_trim_1() {
         assert_eq!(StrSpan::from("&#x40;text&#x50;").trim().to_str(), "&#x40;text&#x50;");
@@ -0,0 +1,241 @@
+use {
+    scan,
+    Error,
+    State,
+    StrSpan,
+    StreamError,
+    TokenType,
+    Tokenizer,
+};
+
+
+impl<'a> Tokenizer<'a> {
+    /// Skips the element that was just started.
+    ///
+    /// The same as [`element_source`], but without a result.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Token, Tokenizer};
+    ///
+    /// let mut p = Tokenizer::from("<root><skip><a/>text</skip><b/></root>");
+    /// p.next(); // <root
+    /// p.next(); // >
+    /// match p.next() {
+    ///     Some(Ok(Token::ElementStart(_, local))) => assert_eq!(local.to_str(), "skip"),
+    ///     _ => panic!(),
+    /// }
+    ///
+    /// p.skip_element().unwrap();
+    /// match p.next() {
+    ///     Some(Ok(Token::ElementStart(_, local))) => assert_eq!(local.to_str(), "b"),
+    ///     _ => panic!(),
+    /// }
+    /// ```
+    ///
+    /// [`element_source`]: #method.element_source
+    pub fn skip_element(&mut self) -> Result<(), Error> {
+        self.element_source().map(|_| ())
+    }
+
+    /// Consumes the element that was just started and returns its complete markup,
+    /// from the start tag to the matching close tag.
+    ///
+    /// Must be called after `Token::ElementStart` and before the `Token::ElementEnd`
+    /// of the start tag, i.e. attributes can be already processed.
+    /// Otherwise, `UnexpectedToken` is returned.
+    /// The next token will be the one after the element.
+    ///
+    /// Unlike a regular tokenization, only delimiters like `<` and `>` are searched for,
+    /// which is much faster. Tags are checked to be balanced and comments, CDATA and
+    /// processing instructions are skipped as a whole, but the element content
+    /// is not validated otherwise.
+    ///
+    /// On error, the tokenizer stops, like on any other error. Unless the recovery mode
+    /// is enabled, in which case the tokenizer is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Token, Tokenizer};
+    ///
+    /// let text = "<records><record id='1'><!-- </record> --><a/></record></records>";
+    /// let mut p = Tokenizer::from(text);
+    /// p.next(); // <records
+    /// p.next(); // >
+    /// p.next(); // <record
+    /// p.next(); // id='1'
+    ///
+    /// let record = p.element_source().unwrap();
+    /// assert_eq!(record.to_str(), "<record id='1'><!-- </record> --><a/></record>");
+    /// match p.next() {
+    ///     Some(Ok(Token::ElementEnd(_))) => {}
+    ///     _ => panic!(),
+    /// }
+    /// ```
+    pub fn element_source(&mut self) -> Result<StrSpan<'a>, Error> {
+        // The source is scanned from the current position, so peeked tokens are outdated.
+        self.peeked.clear();
+
+        let pos = self.pos();
+        if self.state != State::Attributes {
+            let text_pos = self.gen_text_pos_from(pos);
+            return Err(Error::UnexpectedToken(TokenType::ElementStart, text_pos, pos..pos));
+        }
+
+        // Attribute values cannot contain `<`, so the last one is the element start.
+        let span = self.stream.span();
+        let start = span.full_str()[..pos].rfind('<').unwrap_or(span.start());
+
+        let end = match self.scan_element(start, pos) {
+            Ok(end) => end,
+            Err(e) => {
+                if !self.recovery {
+                    self.stream.jump_to_end();
+                    self.state = State::End;
+                }
+
+                return Err(e);
+            }
+        };
+
+        self.stream.advance(end - pos);
+
+        // The same as after `ElementEnd::Close`.
+        if self.depth == 0 && !self.fragment_parsing {
+            self.state = State::AfterElements;
+        } else {
+            self.state = State::Elements;
+        }
+
+        Ok(StrSpan::from_substr(span.full_str(), start, end))
+    }
+
+    // Returns the end of the element that starts at `start`.
+    //
+    // `pos` must point to the inside of the element start tag, after the name.
+    fn scan_element(&self, start: usize, pos: usize) -> Result<usize, Error> {
+        let bytes = &self.stream.span().full_str().as_bytes()[..self.stream.span().end()];
+
+        // Names are compared as is, so slices are enough.
+        let mut stack = vec![(start, tag_name(bytes, start + 1))];
+        let mut pos = match find_tag_end(bytes, pos) {
+            Some(end) if bytes[end - 1] == b'/' => return Ok(end + 1),
+            Some(end) => end + 1,
+            None => return Err(self.unclosed_tag(&stack)),
+        };
+
+        while let Some(idx) = scan::find_byte(b'<', &bytes[pos..]) {
+            let tag_start = pos + idx;
+            let tail = &bytes[tag_start..];
+
+            if tail.starts_with(b"</") {
+                let tag_end = match scan::find_byte(b'>', tail) {
+                    Some(i) => tag_start + i,
+                    None => break,
+                };
+
+                let name = trim_end(&bytes[tag_start + 2..tag_end]);
+                let expected = stack.last().unwrap().1;
+                if name != expected {
+                    return Err(Error::UnexpectedCloseTag(
+                        to_str(expected).into(),
+                        to_str(name).into(),
+                        self.gen_text_pos_from(tag_start),
+                        tag_start..tag_end + 1,
+                    ));
+                }
+
+                stack.pop();
+                if stack.is_empty() {
+                    return Ok(tag_end + 1);
+                }
+
+                pos = tag_end + 1;
+            } else if tail.starts_with(b"<!--") {
+                pos = self.skip_until(bytes, tag_start, 4, b"-->", TokenType::Comment)?;
+            } else if tail.starts_with(b"<![CDATA[") {
+                pos = self.skip_until(bytes, tag_start, 9, b"]]>", TokenType::CDSect)?;
+            } else if tail.starts_with(b"<?") {
+                pos = self.skip_until(bytes, tag_start, 2, b"?>", TokenType::PI)?;
+            } else if tail.starts_with(b"<!") {
+                let text_pos = self.gen_text_pos_from(tag_start);
+                return Err(Error::UnknownToken(text_pos, tag_start..tag_start + 2));
+            } else {
+                match find_tag_end(bytes, tag_start + 1) {
+                    Some(end) => {
+                        if bytes[end - 1] != b'/' {
+                            stack.push((tag_start, tag_name(bytes, tag_start + 1)));
+                        }
+
+                        pos = end + 1;
+                    }
+                    None => {
+                        stack.push((tag_start, tag_name(bytes, tag_start + 1)));
+                        break;
+                    }
+                }
+            }
+        }
+
+        Err(self.unclosed_tag(&stack))
+    }
+
+    // Returns the position after the `end` string.
+    fn skip_until(&self, bytes: &[u8], start: usize, len: usize, end: &[u8], token_type: TokenType)
+        -> Result<usize, Error>
+    {
+        match scan::find_string(end, &bytes[start + len..]) {
+            Some(idx) => Ok(start + len + idx + end.len()),
+            None => {
+                let e = StreamError::UnexpectedEndOfStream;
+                let pos = self.gen_text_pos_from(start);
+                Err(Error::InvalidToken(token_type, pos, Some(Box::new(e)), start..bytes.len()))
+            }
+        }
+    }
+
+    fn unclosed_tag(&self, stack: &[(usize, &[u8])]) -> Error {
+        let (start, name) = *stack.last().unwrap();
+        let pos = self.gen_text_pos_from(start);
+        Error::UnclosedTag(to_str(name).into(), pos, start..start + 1 + name.len())
+    }
+}
+
+// Returns a tag name that starts at `pos`.
+fn tag_name(bytes: &[u8], pos: usize) -> &[u8] {
+    let len = bytes[pos..].iter()
+        .position(|c| matches!(*c, b' ' | b'\t' | b'\n' | b'\r' | b'/' | b'>'))
+        .unwrap_or(bytes.len() - pos);
+    &bytes[pos..pos + len]
+}
+
+// Returns the position of `>` that ends a start tag, skipping quoted attribute values.
+fn find_tag_end(bytes: &[u8], mut pos: usize) -> Option<usize> {
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'>' => return Some(pos),
+            quote @ b'"' | quote @ b'\'' => {
+                pos += 1 + scan::find_byte(quote, &bytes[pos + 1..])?;
+            }
+            _ => {}
+        }
+
+        pos += 1;
+    }
+
+    None
+}
+
+fn trim_end(bytes: &[u8]) -> &[u8] {
+    let len = bytes.iter().rposition(|c| !matches!(*c, b' ' | b'\t' | b'\n' | b'\r'))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    &bytes[..len]
+}
+
+// Names are always split at ASCII bytes, so they are valid UTF-8.
+fn to_str(bytes: &[u8]) -> &str {
+    ::std::str::from_utf8(bytes).unwrap_or_default()
+}
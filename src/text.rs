@@ -0,0 +1,241 @@
+use std::borrow::Cow;
+
+use {
+    CharsKind,
+    Error,
+    Reference,
+    Stream,
+    StrSpan,
+    Token,
+    Tokenizer,
+};
+
+
+/// A logical text chunk produced by [`MergeText`].
+///
+/// [`MergeText`]: struct.MergeText.html
+#[derive(Clone, PartialEq, Debug)]
+pub struct TextChunk<'a> {
+    text: Cow<'a, str>,
+    spans: Vec<StrSpan<'a>>,
+}
+
+impl<'a> TextChunk<'a> {
+    /// Returns the unescaped text.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the unescaped text.
+    ///
+    /// The text is borrowed when the chunk consists of a single span
+    /// without references and `\r`.
+    pub fn into_text(self) -> Cow<'a, str> {
+        self.text
+    }
+
+    /// Returns spans of the merged `Text`, `Whitespaces` and `Cdata` tokens.
+    ///
+    /// CDATA spans contain only the section content.
+    pub fn spans(&self) -> &[StrSpan<'a>] {
+        &self.spans
+    }
+}
+
+
+/// A token produced by [`MergeText`].
+///
+/// [`MergeText`]: struct.MergeText.html
+#[derive(Clone, PartialEq, Debug)]
+pub enum MergedToken<'a> {
+    /// Adjacent `Text`, `Whitespaces` and `Cdata` tokens.
+    Text(TextChunk<'a>),
+    /// Any other token.
+    Token(Token<'a>),
+}
+
+
+/// An iterator adapter that merges adjacent character data tokens.
+///
+/// Created by [`Tokenizer::merge_text`].
+///
+/// [`Tokenizer::merge_text`]: struct.Tokenizer.html#method.merge_text
+pub struct MergeText<'a> {
+    tokenizer: Tokenizer<'a>,
+    pending: Option<Result<Token<'a>, Error>>,
+}
+
+impl<'a> Tokenizer<'a> {
+    /// Merges adjacent `Text`, `Whitespaces` and `Cdata` tokens into a single
+    /// unescaped [`TextChunk`].
+    ///
+    /// Character references and predefined entity references are unescaped
+    /// and line endings are normalized. References to other entities
+    /// and invalid references are left as is.
+    ///
+    /// Any other token, including comments and processing instructions,
+    /// ends the current chunk.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{MergedToken, Tokenizer};
+    ///
+    /// let text = "<p>a &lt; b <![CDATA[& c]]>&#x21;</p>";
+    /// let chunk = Tokenizer::from(text).merge_text()
+    ///     .filter_map(|t| match t.unwrap() {
+    ///         MergedToken::Text(chunk) => Some(chunk),
+    ///         _ => None,
+    ///     })
+    ///     .next().unwrap();
+    ///
+    /// assert_eq!(chunk.as_str(), "a < b & c!");
+    /// assert_eq!(chunk.spans().len(), 3);
+    /// ```
+    ///
+    /// [`TextChunk`]: struct.TextChunk.html
+    pub fn merge_text(self) -> MergeText<'a> {
+        MergeText {
+            tokenizer: self,
+            pending: None,
+        }
+    }
+}
+
+impl<'a> Iterator for MergeText<'a> {
+    type Item = Result<MergedToken<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut spans = Vec::new();
+
+        while let Some(token) = self.pending.take().or_else(|| self.tokenizer.next()) {
+            match token {
+                Ok(Token::Text(span)) | Ok(Token::Whitespaces(span)) => {
+                    spans.push((span, CharsKind::CharData));
+                }
+                Ok(Token::Cdata(span)) => spans.push((span, CharsKind::CData)),
+                token => {
+                    if spans.is_empty() {
+                        return Some(token.map(MergedToken::Token));
+                    }
+
+                    // Return the text first.
+                    self.pending = Some(token);
+                    break;
+                }
+            }
+        }
+
+        if spans.is_empty() {
+            return None;
+        }
+
+        let chunk = make_chunk(&spans, self.tokenizer.is_xml11_mode());
+        Some(Ok(MergedToken::Text(chunk)))
+    }
+}
+
+fn make_chunk<'a>(spans: &[(StrSpan<'a>, CharsKind)], xml11: bool) -> TextChunk<'a> {
+    let text = if let [(span, kind)] = *spans {
+        unescape(span.to_str(), kind, xml11)
+    } else {
+        let len = spans.iter().map(|&(span, _)| span.len()).sum();
+        let mut s = String::with_capacity(len);
+        for &(span, kind) in spans {
+            push_unescaped(span.to_str(), kind, xml11, &mut s);
+        }
+
+        Cow::Owned(s)
+    };
+
+    TextChunk {
+        text,
+        spans: spans.iter().map(|&(span, _)| span).collect(),
+    }
+}
+
+// Returns the text with normalized line endings and, unless it's CDATA, resolved references.
+//
+// Attribute values are normalized according to
+// https://www.w3.org/TR/xml/#AVNormalize, as if they have the CDATA type.
+pub(crate) fn unescape(text: &str, kind: CharsKind, xml11: bool) -> Cow<'_, str> {
+    let needs_unescape = text.bytes().any(|c| match c {
+        b'\r' => true,
+        // NEL and LINE SEPARATOR start bytes.
+        0xC2 | 0xE2 => xml11,
+        b'&' => kind != CharsKind::CData,
+        b'\n' | b'\t' => kind == CharsKind::AttributeValue,
+        _ => false,
+    });
+
+    if needs_unescape {
+        let mut s = String::with_capacity(text.len());
+        push_unescaped(text, kind, xml11, &mut s);
+        Cow::Owned(s)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+const NEL: &[u8] = "\u{85}".as_bytes();
+const LS: &[u8] = "\u{2028}".as_bytes();
+
+fn push_unescaped(text: &str, kind: CharsKind, xml11: bool, out: &mut String) {
+    let line_break = if kind == CharsKind::AttributeValue { ' ' } else { '\n' };
+
+    let bytes = text.as_bytes();
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\r' => {
+                out.push_str(&text[start..i]);
+                out.push(line_break);
+
+                i += 1;
+                if bytes.get(i) == Some(&b'\n') {
+                    i += 1;
+                } else if xml11 && bytes[i..].starts_with(NEL) {
+                    i += NEL.len();
+                }
+                start = i;
+            }
+            // NEL and LINE SEPARATOR are line breaks in XML 1.1.
+            0xC2 | 0xE2 if xml11 && (bytes[i..].starts_with(NEL) || bytes[i..].starts_with(LS)) => {
+                out.push_str(&text[start..i]);
+                out.push(line_break);
+
+                i += if bytes[i] == 0xC2 { NEL.len() } else { LS.len() };
+                start = i;
+            }
+            b'\n' | b'\t' if kind == CharsKind::AttributeValue => {
+                out.push_str(&text[start..i]);
+                out.push(' ');
+
+                i += 1;
+                start = i;
+            }
+            b'&' if kind != CharsKind::CData => {
+                let mut s = Stream::from(&text[i..]);
+                if xml11 {
+                    s.enable_xml11_mode();
+                }
+
+                // Predefined entities are returned as `CharRef` too.
+                match s.try_consume_reference() {
+                    Some(Reference::CharRef(c)) => {
+                        out.push_str(&text[start..i]);
+                        out.push(c);
+
+                        i += s.pos();
+                        start = i;
+                    }
+                    _ => i += 1,
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    out.push_str(&text[start..]);
+}
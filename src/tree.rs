@@ -0,0 +1,517 @@
+//! A minimal read-only DOM built on top of the `Tokenizer`.
+//!
+//! The tree is intended for simple tools that just need to walk a document.
+//! If you need namespaces resolution, entities expansion and so on -
+//! use [roxmltree](https://github.com/RazrFalcon/roxmltree) instead.
+//!
+//! # Example
+//!
+//! ```
+//! use xmlparser::tree::Document;
+//!
+//! let doc = Document::parse("<svg><rect id='r1'/><!-- comment --></svg>").unwrap();
+//! let svg = doc.root_element().unwrap();
+//! let rect = svg.first_child().unwrap();
+//! assert!(rect.has_tag_name("rect"));
+//! assert_eq!(rect.attribute("id").unwrap().to_str(), "r1");
+//! assert!(rect.next_sibling().unwrap().is_comment());
+//! ```
+
+use std::fmt;
+use std::ptr;
+
+use sax::{
+    is_qname_eq,
+    TagStack,
+};
+use {
+    Attribute,
+    ElementEnd,
+    Error,
+    Stream,
+    StrSpan,
+    TextPos,
+    Token,
+    Tokenizer,
+};
+
+
+type NodeId = usize;
+
+
+/// A node kind.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NodeKind<'a> {
+    /// The document node.
+    ///
+    /// Contains the root element, comments and processing instructions outside of it.
+    Root,
+    /// An element node.
+    ///
+    /// Contains prefix and local part of the qualified name.
+    Element(StrSpan<'a>, StrSpan<'a>),
+    /// A text node.
+    ///
+    /// Whitespaces-only text is stored as a text node too.
+    Text(StrSpan<'a>),
+    /// A comment node.
+    Comment(StrSpan<'a>),
+    /// A processing instruction node.
+    ///
+    /// Contains target and an optional content.
+    ProcessingInstruction(StrSpan<'a>, Option<StrSpan<'a>>),
+    /// A CDATA node.
+    Cdata(StrSpan<'a>),
+}
+
+
+struct NodeData<'a> {
+    kind: NodeKind<'a>,
+    span: StrSpan<'a>,
+    parent: Option<NodeId>,
+    prev_sibling: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+    first_child: Option<NodeId>,
+    last_child: Option<NodeId>,
+    attributes: (usize, usize),
+}
+
+
+/// An XML tree.
+pub struct Document<'a> {
+    text: &'a str,
+    // Keeps the tokenizer modes, so positions have the same line breaks
+    // as the tokenizer errors.
+    stream: Stream<'a>,
+    nodes: Vec<NodeData<'a>>,
+    attrs: Vec<Attribute<'a>>,
+}
+
+impl<'a> Document<'a> {
+    /// Parses the input XML string.
+    pub fn parse(text: &'a str) -> Result<Document<'a>, Error> {
+        Self::from_tokenizer(Tokenizer::from(text))
+    }
+
+    /// Builds a tree from the tokenizer output.
+    ///
+    /// The tokenizer can be preconfigured, e.g. to parse a fragment.
+    /// In this case the document node can contain more than one element.
+    ///
+    /// # Errors
+    ///
+    /// In addition to the tokenizer errors, checks that close tags are matching
+    /// the open ones, which produces `UnexpectedCloseTag` and `UnclosedTag` errors.
+    pub fn from_tokenizer(tokenizer: Tokenizer<'a>) -> Result<Document<'a>, Error> {
+        let text = tokenizer.stream.span().full_str();
+        let mut doc = Document {
+            text,
+            stream: Stream::from(text),
+            nodes: Vec::new(),
+            attrs: Vec::new(),
+        };
+
+        if tokenizer.is_xml11_mode() {
+            doc.stream.enable_xml11_mode();
+        }
+        if tokenizer.is_cr_line_breaks_mode() {
+            doc.stream.enable_cr_line_breaks_mode();
+        }
+
+        doc.push_node(None, NodeKind::Root, StrSpan::from(text));
+        doc.build(tokenizer)?;
+
+        Ok(doc)
+    }
+
+    fn build(&mut self, mut tokenizer: Tokenizer<'a>) -> Result<(), Error> {
+        // Open elements with their node IDs.
+        let mut stack = TagStack::new();
+        let mut parent = 0;
+        let mut element = 0;
+
+        loop {
+            let token_start = tokenizer.pos();
+            let token = match tokenizer.next() {
+                Some(token) => token?,
+                None => break,
+            };
+            let token_end = tokenizer.pos();
+
+            let markup_start = tokenizer.markup_start(token_start);
+            let markup = StrSpan::from_substr(self.text, markup_start, token_end);
+
+            match token {
+                Token::ElementStart(prefix, local) => {
+                    element = self.push_node(Some(parent), NodeKind::Element(prefix, local), markup);
+                    let idx = self.attrs.len();
+                    self.nodes[element].attributes = (idx, idx);
+                }
+                Token::Attribute((prefix, local), value) => {
+                    self.attrs.push(Attribute::new(prefix, local, value));
+                    self.nodes[element].attributes.1 = self.attrs.len();
+                }
+                Token::ElementEnd(ElementEnd::Open) => {
+                    if let NodeKind::Element(prefix, local) = self.nodes[element].kind {
+                        stack.push(prefix, local, self.nodes[element].span.start(), element);
+                    }
+
+                    parent = element;
+                }
+                Token::ElementEnd(ElementEnd::Empty) => {
+                    self.set_span_end(element, token_end);
+                }
+                Token::ElementEnd(ElementEnd::Close(prefix, local)) => {
+                    let tag = stack.close(&tokenizer, prefix, local, markup_start)?;
+                    self.set_span_end(tag.data, token_end);
+                    parent = stack.last().map_or(0, |tag| tag.data);
+                }
+                Token::Text(text) | Token::Whitespaces(text) => {
+                    self.push_node(Some(parent), NodeKind::Text(text), text);
+                }
+                Token::Cdata(text) => {
+                    self.push_node(Some(parent), NodeKind::Cdata(text), markup);
+                }
+                Token::Comment(text) => {
+                    self.push_node(Some(parent), NodeKind::Comment(text), markup);
+                }
+                Token::ProcessingInstruction(target, content) => {
+                    let kind = NodeKind::ProcessingInstruction(target, content);
+                    self.push_node(Some(parent), kind, markup);
+                }
+                Token::Declaration(..) => {
+                    // The XML 1.1 mode can be enabled by the XML declaration.
+                    if tokenizer.is_xml11_mode() {
+                        self.stream.enable_xml11_mode();
+                    }
+                }
+                Token::DtdStart(..)
+                | Token::EmptyDtd(..)
+                | Token::EntityDeclaration(..)
+                | Token::DtdEnd => {}
+            }
+        }
+
+        stack.finish(&tokenizer)
+    }
+
+    fn push_node(&mut self, parent: Option<NodeId>, kind: NodeKind<'a>, span: StrSpan<'a>) -> NodeId {
+        let id = self.nodes.len();
+
+        let mut prev_sibling = None;
+        if let Some(parent) = parent {
+            prev_sibling = self.nodes[parent].last_child;
+            if let Some(prev) = prev_sibling {
+                self.nodes[prev].next_sibling = Some(id);
+            } else {
+                self.nodes[parent].first_child = Some(id);
+            }
+            self.nodes[parent].last_child = Some(id);
+        }
+
+        self.nodes.push(NodeData {
+            kind,
+            span,
+            parent,
+            prev_sibling,
+            next_sibling: None,
+            first_child: None,
+            last_child: None,
+            attributes: (0, 0),
+        });
+
+        id
+    }
+
+    fn set_span_end(&mut self, id: NodeId, end: usize) {
+        let start = self.nodes[id].span.start();
+        self.nodes[id].span = StrSpan::from_substr(self.text, start, end);
+    }
+
+    pub(crate) fn text_pos_at(&self, pos: usize) -> TextPos {
+        self.stream.gen_text_pos_from(pos)
+    }
+
+    /// Returns the document node.
+    pub fn root(&self) -> Node<'a, '_> {
+        Node { id: 0, doc: self }
+    }
+
+    /// Returns the first element of the document node.
+    ///
+    /// Can be `None` only when the document has no elements at all.
+    pub fn root_element(&self) -> Option<Node<'a, '_>> {
+        self.root().children().find(|n| n.is_element())
+    }
+
+    /// Returns an iterator over all document nodes in the document order.
+    ///
+    /// Includes the document node itself.
+    pub fn descendants(&self) -> Descendants<'a, '_> {
+        self.root().descendants()
+    }
+
+    /// Returns the original text.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Checks that the document was parsed in the XML 1.1 mode.
+    ///
+    /// The mode is enabled by `version="1.1"` in the XML declaration
+    /// or by the tokenizer the document was built from.
+    pub fn is_xml11_mode(&self) -> bool {
+        self.stream.is_xml11_mode()
+    }
+}
+
+impl<'a> fmt::Debug for Document<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Document({} nodes)", self.nodes.len())
+    }
+}
+
+
+/// A node handle.
+#[derive(Clone, Copy)]
+pub struct Node<'a, 'd> {
+    id: NodeId,
+    doc: &'d Document<'a>,
+}
+
+impl<'a, 'd> Node<'a, 'd> {
+    fn data(&self) -> &'d NodeData<'a> {
+        &self.doc.nodes[self.id]
+    }
+
+    fn node(&self, id: Option<NodeId>) -> Option<Node<'a, 'd>> {
+        id.map(|id| Node { id, doc: self.doc })
+    }
+
+    /// Returns the node kind.
+    pub fn kind(&self) -> NodeKind<'a> {
+        self.data().kind
+    }
+
+    /// Returns the node's document.
+    pub fn document(&self) -> &'d Document<'a> {
+        self.doc
+    }
+
+    /// Checks that the node is the document node.
+    pub fn is_root(&self) -> bool {
+        self.id == 0
+    }
+
+    /// Checks that the node is an element.
+    pub fn is_element(&self) -> bool {
+        matches!(self.kind(), NodeKind::Element(..))
+    }
+
+    /// Checks that the node is a text node.
+    pub fn is_text(&self) -> bool {
+        matches!(self.kind(), NodeKind::Text(..))
+    }
+
+    /// Checks that the node is a comment.
+    pub fn is_comment(&self) -> bool {
+        matches!(self.kind(), NodeKind::Comment(..))
+    }
+
+    /// Checks that the node is a processing instruction.
+    pub fn is_pi(&self) -> bool {
+        matches!(self.kind(), NodeKind::ProcessingInstruction(..))
+    }
+
+    /// Checks that the node is a CDATA section.
+    pub fn is_cdata(&self) -> bool {
+        matches!(self.kind(), NodeKind::Cdata(..))
+    }
+
+    /// Returns element's prefix and local part of the qualified name.
+    ///
+    /// Returns `None` for non-element nodes.
+    pub fn tag_name(&self) -> Option<(StrSpan<'a>, StrSpan<'a>)> {
+        match self.kind() {
+            NodeKind::Element(prefix, local) => Some((prefix, local)),
+            _ => None,
+        }
+    }
+
+    /// Checks that the node is an element with the specified qualified name.
+    ///
+    /// `name` can be either `local` or `prefix:local`.
+    pub fn has_tag_name(&self, name: &str) -> bool {
+        match self.kind() {
+            NodeKind::Element(prefix, local) => is_qname_eq(prefix, local, name),
+            _ => false,
+        }
+    }
+
+    /// Returns the content of a text, comment or CDATA node.
+    ///
+    /// Text is returned as is, without unescaping.
+    pub fn text(&self) -> Option<StrSpan<'a>> {
+        match self.kind() {
+            NodeKind::Text(text)
+            | NodeKind::Comment(text)
+            | NodeKind::Cdata(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Returns element's attributes.
+    ///
+    /// Returns an empty slice for non-element nodes.
+    pub fn attributes(&self) -> &'d [Attribute<'a>] {
+        let (start, end) = self.data().attributes;
+        &self.doc.attrs[start..end]
+    }
+
+    /// Returns a value of the attribute with the specified qualified name.
+    ///
+    /// `name` can be either `local` or `prefix:local`.
+    pub fn attribute(&self, name: &str) -> Option<StrSpan<'a>> {
+        self.attributes().iter().find(|a| a.has_name(name)).map(|a| a.value())
+    }
+
+    /// Returns the node source.
+    ///
+    /// For elements it includes the whole element, from `<` to the end of the close tag.
+    pub fn span(&self) -> StrSpan<'a> {
+        self.data().span
+    }
+
+    /// Calculates the node position in the original text.
+    ///
+    /// This operation is expensive.
+    pub fn text_pos(&self) -> TextPos {
+        self.doc.text_pos_at(self.span().start())
+    }
+
+    /// Returns the parent node.
+    pub fn parent(&self) -> Option<Node<'a, 'd>> {
+        self.node(self.data().parent)
+    }
+
+    /// Returns the previous sibling node.
+    pub fn prev_sibling(&self) -> Option<Node<'a, 'd>> {
+        self.node(self.data().prev_sibling)
+    }
+
+    /// Returns the next sibling node.
+    pub fn next_sibling(&self) -> Option<Node<'a, 'd>> {
+        self.node(self.data().next_sibling)
+    }
+
+    /// Returns the first child node.
+    pub fn first_child(&self) -> Option<Node<'a, 'd>> {
+        self.node(self.data().first_child)
+    }
+
+    /// Returns the last child node.
+    pub fn last_child(&self) -> Option<Node<'a, 'd>> {
+        self.node(self.data().last_child)
+    }
+
+    /// Checks that the node has child nodes.
+    pub fn has_children(&self) -> bool {
+        self.data().first_child.is_some()
+    }
+
+    /// Returns an iterator over child nodes.
+    pub fn children(&self) -> Children<'a, 'd> {
+        Children { next: self.first_child() }
+    }
+
+    /// Returns an iterator over ancestor nodes, starting at the parent.
+    pub fn ancestors(&self) -> Ancestors<'a, 'd> {
+        Ancestors { next: self.parent() }
+    }
+
+    /// Returns an iterator over the node and its descendants in the document order.
+    pub fn descendants(&self) -> Descendants<'a, 'd> {
+        Descendants { root: *self, next: Some(*self) }
+    }
+}
+
+impl<'a, 'd> PartialEq for Node<'a, 'd> {
+    fn eq(&self, other: &Node<'a, 'd>) -> bool {
+        self.id == other.id && ptr::eq(self.doc, other.doc)
+    }
+}
+
+impl<'a, 'd> fmt::Debug for Node<'a, 'd> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Node({:?} {}..{})", self.kind(), self.span().start(), self.span().end())
+    }
+}
+
+
+/// An iterator over child nodes.
+#[derive(Clone)]
+pub struct Children<'a, 'd> {
+    next: Option<Node<'a, 'd>>,
+}
+
+impl<'a, 'd> Iterator for Children<'a, 'd> {
+    type Item = Node<'a, 'd>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+        self.next = node.next_sibling();
+        Some(node)
+    }
+}
+
+
+/// An iterator over ancestor nodes.
+#[derive(Clone)]
+pub struct Ancestors<'a, 'd> {
+    next: Option<Node<'a, 'd>>,
+}
+
+impl<'a, 'd> Iterator for Ancestors<'a, 'd> {
+    type Item = Node<'a, 'd>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+        self.next = node.parent();
+        Some(node)
+    }
+}
+
+
+/// An iterator over descendant nodes.
+#[derive(Clone)]
+pub struct Descendants<'a, 'd> {
+    root: Node<'a, 'd>,
+    next: Option<Node<'a, 'd>>,
+}
+
+impl<'a, 'd> Iterator for Descendants<'a, 'd> {
+    type Item = Node<'a, 'd>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+
+        self.next = node.first_child();
+        if self.next.is_none() {
+            let mut curr = node;
+            while curr != self.root {
+                if let Some(sibling) = curr.next_sibling() {
+                    self.next = Some(sibling);
+                    break;
+                }
+
+                match curr.parent() {
+                    Some(parent) => curr = parent,
+                    None => break,
+                }
+            }
+        }
+
+        Some(node)
+    }
+}
+
@@ -0,0 +1,1181 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::ops::Range;
+
+use automaton::{Automaton, Particle};
+use sax::{OpenTag, TagStack};
+use text::unescape;
+use {
+    CharsKind,
+    ElementEnd,
+    EntityDefinition,
+    Error,
+    ExternalId,
+    Stream,
+    StreamError,
+    StrSpan,
+    Token,
+    TokenType,
+    Tokenizer,
+    XmlCharExt,
+};
+
+
+/// A validity constraint violation.
+///
+/// Produced by [`Tokenizer::validate`] and by the XML Schema validator
+/// as a part of `Error::InvalidDocument`.
+///
+/// All names are qualified names as written in the document.
+///
+/// [`Tokenizer::validate`]: struct.Tokenizer.html#method.validate
+#[derive(Clone, PartialEq, Debug)]
+pub enum ValidityError {
+    /// The document has no DOCTYPE.
+    NoDtd,
+
+    /// The root element doesn't match the DOCTYPE name.
+    ///
+    /// Contains the expected and the actual names.
+    RootElementMismatch(Box<str>, Box<str>),
+
+    /// An element type is declared more than once.
+    DuplicateElementDecl(Box<str>),
+
+    /// An element is not declared in the DTD.
+    UndeclaredElement(Box<str>),
+
+    /// A child element doesn't match the parent content model.
+    ///
+    /// Contains the parent and the child names.
+    UnexpectedElement(Box<str>, Box<str>),
+
+    /// Text inside an element with element content.
+    UnexpectedText(Box<str>),
+
+    /// Content inside an element declared as `EMPTY`.
+    NotEmpty(Box<str>),
+
+    /// An element was closed before its content model was matched.
+    IncompleteContent(Box<str>),
+
+    /// An attribute is not declared in the DTD.
+    ///
+    /// Contains the element and the attribute names.
+    UndeclaredAttribute(Box<str>, Box<str>),
+
+    /// A `#REQUIRED` attribute is missing.
+    ///
+    /// Contains the element and the attribute names.
+    MissingAttribute(Box<str>, Box<str>),
+
+    /// An attribute value doesn't match the attribute type.
+    ///
+    /// Contains the attribute name and a description of the expected value.
+    InvalidAttributeValue(Box<str>, Box<str>),
+
+    /// A `#FIXED` attribute has a different value.
+    ///
+    /// Contains the attribute name and the fixed value.
+    FixedAttributeValue(Box<str>, Box<str>),
+
+    /// An `ID` value is used more than once.
+    DuplicateId(Box<str>),
+
+    /// An `IDREF` value doesn't match any `ID` in the document.
+    UnknownId(Box<str>),
+
+    /// An element value doesn't match the element simple type.
+    ///
+    /// Contains the element name and a description of the expected value.
+    InvalidElementValue(Box<str>, Box<str>),
+}
+
+impl ValidityError {
+    /// Returns a stable, machine-readable error code.
+    ///
+    /// | Code       | Error                   |
+    /// |------------|-------------------------|
+    /// | `XML-V001` | `NoDtd`                 |
+    /// | `XML-V002` | `RootElementMismatch`   |
+    /// | `XML-V003` | `DuplicateElementDecl`  |
+    /// | `XML-V004` | `UndeclaredElement`     |
+    /// | `XML-V005` | `UnexpectedElement`     |
+    /// | `XML-V006` | `UnexpectedText`        |
+    /// | `XML-V007` | `NotEmpty`              |
+    /// | `XML-V008` | `IncompleteContent`     |
+    /// | `XML-V009` | `UndeclaredAttribute`   |
+    /// | `XML-V010` | `MissingAttribute`      |
+    /// | `XML-V011` | `InvalidAttributeValue` |
+    /// | `XML-V012` | `FixedAttributeValue`   |
+    /// | `XML-V013` | `DuplicateId`           |
+    /// | `XML-V014` | `UnknownId`             |
+    /// | `XML-V015` | `InvalidElementValue`   |
+    ///
+    /// Codes are never reused or changed, only appended.
+    pub fn code(&self) -> &'static str {
+        match *self {
+            ValidityError::NoDtd => "XML-V001",
+            ValidityError::RootElementMismatch(..) => "XML-V002",
+            ValidityError::DuplicateElementDecl(..) => "XML-V003",
+            ValidityError::UndeclaredElement(..) => "XML-V004",
+            ValidityError::UnexpectedElement(..) => "XML-V005",
+            ValidityError::UnexpectedText(..) => "XML-V006",
+            ValidityError::NotEmpty(..) => "XML-V007",
+            ValidityError::IncompleteContent(..) => "XML-V008",
+            ValidityError::UndeclaredAttribute(..) => "XML-V009",
+            ValidityError::MissingAttribute(..) => "XML-V010",
+            ValidityError::InvalidAttributeValue(..) => "XML-V011",
+            ValidityError::FixedAttributeValue(..) => "XML-V012",
+            ValidityError::DuplicateId(..) => "XML-V013",
+            ValidityError::UnknownId(..) => "XML-V014",
+            ValidityError::InvalidElementValue(..) => "XML-V015",
+        }
+    }
+
+    pub(crate) fn spec_url(&self) -> &'static str {
+        match *self {
+            ValidityError::NoDtd => "https://www.w3.org/TR/xml/#dt-valid",
+            ValidityError::RootElementMismatch(..) => "https://www.w3.org/TR/xml/#vc-roottype",
+            ValidityError::DuplicateElementDecl(..) => "https://www.w3.org/TR/xml/#EDUnique",
+            ValidityError::UndeclaredElement(..)
+            | ValidityError::UnexpectedElement(..)
+            | ValidityError::UnexpectedText(..)
+            | ValidityError::NotEmpty(..)
+            | ValidityError::IncompleteContent(..) => "https://www.w3.org/TR/xml/#elementvalid",
+            ValidityError::UndeclaredAttribute(..)
+            | ValidityError::InvalidAttributeValue(..) => "https://www.w3.org/TR/xml/#ValueType",
+            ValidityError::MissingAttribute(..) => "https://www.w3.org/TR/xml/#RequiredAttr",
+            ValidityError::FixedAttributeValue(..) => "https://www.w3.org/TR/xml/#FixedAttr",
+            ValidityError::DuplicateId(..) => "https://www.w3.org/TR/xml/#id",
+            ValidityError::UnknownId(..) => "https://www.w3.org/TR/xml/#idref",
+            ValidityError::InvalidElementValue(..) => "https://www.w3.org/TR/xmlschema-1/#cvc-type",
+        }
+    }
+}
+
+impl fmt::Display for ValidityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidityError::NoDtd => {
+                write!(f, "the document has no DTD")
+            }
+            ValidityError::RootElementMismatch(ref expected, ref actual) => {
+                write!(f, "expected '{}' root element not '{}'", expected, actual)
+            }
+            ValidityError::DuplicateElementDecl(ref name) => {
+                write!(f, "element '{}' is declared more than once", name)
+            }
+            ValidityError::UndeclaredElement(ref name) => {
+                write!(f, "element '{}' is not declared", name)
+            }
+            ValidityError::UnexpectedElement(ref parent, ref child) => {
+                write!(f, "element '{}' is not allowed here in '{}'", child, parent)
+            }
+            ValidityError::UnexpectedText(ref name) => {
+                write!(f, "text is not allowed in '{}'", name)
+            }
+            ValidityError::NotEmpty(ref name) => {
+                write!(f, "element '{}' is declared EMPTY but has content", name)
+            }
+            ValidityError::IncompleteContent(ref name) => {
+                write!(f, "element '{}' content is incomplete", name)
+            }
+            ValidityError::UndeclaredAttribute(ref element, ref name) => {
+                write!(f, "attribute '{}' is not declared for '{}'", name, element)
+            }
+            ValidityError::MissingAttribute(ref element, ref name) => {
+                write!(f, "required attribute '{}' is missing in '{}'", name, element)
+            }
+            ValidityError::InvalidAttributeValue(ref name, ref expected) => {
+                write!(f, "attribute '{}' value must be {}", name, expected)
+            }
+            ValidityError::FixedAttributeValue(ref name, ref value) => {
+                write!(f, "attribute '{}' value must be '{}'", name, value)
+            }
+            ValidityError::DuplicateId(ref id) => {
+                write!(f, "ID '{}' is already used", id)
+            }
+            ValidityError::UnknownId(ref id) => {
+                write!(f, "IDREF '{}' doesn't match any ID", id)
+            }
+            ValidityError::InvalidElementValue(ref name, ref expected) => {
+                write!(f, "element '{}' value must be {}", name, expected)
+            }
+        }
+    }
+}
+
+
+#[derive(Clone, PartialEq, Debug)]
+enum AttributeType<'a> {
+    Cdata,
+    Id,
+    IdRef,
+    IdRefs,
+    Entity,
+    Entities,
+    NmToken,
+    NmTokens,
+    Notation(Vec<&'a str>),
+    Enumeration(Vec<&'a str>),
+}
+
+impl<'a> AttributeType<'a> {
+    fn expected(&self) -> Box<str> {
+        let s = match *self {
+            AttributeType::Cdata => "a string",
+            AttributeType::Id | AttributeType::IdRef => "a name",
+            AttributeType::IdRefs => "a list of names",
+            AttributeType::Entity => "a declared unparsed entity name",
+            AttributeType::Entities => "a list of declared unparsed entity names",
+            AttributeType::NmToken => "a name token",
+            AttributeType::NmTokens => "a list of name tokens",
+            AttributeType::Notation(ref list) | AttributeType::Enumeration(ref list) => {
+                let list: Vec<String> = list.iter().map(|s| format!("'{}'", s)).collect();
+                return format!("one of {}", list.join(", ")).into();
+            }
+        };
+
+        s.into()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum DefaultDecl<'a> {
+    Required,
+    Implied,
+    Fixed(StrSpan<'a>),
+    Value(StrSpan<'a>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct AttributeDecl<'a> {
+    prefix: StrSpan<'a>,
+    local: StrSpan<'a>,
+    name: &'a str,
+    kind: AttributeType<'a>,
+    default: DefaultDecl<'a>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum ContentSpec<'a> {
+    Empty,
+    Any,
+    // Allowed child elements.
+    Mixed(Vec<&'a str>),
+    Children(Automaton<&'a str>),
+}
+
+// Declarations from the internal subset.
+struct Dtd<'a> {
+    root: &'a str,
+    elements: HashMap<&'a str, ContentSpec<'a>>,
+    attributes: HashMap<&'a str, Vec<AttributeDecl<'a>>>,
+    // Only unparsed entities, since `ENTITY` values cannot reference parsed ones.
+    entities: Vec<&'a str>,
+}
+
+
+impl<'a> Tokenizer<'a> {
+    /// Validates the token stream against the DTD internal subset.
+    ///
+    /// Returns all tokens as is, interleaved with validity errors.
+    /// An error is returned right before the token that caused it.
+    ///
+    /// Checks:
+    ///
+    /// - That the root element matches the DOCTYPE name.
+    /// - That elements and attributes are declared.
+    /// - Element content against `EMPTY`, `ANY`, mixed and children content models.
+    /// - `#REQUIRED` and `#FIXED` attributes.
+    /// - Attribute values against their types, including enumerations.
+    /// - `ID` uniqueness and `IDREF`/`IDREFS` resolution.
+    ///
+    /// Missing attributes with a default value are returned as `Attribute` tokens
+    /// right before the start tag end. Their spans point to the DTD.
+    ///
+    /// Validity errors are returned as `Error::InvalidDocument` and the validation
+    /// continues after them. Like [`parse_with`], checks that close tags are matching
+    /// the open ones. The iterator stops after the first well-formedness error.
+    ///
+    /// External subsets and parameter entities are not loaded.
+    /// General entity references in text are not expanded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmlparser::{Error, Token, Tokenizer, ValidityError};
+    ///
+    /// let text = "\
+    /// <!DOCTYPE list [
+    ///     <!ELEMENT list (item+)>
+    ///     <!ELEMENT item EMPTY>
+    ///     <!ATTLIST item kind (a|b) 'a'>
+    /// ]>
+    /// <list><item kind='c'/></list>";
+    ///
+    /// let mut errors = Vec::new();
+    /// let mut defaults = 0;
+    /// for token in Tokenizer::from(text).validate() {
+    ///     match token {
+    ///         Ok(Token::Attribute(..)) => defaults += 1,
+    ///         Ok(_) => {}
+    ///         Err(e) => errors.push(e.to_string()),
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(errors, vec!["attribute 'kind' value must be one of 'a', 'b' at 6:13"]);
+    /// ```
+    ///
+    /// [`parse_with`]: struct.Tokenizer.html#method.parse_with
+    pub fn validate(self) -> Validate<'a> {
+        let rules = DtdRules {
+            dtd: None,
+            subset_start: 0,
+            tag: None,
+            has_root: false,
+            ids: HashSet::new(),
+            idrefs: Vec::new(),
+        };
+
+        Validate(Validator::new(self, rules))
+    }
+}
+
+
+// A validator state available to the rules.
+pub(crate) struct Context<'a, F> {
+    pub(crate) tokenizer: Tokenizer<'a>,
+    // Open elements with the rules data.
+    pub(crate) stack: TagStack<'a, F>,
+    pub(crate) queue: VecDeque<Result<Token<'a>, Error>>,
+}
+
+impl<'a, F> Context<'a, F> {
+    pub(crate) fn push_error(&mut self, e: ValidityError, range: Range<usize>) {
+        let pos = self.tokenizer.gen_text_pos_from(range.start);
+        self.queue.push_back(Err(Error::InvalidDocument(Box::new(e), pos, range)));
+    }
+
+    pub(crate) fn text(&self) -> &'a str {
+        self.tokenizer.stream.span().full_str()
+    }
+}
+
+// Validation rules used by the `Validator`.
+#[allow(unused_variables)]
+pub(crate) trait Rules<'a> {
+    // Data stored for each open element.
+    type Frame;
+
+    // Processes a token other than a close tag.
+    //
+    // The token must be pushed to the queue after errors caused by it.
+    fn process_token(&mut self, cx: &mut Context<'a, Self::Frame>, token: Token<'a>,
+                     token_start: usize) -> Result<(), Error>;
+
+    // Called when an element is closed by a matching close tag.
+    fn end_element(&mut self, cx: &mut Context<'a, Self::Frame>, tag: OpenTag<'a, Self::Frame>,
+                   range: Range<usize>);
+
+    // Called at the end of the document when all elements are closed.
+    fn finish(&mut self, cx: &mut Context<'a, Self::Frame>) {}
+}
+
+// An iterator over tokens interleaved with validity errors.
+//
+// Checks that close tags are matching the open ones and stops after
+// the first well-formedness error.
+pub(crate) struct Validator<'a, R: Rules<'a>> {
+    cx: Context<'a, R::Frame>,
+    rules: R,
+    done: bool,
+}
+
+impl<'a, R: Rules<'a>> Validator<'a, R> {
+    pub(crate) fn new(tokenizer: Tokenizer<'a>, rules: R) -> Self {
+        let cx = Context {
+            tokenizer,
+            stack: TagStack::new(),
+            queue: VecDeque::new(),
+        };
+
+        Validator { cx, rules, done: false }
+    }
+
+    fn process_token(&mut self, token: Token<'a>, token_start: usize) -> Result<(), Error> {
+        if let Token::ElementEnd(ElementEnd::Close(prefix, local)) = token {
+            let cx = &mut self.cx;
+            let tag = cx.stack.close(&cx.tokenizer, prefix, local, token_start)?;
+            let range = token_start..cx.tokenizer.pos();
+            self.rules.end_element(cx, tag, range);
+            cx.queue.push_back(Ok(token));
+            return Ok(());
+        }
+
+        self.rules.process_token(&mut self.cx, token, token_start)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.cx.stack.finish(&self.cx.tokenizer)?;
+        self.rules.finish(&mut self.cx);
+        Ok(())
+    }
+}
+
+impl<'a, R: Rules<'a>> Iterator for Validator<'a, R> {
+    type Item = Result<Token<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.cx.queue.pop_front() {
+                return Some(item);
+            }
+
+            if self.done {
+                return None;
+            }
+
+            let token_start = self.cx.tokenizer.pos();
+            let res = match self.cx.tokenizer.next() {
+                Some(Ok(token)) => self.process_token(token, token_start),
+                Some(Err(e)) => Err(e),
+                None => {
+                    self.done = true;
+                    self.finish()
+                }
+            };
+
+            if let Err(e) = res {
+                self.done = true;
+                self.cx.queue.push_back(Err(e));
+            }
+        }
+    }
+}
+
+
+// A start tag being parsed.
+struct Tag<'a> {
+    prefix: StrSpan<'a>,
+    local: StrSpan<'a>,
+    name: &'a str,
+    start: usize,
+    // Names of the parsed attributes.
+    attributes: Vec<&'a str>,
+}
+
+// An open element.
+struct Frame<'a> {
+    name: &'a str,
+    // Content model states.
+    states: Vec<usize>,
+}
+
+// The DTD validation rules.
+struct DtdRules<'a> {
+    dtd: Option<Dtd<'a>>,
+    // The position after the internal subset start.
+    subset_start: usize,
+    tag: Option<Tag<'a>>,
+    has_root: bool,
+    ids: HashSet<String>,
+    // `IDREF` values with their ranges, resolved at the end of the document.
+    idrefs: Vec<(String, Range<usize>)>,
+}
+
+type DtdContext<'a> = Context<'a, Frame<'a>>;
+
+/// An iterator over validated tokens.
+///
+/// Created by [`Tokenizer::validate`].
+///
+/// [`Tokenizer::validate`]: struct.Tokenizer.html#method.validate
+pub struct Validate<'a>(Validator<'a, DtdRules<'a>>);
+
+impl<'a> Iterator for Validate<'a> {
+    type Item = Result<Token<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl<'a> Rules<'a> for DtdRules<'a> {
+    type Frame = Frame<'a>;
+
+    fn process_token(&mut self, cx: &mut DtdContext<'a>, token: Token<'a>,
+                     token_start: usize) -> Result<(), Error> {
+        match token {
+            Token::DtdStart(name, _) | Token::EmptyDtd(name, _) => {
+                self.dtd = Some(Dtd {
+                    root: name.to_str(),
+                    elements: HashMap::new(),
+                    attributes: HashMap::new(),
+                    entities: Vec::new(),
+                });
+                self.subset_start = cx.tokenizer.pos();
+            }
+            Token::EntityDeclaration(name, ref def) => {
+                let is_unparsed = is_unparsed_entity(cx.text(), def, cx.tokenizer.pos());
+                if let Some(ref mut dtd) = self.dtd {
+                    if is_unparsed {
+                        dtd.entities.push(name.to_str());
+                    }
+                }
+            }
+            Token::DtdEnd => {
+                let text = cx.text();
+                let end = match text[..cx.tokenizer.pos()].rfind(']') {
+                    Some(idx) => idx,
+                    None => cx.tokenizer.pos(),
+                };
+
+                let span = StrSpan::from_substr(text, self.subset_start, end);
+                self.parse_subset(cx, span)?;
+            }
+            Token::ElementStart(prefix, local) => {
+                let start = cx.tokenizer.markup_start(token_start);
+                let name = qname_str(cx.text(), prefix, local);
+                self.start_element(cx, name, start..local.end());
+                self.tag = Some(Tag { prefix, local, name, start, attributes: Vec::new() });
+            }
+            Token::Attribute((prefix, local), value) => {
+                let start = if prefix.is_empty() { local.start() } else { prefix.start() };
+                let name = qname_str(cx.text(), prefix, local);
+                self.check_attribute(cx, name, value, start..value.end() + 1);
+            }
+            Token::ElementEnd(ElementEnd::Open) => {
+                self.end_tag(cx);
+            }
+            Token::ElementEnd(ElementEnd::Empty) => {
+                self.end_tag(cx);
+                if let Some(tag) = cx.stack.pop() {
+                    let pos = cx.tokenizer.pos();
+                    self.end_element(cx, tag, pos - 2..pos);
+                }
+            }
+            Token::Text(text) | Token::Cdata(text) => {
+                let is_space = matches!(token, Token::Text(_))
+                    && text.as_bytes().iter().all(|&c| matches!(c, b' ' | b'\t' | b'\n' | b'\r'));
+                self.check_content(cx, Some(is_space), token_start..cx.tokenizer.pos());
+            }
+            Token::Whitespaces(_) => {
+                self.check_content(cx, Some(true), token_start..cx.tokenizer.pos());
+            }
+            Token::Comment(_) | Token::ProcessingInstruction(..) => {
+                self.check_content(cx, None, token_start..cx.tokenizer.pos());
+            }
+            Token::ElementEnd(ElementEnd::Close(..)) | Token::Declaration(..) => {}
+        }
+
+        cx.queue.push_back(Ok(token));
+        Ok(())
+    }
+
+    fn end_element(&mut self, cx: &mut DtdContext<'a>, tag: OpenTag<'a, Frame<'a>>,
+                   range: Range<usize>) {
+        let frame = tag.data;
+        let is_incomplete = match self.dtd.as_ref().and_then(|dtd| dtd.elements.get(frame.name)) {
+            Some(ContentSpec::Children(model)) => !model.accepts(&frame.states),
+            _ => false,
+        };
+
+        if is_incomplete {
+            cx.push_error(ValidityError::IncompleteContent(frame.name.into()), range);
+        }
+    }
+
+    fn finish(&mut self, cx: &mut DtdContext<'a>) {
+        let idrefs = ::std::mem::take(&mut self.idrefs);
+        for (id, range) in idrefs {
+            if !self.ids.contains(&id) {
+                cx.push_error(ValidityError::UnknownId(id.into()), range);
+            }
+        }
+    }
+}
+
+impl<'a> DtdRules<'a> {
+    fn start_element(&mut self, cx: &mut DtdContext<'a>, name: &'a str, range: Range<usize>) {
+        let mut errors = Vec::new();
+        if cx.stack.is_empty() && !self.has_root {
+            self.has_root = true;
+            match self.dtd {
+                None => errors.push(ValidityError::NoDtd),
+                Some(ref dtd) if dtd.root != name => {
+                    errors.push(ValidityError::RootElementMismatch(dtd.root.into(), name.into()));
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(ref dtd) = self.dtd {
+            if !dtd.elements.contains_key(name) {
+                errors.push(ValidityError::UndeclaredElement(name.into()));
+            }
+
+            if let Some(parent) = cx.stack.last_mut().map(|tag| &mut tag.data) {
+                match dtd.elements.get(parent.name) {
+                    Some(&ContentSpec::Empty) => {
+                        errors.push(ValidityError::NotEmpty(parent.name.into()));
+                    }
+                    Some(ContentSpec::Mixed(names)) if !names.contains(&name) => {
+                        errors.push(ValidityError::UnexpectedElement(parent.name.into(), name.into()));
+                    }
+                    Some(ContentSpec::Children(model)) => {
+                        let next = model.step(&parent.states, |n| *n == name);
+                        if next.is_empty() {
+                            // Keep the current states, so the next siblings are still checked.
+                            errors.push(ValidityError::UnexpectedElement(parent.name.into(), name.into()));
+                        } else {
+                            parent.states = next;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for e in errors {
+            cx.push_error(e, range.clone());
+        }
+    }
+
+    fn check_attribute(&mut self, cx: &mut DtdContext<'a>, name: &'a str, value: StrSpan<'a>,
+                       range: Range<usize>) {
+        let element = match self.tag {
+            Some(ref mut tag) => {
+                tag.attributes.push(name);
+                tag.name
+            }
+            None => return,
+        };
+
+        let dtd = match self.dtd {
+            Some(ref dtd) if dtd.elements.contains_key(element) => dtd,
+            // Undeclared elements are already reported.
+            _ => return,
+        };
+
+        let decl = match dtd.attributes.get(element).and_then(|list| list.iter().find(|d| d.name == name)) {
+            Some(decl) => decl,
+            None => {
+                let e = ValidityError::UndeclaredAttribute(element.into(), name.into());
+                cx.push_error(e, range);
+                return;
+            }
+        };
+
+        let xml11 = cx.tokenizer.is_xml11_mode();
+        let normalized = normalize(value, &decl.kind, xml11);
+        let mut errors = Vec::new();
+        if let Err(expected) = check_value(&decl.kind, &normalized, Some(&dtd.entities)) {
+            errors.push(ValidityError::InvalidAttributeValue(name.into(), expected));
+        } else if let DefaultDecl::Fixed(fixed) = decl.default {
+            if normalize(fixed, &decl.kind, xml11) != normalized {
+                errors.push(ValidityError::FixedAttributeValue(name.into(), fixed.to_str().into()));
+            }
+        }
+
+        if errors.is_empty() {
+            match decl.kind {
+                AttributeType::Id if !self.ids.insert(normalized.to_string()) => {
+                    errors.push(ValidityError::DuplicateId(normalized.into()));
+                }
+                AttributeType::IdRef | AttributeType::IdRefs => {
+                    let value_range = value.start()..value.end();
+                    for id in normalized.split(' ') {
+                        self.idrefs.push((id.to_string(), value_range.clone()));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for e in errors {
+            cx.push_error(e, range.clone());
+        }
+    }
+
+    // Reports missing required attributes, returns default ones and opens the element.
+    fn end_tag(&mut self, cx: &mut DtdContext<'a>) {
+        let tag = match self.tag.take() {
+            Some(tag) => tag,
+            None => return,
+        };
+
+        let range = tag.start..tag.local.end();
+        let mut frame = Frame { name: tag.name, states: Vec::new() };
+
+        let mut errors = Vec::new();
+        if let Some(ref dtd) = self.dtd {
+            if let Some(list) = dtd.attributes.get(tag.name) {
+                for decl in list.iter().filter(|d| !tag.attributes.contains(&d.name)) {
+                    match decl.default {
+                        DefaultDecl::Required => {
+                            errors.push(ValidityError::MissingAttribute(tag.name.into(), decl.name.into()));
+                        }
+                        DefaultDecl::Fixed(value) | DefaultDecl::Value(value) => {
+                            let token = Token::Attribute((decl.prefix, decl.local), value);
+                            cx.queue.push_back(Ok(token));
+                        }
+                        DefaultDecl::Implied => {}
+                    }
+                }
+            }
+
+            if let Some(ContentSpec::Children(model)) = dtd.elements.get(tag.name) {
+                frame.states = model.start();
+            }
+        }
+
+        for e in errors {
+            cx.push_error(e, range.clone());
+        }
+
+        cx.stack.push(tag.prefix, tag.local, tag.start, frame);
+    }
+
+    // Checks a non-element content. `None` is for comments and processing instructions.
+    fn check_content(&mut self, cx: &mut DtdContext<'a>, is_space: Option<bool>, range: Range<usize>) {
+        let name = match cx.stack.last() {
+            Some(tag) => tag.data.name,
+            None => return,
+        };
+
+        let e = match self.dtd.as_ref().and_then(|dtd| dtd.elements.get(name)) {
+            Some(&ContentSpec::Empty) => ValidityError::NotEmpty(name.into()),
+            Some(&ContentSpec::Children(_)) if is_space == Some(false) => {
+                ValidityError::UnexpectedText(name.into())
+            }
+            _ => return,
+        };
+
+        cx.push_error(e, range);
+    }
+
+    fn parse_subset(&mut self, cx: &mut DtdContext<'a>, span: StrSpan<'a>) -> Result<(), Error> {
+        let mut s = Stream::from(span);
+        if cx.tokenizer.is_xml11_mode() {
+            s.enable_xml11_mode();
+        }
+
+        loop {
+            s.skip_spaces();
+            if s.at_end() {
+                break;
+            }
+
+            let start = s.pos();
+            if s.starts_with(b"<!ELEMENT") {
+                s.advance(9);
+                let (name, spec) = map_decl_err(parse_element_decl(&mut s), TokenType::ElementDecl, &s, start)?;
+                let dtd = self.dtd.as_mut().unwrap();
+                if dtd.elements.contains_key(name.to_str()) {
+                    let e = ValidityError::DuplicateElementDecl(name.to_str().into());
+                    cx.push_error(e, span.start() + start..name.end());
+                } else {
+                    dtd.elements.insert(name.to_str(), spec);
+                }
+            } else if s.starts_with(b"<!ATTLIST") {
+                s.advance(9);
+                let (name, list) = map_decl_err(parse_attlist_decl(&mut s), TokenType::AttlistDecl, &s, start)?;
+                self.add_attributes(cx, name.to_str(), list);
+            } else if s.starts_with(b"<!--") {
+                // Comments, processing instructions, entity and notation declarations
+                // are already checked by the tokenizer.
+                s.skip_until_string(b"-->");
+                let _ = s.skip_string(b"-->");
+            } else if s.starts_with(b"<?") {
+                s.skip_until_string(b"?>");
+                let _ = s.skip_string(b"?>");
+            } else if s.starts_with(b"<!") {
+                while let Some(c) = s.get_curr_byte() {
+                    s.advance(1);
+                    match c {
+                        b'"' | b'\'' => {
+                            s.skip_until_byte(c);
+                            let _ = s.consume_byte(c);
+                        }
+                        b'>' => break,
+                        _ => {}
+                    }
+                }
+            } else {
+                // Parameter entity references.
+                s.advance(1);
+                s.skip_bytes(|_, c| c != b'<');
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_attributes(&mut self, cx: &mut DtdContext<'a>, element: &'a str, list: Vec<AttributeDecl<'a>>) {
+        let mut errors = Vec::new();
+        {
+            let xml11 = cx.tokenizer.is_xml11_mode();
+            let decls = self.dtd.as_mut().unwrap().attributes.entry(element).or_default();
+            for decl in list {
+                // The first declaration is binding.
+                if decls.iter().any(|d| d.name == decl.name) {
+                    continue;
+                }
+
+                if let DefaultDecl::Fixed(value) | DefaultDecl::Value(value) = decl.default {
+                    let normalized = normalize(value, &decl.kind, xml11);
+                    if let Err(expected) = check_value(&decl.kind, &normalized, None) {
+                        let e = ValidityError::InvalidAttributeValue(decl.name.into(), expected);
+                        errors.push((e, value.start()..value.end()));
+                    }
+                }
+
+                decls.push(decl);
+            }
+        }
+
+        for (e, range) in errors {
+            cx.push_error(e, range);
+        }
+    }
+}
+
+
+// Returns a qualified name as written in the document.
+pub(crate) fn qname_str<'a>(text: &'a str, prefix: StrSpan<'a>, local: StrSpan<'a>) -> &'a str {
+    if prefix.is_empty() {
+        local.to_str()
+    } else {
+        &text[prefix.start()..local.end()]
+    }
+}
+
+fn map_decl_err<T>(res: Result<T, StreamError>, token_type: TokenType, s: &Stream, start: usize)
+    -> Result<T, Error>
+{
+    res.map_err(|e| {
+        let pos = s.gen_text_pos_from(start);
+        let offset = s.span().start();
+        Error::InvalidToken(token_type, pos, Some(Box::new(e)), offset + start..offset + s.pos())
+    })
+}
+
+// Normalizes an attribute value according to its type.
+//
+// https://www.w3.org/TR/xml/#AVNormalize
+fn normalize<'a>(value: StrSpan<'a>, kind: &AttributeType, xml11: bool) -> Cow<'a, str> {
+    let value = unescape(value.to_str(), CharsKind::AttributeValue, xml11);
+    let is_normalized = !value.starts_with(' ') && !value.ends_with(' ') && !value.contains("  ");
+    if *kind == AttributeType::Cdata || is_normalized {
+        value
+    } else {
+        let list: Vec<&str> = value.split(' ').filter(|s| !s.is_empty()).collect();
+        Cow::Owned(list.join(" "))
+    }
+}
+
+// Returns a description of the expected value on error.
+//
+// Checks that an entity declaration ending at `end` has an `NDATA` part.
+//
+// The tokenizer skips `NDataDecl`, so we have to check the declaration source.
+fn is_unparsed_entity(text: &str, def: &EntityDefinition, end: usize) -> bool {
+    let literal = match *def {
+        EntityDefinition::ExternalId(ExternalId::System(literal))
+        | EntityDefinition::ExternalId(ExternalId::Public(_, literal)) => literal,
+        EntityDefinition::EntityValue(_) => return false,
+    };
+
+    // Skip the closing quote. The declaration is already checked by the tokenizer,
+    // so only spaces and `NDATA` can follow.
+    text[literal.end() + 1..end].trim_start().starts_with("NDATA")
+}
+
+// Entity names are not checked when `entities` is `None`.
+fn check_value(kind: &AttributeType, value: &str, entities: Option<&[&str]>) -> Result<(), Box<str>> {
+    let is_entity = |v: &str| is_name(v) && entities.map_or(true, |list| list.contains(&v));
+    let is_valid = match *kind {
+        AttributeType::Cdata => true,
+        AttributeType::Id | AttributeType::IdRef => is_name(value),
+        AttributeType::IdRefs => !value.is_empty() && value.split(' ').all(is_name),
+        AttributeType::Entity => is_entity(value),
+        AttributeType::Entities => !value.is_empty() && value.split(' ').all(is_entity),
+        AttributeType::NmToken => is_nmtoken(value),
+        AttributeType::NmTokens => !value.is_empty() && value.split(' ').all(is_nmtoken),
+        AttributeType::Notation(ref list) | AttributeType::Enumeration(ref list) => list.contains(&value),
+    };
+
+    if is_valid {
+        Ok(())
+    } else {
+        Err(kind.expected())
+    }
+}
+
+fn is_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().map_or(false, |c| c.is_xml_name_start()) && chars.all(|c| c.is_xml_name())
+}
+
+fn is_nmtoken(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_xml_name())
+}
+
+// elementdecl ::= '<!ELEMENT' S Name S contentspec S? '>'
+// contentspec ::= 'EMPTY' | 'ANY' | Mixed | children
+fn parse_element_decl<'a>(s: &mut Stream<'a>) -> Result<(StrSpan<'a>, ContentSpec<'a>), StreamError> {
+    s.consume_spaces()?;
+    let name = s.consume_name()?;
+    s.consume_spaces()?;
+
+    let spec = if s.starts_with(b"EMPTY") {
+        s.advance(5);
+        ContentSpec::Empty
+    } else if s.starts_with(b"ANY") {
+        s.advance(3);
+        ContentSpec::Any
+    } else {
+        s.consume_byte(b'(')?;
+        s.skip_spaces();
+        if s.starts_with(b"#PCDATA") {
+            s.advance(7);
+            ContentSpec::Mixed(parse_mixed(s)?)
+        } else {
+            let mut names = Vec::new();
+            let particle = parse_group(s, &mut names)?;
+            let particle = parse_occurrence(s, particle);
+            ContentSpec::Children(Automaton::new(&particle, names))
+        }
+    };
+
+    s.skip_spaces();
+    s.consume_byte(b'>')?;
+
+    Ok((name, spec))
+}
+
+// Mixed ::= '(' S? '#PCDATA' (S? '|' S? Name)* S? ')*' | '(' S? '#PCDATA' S? ')'
+fn parse_mixed<'a>(s: &mut Stream<'a>) -> Result<Vec<&'a str>, StreamError> {
+    let mut names = Vec::new();
+    loop {
+        s.skip_spaces();
+        if s.curr_byte()? != b'|' {
+            break;
+        }
+
+        s.advance(1);
+        s.skip_spaces();
+        names.push(s.consume_name()?.to_str());
+    }
+
+    s.consume_byte(b')')?;
+    if !names.is_empty() {
+        s.consume_byte(b'*')?;
+    } else if s.is_curr_byte_eq(b'*') {
+        s.advance(1);
+    }
+
+    Ok(names)
+}
+
+// cp     ::= (Name | choice | seq) ('?' | '*' | '+')?
+// choice ::= '(' S? cp ( S? '|' S? cp )+ S? ')'
+// seq    ::= '(' S? cp ( S? ',' S? cp )* S? ')'
+//
+// The opening bracket is already consumed.
+fn parse_group<'a>(s: &mut Stream<'a>, names: &mut Vec<&'a str>) -> Result<Particle, StreamError> {
+    s.skip_spaces();
+    let mut items = vec![parse_cp(s, names)?];
+    let mut separator = None;
+    loop {
+        s.skip_spaces();
+        let c = s.curr_byte()?;
+        if c == b')' {
+            s.advance(1);
+            break;
+        }
+
+        match separator {
+            Some(sep) if c != sep => {
+                return Err(StreamError::InvalidCharMultiple(s.curr_char()?, vec![sep, b')'], s.gen_text_pos()));
+            }
+            None if c != b'|' && c != b',' => {
+                return Err(StreamError::InvalidCharMultiple(s.curr_char()?, vec![b'|', b',', b')'],
+                                                            s.gen_text_pos()));
+            }
+            _ => separator = Some(c),
+        }
+
+        s.advance(1);
+        s.skip_spaces();
+        items.push(parse_cp(s, names)?);
+    }
+
+    if separator == Some(b'|') {
+        Ok(Particle::Choice(items))
+    } else {
+        Ok(Particle::Seq(items))
+    }
+}
+
+fn parse_cp<'a>(s: &mut Stream<'a>, names: &mut Vec<&'a str>) -> Result<Particle, StreamError> {
+    let particle = if s.curr_byte()? == b'(' {
+        s.advance(1);
+        parse_group(s, names)?
+    } else {
+        names.push(s.consume_name()?.to_str());
+        Particle::Symbol(names.len() - 1)
+    };
+
+    Ok(parse_occurrence(s, particle))
+}
+
+fn parse_occurrence(s: &mut Stream, particle: Particle) -> Particle {
+    let particle = match s.get_curr_byte() {
+        Some(b'?') => Particle::Optional(Box::new(particle)),
+        Some(b'*') => Particle::ZeroOrMore(Box::new(particle)),
+        Some(b'+') => Particle::OneOrMore(Box::new(particle)),
+        _ => return particle,
+    };
+
+    s.advance(1);
+    particle
+}
+
+// AttlistDecl ::= '<!ATTLIST' S Name AttDef* S? '>'
+// AttDef      ::= S Name S AttType S DefaultDecl
+fn parse_attlist_decl<'a>(s: &mut Stream<'a>)
+    -> Result<(StrSpan<'a>, Vec<AttributeDecl<'a>>), StreamError>
+{
+    s.consume_spaces()?;
+    let name = s.consume_name()?;
+
+    let mut list = Vec::new();
+    loop {
+        s.skip_spaces();
+        if s.curr_byte()? == b'>' {
+            s.advance(1);
+            break;
+        }
+
+        let (prefix, local) = s.consume_qname()?;
+        s.consume_spaces()?;
+        let kind = parse_attribute_type(s)?;
+        s.consume_spaces()?;
+        let default = parse_default_decl(s)?;
+
+        let text = s.span().full_str();
+        list.push(AttributeDecl { prefix, local, name: qname_str(text, prefix, local), kind, default });
+    }
+
+    Ok((name, list))
+}
+
+// AttType ::= StringType | TokenizedType | EnumeratedType
+fn parse_attribute_type<'a>(s: &mut Stream<'a>) -> Result<AttributeType<'a>, StreamError> {
+    // Longer names first.
+    let types: &[(&str, AttributeType)] = &[
+        ("CDATA", AttributeType::Cdata),
+        ("IDREFS", AttributeType::IdRefs),
+        ("IDREF", AttributeType::IdRef),
+        ("ID", AttributeType::Id),
+        ("ENTITIES", AttributeType::Entities),
+        ("ENTITY", AttributeType::Entity),
+        ("NMTOKENS", AttributeType::NmTokens),
+        ("NMTOKEN", AttributeType::NmToken),
+    ];
+
+    for &(name, ref kind) in types {
+        if s.starts_with(name.as_bytes()) {
+            s.advance(name.len());
+            return Ok(kind.clone());
+        }
+    }
+
+    if s.starts_with(b"NOTATION") {
+        s.advance(8);
+        s.consume_spaces()?;
+        s.consume_byte(b'(')?;
+        return Ok(AttributeType::Notation(parse_enumeration(s, true)?));
+    }
+
+    if s.is_curr_byte_eq(b'(') {
+        s.advance(1);
+        return Ok(AttributeType::Enumeration(parse_enumeration(s, false)?));
+    }
+
+    let mut expected: Vec<String> = types.iter().map(|&(name, _)| name.to_string()).collect();
+    expected.push("NOTATION".to_string());
+    expected.push("(".to_string());
+    Err(invalid_keyword(s, expected))
+}
+
+// Enumeration ::= '(' S? Nmtoken (S? '|' S? Nmtoken)* S? ')'
+// The opening bracket is already consumed.
+fn parse_enumeration<'a>(s: &mut Stream<'a>, names_only: bool) -> Result<Vec<&'a str>, StreamError> {
+    let mut list = Vec::new();
+    loop {
+        s.skip_spaces();
+        let value = if names_only {
+            s.consume_name()?
+        } else {
+            let value = s.consume_chars(|_, c| c.is_xml_name());
+            if value.is_empty() {
+                return Err(StreamError::InvalidName);
+            }
+            value
+        };
+        list.push(value.to_str());
+
+        s.skip_spaces();
+        if s.consume_either(b"|)")? == b')' {
+            break;
+        }
+    }
+
+    Ok(list)
+}
+
+// DefaultDecl ::= '#REQUIRED' | '#IMPLIED' | (('#FIXED' S)? AttValue)
+fn parse_default_decl<'a>(s: &mut Stream<'a>) -> Result<DefaultDecl<'a>, StreamError> {
+    if s.starts_with(b"#REQUIRED") {
+        s.advance(9);
+        return Ok(DefaultDecl::Required);
+    }
+
+    if s.starts_with(b"#IMPLIED") {
+        s.advance(8);
+        return Ok(DefaultDecl::Implied);
+    }
+
+    let is_fixed = s.starts_with(b"#FIXED");
+    if is_fixed {
+        s.advance(6);
+        s.consume_spaces()?;
+    } else if s.is_curr_byte_eq(b'#') {
+        let expected = vec!["#REQUIRED".to_string(), "#IMPLIED".to_string(), "#FIXED".to_string()];
+        return Err(invalid_keyword(s, expected));
+    }
+
+    let quote = s.consume_quote()?;
+    let start = s.pos();
+    s.skip_bytes(|_, c| c != quote);
+    let value = s.slice_back(start);
+    s.consume_byte(quote)?;
+
+    if is_fixed {
+        Ok(DefaultDecl::Fixed(value))
+    } else {
+        Ok(DefaultDecl::Value(value))
+    }
+}
+
+fn invalid_keyword(s: &Stream, expected: Vec<String>) -> StreamError {
+    let tail = s.slice_tail().to_str();
+    let len = tail.bytes().skip(1).position(|c| !c.is_ascii_alphabetic()).map_or(tail.len(), |i| i + 1);
+    let actual = tail.get(..len).unwrap_or(tail);
+    StreamError::InvalidString(actual.to_string(), expected, s.gen_text_pos())
+}
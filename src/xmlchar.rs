@@ -2,10 +2,14 @@
 pub trait XmlCharExt {
     /// Checks if the value is within the
     /// [NameStartChar](https://www.w3.org/TR/xml/#NT-NameStartChar) range.
+    ///
+    /// The same for XML 1.0 Fifth Edition and XML 1.1.
     fn is_xml_name_start(&self) -> bool;
 
     /// Checks if the value is within the
     /// [NameChar](https://www.w3.org/TR/xml/#NT-NameChar) range.
+    ///
+    /// The same for XML 1.0 Fifth Edition and XML 1.1.
     fn is_xml_name(&self) -> bool;
 
     /// Checks if the value is within the
@@ -69,6 +73,22 @@ impl XmlCharExt for char {
     }
 }
 
+// Checks if the char is within the XML 1.1
+// [Char](https://www.w3.org/TR/xml11/#NT-Char) range.
+#[inline]
+pub(crate) fn is_xml11_char(c: char) -> bool {
+    matches!(c as u32, 0x000001..=0x00D7FF | 0x00E000..=0x00FFFD | 0x010000..=0x10FFFF)
+}
+
+// Checks if the char is within the XML 1.1
+// [RestrictedChar](https://www.w3.org/TR/xml11/#NT-RestrictedChar) range.
+//
+// Such chars are allowed only as character references.
+#[inline]
+pub(crate) fn is_xml11_restricted_char(c: char) -> bool {
+    matches!(c as u32, 0x01..=0x08 | 0x0B..=0x0C | 0x0E..=0x1F | 0x7F..=0x84 | 0x86..=0x9F)
+}
+
 
 /// Extension methods for XML-subset only operations.
 pub trait XmlByteExt {
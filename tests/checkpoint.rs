@@ -0,0 +1,174 @@
+extern crate xmlparser as xml;
+
+use xml::{Checkpoint, CheckpointError, StrSpan, Token, Tokenizer};
+
+fn tokens<'a>(p: Tokenizer<'a>) -> Vec<Token<'a>> {
+    p.map(|t| t.unwrap()).collect()
+}
+
+#[test]
+fn restore_01() {
+    // A checkpoint after any token.
+    let text = "\
+<?xml version='1.0'?>
+<!DOCTYPE a [
+    <!ENTITY e 'x'>
+    <!-- c -->
+]>
+<a x='1' y=\"2\">text<b/><![CDATA[d]]><?pi?>&e;</a>
+<!-- end -->
+";
+
+    let all = tokens(Tokenizer::from(text));
+    for i in 0..all.len() + 1 {
+        let mut p = Tokenizer::from(text);
+        for _ in 0..i {
+            p.next();
+        }
+
+        let checkpoint = p.checkpoint();
+        assert_eq!(tokens(Tokenizer::from_checkpoint(text, checkpoint).unwrap()), &all[i..]);
+
+        let mut p = Tokenizer::from(text);
+        p.restore(checkpoint).unwrap();
+        assert_eq!(tokens(p), &all[i..]);
+    }
+}
+
+#[test]
+fn restore_02() {
+    // The tokenizer can go back and forth.
+    let text = "<a><b/><c/></a>";
+    let mut p = Tokenizer::from(text);
+    p.next();
+    let first = p.checkpoint();
+    p.next();
+    p.next();
+    let second = p.checkpoint();
+
+    p.restore(first).unwrap();
+    assert_eq!(p.checkpoint(), first);
+    p.restore(second).unwrap();
+    match p.next().unwrap().unwrap() {
+        Token::ElementEnd(_) => {}
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn restore_03() {
+    // The XML 1.1 mode is stored.
+    let text = "<?xml version='1.1'?><a>\u{2028}</a>";
+    let mut p = Tokenizer::from(text);
+    p.next();
+    let checkpoint = p.checkpoint();
+    match Tokenizer::from_checkpoint(text, checkpoint).unwrap().nth(2) {
+        Some(Ok(Token::Whitespaces(_))) => {}
+        _ => panic!(),
+    }
+
+    // And reset.
+    let mut p = Tokenizer::from(text);
+    let start = p.checkpoint();
+    p.next();
+    assert!(p.is_xml11_mode());
+    p.restore(start).unwrap();
+    assert!(!p.is_xml11_mode());
+}
+
+#[test]
+fn restore_04() {
+    // The fragment mode is stored.
+    let text = "<a/><b/>";
+    let mut p = Tokenizer::from(text);
+    p.enable_fragment_mode();
+    p.next();
+    p.next();
+    let checkpoint = p.checkpoint();
+    assert_eq!(tokens(Tokenizer::from_checkpoint(text, checkpoint).unwrap()).len(), 2);
+}
+
+#[test]
+fn new_01() {
+    let mut text = String::from("<records>\n");
+    let mut index = Vec::new();
+    for i in 0..10 {
+        text.push_str("  ");
+        index.push(text.len());
+        text.push_str(&format!("<record id='{}'><v/></record>\n", i));
+    }
+    text.push_str("</records>");
+
+    let checkpoint = Checkpoint::new(index[7], 1);
+    assert_eq!(checkpoint.pos(), index[7]);
+    assert_eq!(checkpoint.depth(), 1);
+
+    let mut p = Tokenizer::from_checkpoint(&text, checkpoint).unwrap();
+    match p.nth(1) {
+        Some(Ok(Token::Attribute(_, value))) => assert_eq!(value.to_str(), "7"),
+        _ => panic!(),
+    }
+
+    // The document must end after the root element.
+    assert_eq!(p.filter(|t| t.is_err()).count(), 0);
+}
+
+#[test]
+fn errors_01() {
+    let text = "<a>text</a>";
+    assert_eq!(Tokenizer::from_checkpoint(text, Checkpoint::new(12, 1)).err(),
+               Some(CheckpointError::OutOfRange(12)));
+    assert_eq!(Tokenizer::from_checkpoint(text, Checkpoint::new(5, 1)).err(),
+               Some(CheckpointError::NotTokenBoundary(5)));
+    assert_eq!(Tokenizer::from_checkpoint(text, Checkpoint::new(9, 1)).err(),
+               Some(CheckpointError::NotTokenBoundary(9)));
+    assert!(Tokenizer::from_checkpoint(text, Checkpoint::new(11, 1)).is_ok());
+}
+
+#[test]
+fn errors_02() {
+    // A start tag checkpoint used with a different text.
+    let mut p = Tokenizer::from("<a x='1'/>");
+    p.next();
+    let checkpoint = p.checkpoint();
+    assert!(Tokenizer::from_checkpoint("<b y='2'/>", checkpoint).is_ok());
+    assert_eq!(Tokenizer::from_checkpoint("<ab y='2'/>", checkpoint).err(),
+               Some(CheckpointError::NotTokenBoundary(2)));
+}
+
+#[test]
+fn errors_03() {
+    // Not a char boundary.
+    let text = "<a>\u{e9}</a>";
+    assert_eq!(Tokenizer::from_checkpoint(text, Checkpoint::new(4, 1)).err(),
+               Some(CheckpointError::NotTokenBoundary(4)));
+}
+
+#[test]
+fn errors_04() {
+    // A tokenizer is left unchanged on error.
+    let text = "<a><b/></a>";
+    let mut p = Tokenizer::from(text);
+    p.next();
+    let checkpoint = p.checkpoint();
+    assert!(p.restore(Checkpoint::new(1, 0)).is_err());
+    assert_eq!(p.checkpoint(), checkpoint);
+}
+
+#[test]
+fn errors_05() {
+    // Positions are absolute.
+    let text = "<a><b/></a>";
+    let mut p = Tokenizer::from(StrSpan::from_substr(text, 3, 7));
+    p.enable_fragment_mode();
+    assert_eq!(p.restore(Checkpoint::new(0, 0)), Err(CheckpointError::OutOfRange(0)));
+    assert!(p.restore(Checkpoint::new(7, 0)).is_ok());
+    assert!(p.next().is_none());
+}
+
+#[test]
+fn error_display_01() {
+    assert_eq!(CheckpointError::OutOfRange(5).to_string(), "checkpoint position 5 is out of range");
+    assert_eq!(CheckpointError::NotTokenBoundary(5).to_string(),
+               "checkpoint position 5 is not at a token boundary");
+}
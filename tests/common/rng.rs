@@ -0,0 +1,21 @@
+// A xorshift PRNG. Good enough to produce varied, but reproducible data.
+//
+// Shared by the tests and the benchmark inputs generators.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new() -> Self {
+        Rng(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn range(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
@@ -0,0 +1,324 @@
+// Conformance tests runner.
+//
+// Reads the catalog from `tests/xmlconf/xmlconf.xml`, which includes the vendored
+// W3C XML Conformance Test Suite, runs each test case through
+// the `Tokenizer` in the strict mode and prints a pass/fail matrix.
+// Run with `cargo test --test conformance -- --nocapture` to see it.
+//
+// The tokenizer is not a validating parser, so both `valid` and `invalid` cases
+// must be accepted and only `not-wf` cases must be rejected.
+// `error` cases, namespaces tests, non-UTF-8 files and not-wf cases that rely
+// on external entities are skipped.
+//
+// Known failures are listed in `tests/xmlconf/known-failures.txt`.
+// The test fails on any new failure and on any known failure that passes now,
+// so the list can only shrink. Set `XMLCONF_BLESS=1` to regenerate it.
+// New entries are annotated with the spec sections of the test case.
+
+extern crate xmlparser as xml;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Kind {
+    Valid,
+    Invalid,
+    NotWf,
+    Error,
+}
+
+impl Kind {
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "valid" => Some(Kind::Valid),
+            "invalid" => Some(Kind::Invalid),
+            "not-wf" => Some(Kind::NotWf),
+            "error" => Some(Kind::Error),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Valid => "valid",
+            Kind::Invalid => "invalid",
+            Kind::NotWf => "not-wf",
+            Kind::Error => "error",
+        }
+    }
+}
+
+struct TestCase {
+    id: String,
+    kind: Kind,
+    path: PathBuf,
+    entities: String,
+    recommendation: String,
+    edition: Option<String>,
+    sections: String,
+}
+
+enum Outcome {
+    Pass,
+    Fail(String),
+    Skip,
+}
+
+#[derive(Default)]
+struct Row {
+    pass: usize,
+    fail: usize,
+    known: usize,
+    fixed: usize,
+    skip: usize,
+}
+
+// We need only the tags matching check, so all events are ignored.
+struct Checker;
+
+impl<'a> xml::Handler<'a> for Checker {}
+
+#[test]
+fn conformance() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/xmlconf");
+    let known_path = dir.join("known-failures.txt");
+
+    let mut cases = Vec::new();
+    let mut missing = Vec::new();
+    read_catalog(&dir.join("xmlconf.xml"), false, &mut HashMap::new(), &mut cases, &mut missing);
+    assert!(!cases.is_empty());
+
+    let known = read_known_failures(&known_path);
+
+    let mut matrix: BTreeMap<Kind, Row> = BTreeMap::new();
+    let mut failures = BTreeSet::new();
+    let mut errors = String::new();
+    for case in &cases {
+        let row = matrix.entry(case.kind).or_default();
+        match run(case) {
+            Outcome::Pass => {
+                if known.contains_key(&case.id) {
+                    row.fixed += 1;
+                    writeln!(errors, "{}: passes now, remove it from the known failures",
+                             case.id).unwrap();
+                } else {
+                    row.pass += 1;
+                }
+            }
+            Outcome::Fail(msg) => {
+                failures.insert(case.id.clone());
+                if known.contains_key(&case.id) {
+                    row.known += 1;
+                } else {
+                    row.fail += 1;
+                    writeln!(errors, "{} ({}): {}", case.id, case.path.display(), msg).unwrap();
+                }
+            }
+            Outcome::Skip => row.skip += 1,
+        }
+    }
+
+    for id in known.keys() {
+        if !cases.iter().any(|c| c.id == *id) {
+            writeln!(errors, "{}: no such test case, remove it from the known failures",
+                     id).unwrap();
+        }
+    }
+
+    for path in &missing {
+        writeln!(errors, "{}: a missing catalog", path.strip_prefix(&dir).unwrap_or(path).display())
+            .unwrap();
+    }
+
+    println!("{:<8} {:>6} {:>6} {:>6} {:>6} {:>6}", "TYPE", "pass", "fail", "known", "fixed", "skip");
+    for (kind, row) in &matrix {
+        println!("{:<8} {:>6} {:>6} {:>6} {:>6} {:>6}",
+                 kind.as_str(), row.pass, row.fail, row.known, row.fixed, row.skip);
+    }
+
+    if env::var("XMLCONF_BLESS").is_ok() {
+        write_known_failures(&known_path, &cases, &failures, &known);
+        return;
+    }
+
+    if !errors.is_empty() {
+        panic!("conformance test suite failed:\n{}", errors);
+    }
+}
+
+fn run(case: &TestCase) -> Outcome {
+    // Namespaces are out of the tokenizer scope.
+    if case.kind == Kind::Error || case.recommendation.starts_with("NS") {
+        return Outcome::Skip;
+    }
+
+    // Only the 5th edition rules are implemented.
+    if let Some(ref edition) = case.edition {
+        if !edition.split_whitespace().any(|e| e == "5") {
+            return Outcome::Skip;
+        }
+    }
+
+    // External entities are never loaded, so such documents cannot be rejected.
+    if case.kind == Kind::NotWf && case.entities != "none" {
+        return Outcome::Skip;
+    }
+
+    let text = match read_text(&case.path) {
+        Some(text) => text,
+        None => return Outcome::Skip,
+    };
+
+    let mut tokenizer = xml::Tokenizer::from(text.as_str());
+    tokenizer.enable_strict_mode();
+    match (tokenizer.parse_with(&mut Checker), case.kind) {
+        (Ok(_), Kind::NotWf) => Outcome::Fail("a not well-formed document was accepted".into()),
+        (Err(e), Kind::Valid) | (Err(e), Kind::Invalid) => Outcome::Fail(e.to_string()),
+        _ => Outcome::Pass,
+    }
+}
+
+// Collects test cases from the catalog and all the catalogs included via external entities.
+fn read_catalog(
+    path: &Path,
+    is_entity: bool,
+    entities: &mut HashMap<String, PathBuf>,
+    cases: &mut Vec<TestCase>,
+    missing: &mut Vec<PathBuf>,
+) {
+    let text = read_text(path).unwrap_or_else(|| panic!("failed to read {}", path.display()));
+    let base = path.parent().unwrap();
+
+    let mut text = text.as_str();
+    if is_entity {
+        // Skip the text declaration.
+        if text.starts_with("<?xml") {
+            text = &text[text.find("?>").unwrap() + 2..];
+        }
+    }
+
+    let mut tokenizer = xml::Tokenizer::from(text);
+    if is_entity {
+        tokenizer.enable_fragment_mode();
+    }
+
+    let mut attributes: HashMap<&str, &str> = HashMap::new();
+    let mut in_test = false;
+    for token in tokenizer {
+        let token = token.unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        match token {
+            xml::Token::EntityDeclaration(name, xml::EntityDefinition::ExternalId(
+                xml::ExternalId::System(uri))) => {
+                entities.insert(name.to_str().to_string(), base.join(uri.to_str()));
+            }
+            xml::Token::ElementStart(_, local) => {
+                in_test = local.to_str() == "TEST";
+                attributes.clear();
+            }
+            xml::Token::Attribute((_, local), value) if in_test => {
+                attributes.insert(local.to_str(), value.to_str());
+            }
+            xml::Token::ElementEnd(xml::ElementEnd::Open)
+            | xml::Token::ElementEnd(xml::ElementEnd::Empty) if in_test => {
+                in_test = false;
+                cases.push(make_case(&attributes, base, path));
+            }
+            xml::Token::Text(text) => {
+                for name in entity_references(text.to_str()) {
+                    match entities.get(name).cloned() {
+                        Some(ref path) if path.exists() => {
+                            read_catalog(path, true, entities, cases, missing);
+                        }
+                        Some(path) => missing.push(path),
+                        None => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn make_case(attributes: &HashMap<&str, &str>, base: &Path, catalog: &Path) -> TestCase {
+    let get = |name: &str| {
+        attributes.get(name).map(|v| v.to_string())
+            .unwrap_or_else(|| panic!("{}: a test case without {}", catalog.display(), name))
+    };
+
+    let kind = get("TYPE");
+    TestCase {
+        id: get("ID"),
+        kind: Kind::from_str(&kind).unwrap_or_else(|| panic!("unknown test type: {}", kind)),
+        path: base.join(get("URI")),
+        entities: attributes.get("ENTITIES").unwrap_or(&"none").to_string(),
+        recommendation: attributes.get("RECOMMENDATION").unwrap_or(&"XML1.0").to_string(),
+        edition: attributes.get("EDITION").map(|v| v.to_string()),
+        sections: attributes.get("SECTIONS").unwrap_or(&"").trim().to_string(),
+    }
+}
+
+// Returns names of all `&name;` references in the text.
+fn entity_references(text: &str) -> Vec<&str> {
+    text.split('&').skip(1).filter_map(|s| s.find(';').map(|end| &s[..end])).collect()
+}
+
+// Reads a UTF-8 file without BOM.
+fn read_text(path: &Path) -> Option<String> {
+    let mut data = fs::read(path).ok()?;
+    if data.starts_with(b"\xEF\xBB\xBF") {
+        data.drain(..3);
+    }
+
+    String::from_utf8(data).ok()
+}
+
+// Returns known failures IDs with their comments.
+fn read_known_failures(path: &Path) -> BTreeMap<String, String> {
+    let text = read_text(path).unwrap_or_default();
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, '#');
+            let id = parts.next().unwrap().trim();
+            let comment = parts.next().unwrap_or("").trim();
+            if id.is_empty() {
+                None
+            } else {
+                Some((id.to_string(), comment.to_string()))
+            }
+        })
+        .collect()
+}
+
+// Writes the current failures, preserving comments of the known ones.
+//
+// New failures are commented with the spec sections of the test case.
+fn write_known_failures(path: &Path, cases: &[TestCase], failures: &BTreeSet<String>,
+                        known: &BTreeMap<String, String>) {
+    let mut text = String::from(KNOWN_FAILURES_HEADER);
+    for id in failures {
+        let comment = match known.get(id) {
+            Some(comment) if !comment.is_empty() => comment.as_str(),
+            _ => cases.iter().find(|c| c.id == *id).map_or("", |c| c.sections.as_str()),
+        };
+
+        if comment.is_empty() {
+            writeln!(text, "{}", id)
+        } else {
+            writeln!(text, "{} # {}", id, comment)
+        }.unwrap();
+    }
+
+    fs::write(path, text).unwrap();
+}
+
+const KNOWN_FAILURES_HEADER: &str = "\
+# Test cases that are known to fail. One ID per line.
+#
+# This list must only shrink. Regenerate it with
+# `XMLCONF_BLESS=1 cargo test --test conformance`.
+";
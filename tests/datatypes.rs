@@ -0,0 +1,215 @@
+extern crate xmlparser as xml;
+
+use std::fmt::Debug;
+
+use xml::{Date, DateTime, Duration, Stream, StreamError, StrSpan, Token, Tokenizer, ValueList};
+
+fn parse<'a, T, F>(text: &'a str, f: F) -> Result<T, StreamError>
+    where F: FnOnce(&mut Stream<'a>) -> Result<T, StreamError>
+{
+    Stream::parse_value(StrSpan::from(text), f)
+}
+
+fn error<T: Debug>(result: Result<T, StreamError>) -> String {
+    result.unwrap_err().to_string()
+}
+
+#[test]
+fn boolean_01() {
+    assert!(parse("true", Stream::consume_boolean).unwrap());
+    assert!(!parse(" 0 ", Stream::consume_boolean).unwrap());
+    assert_eq!(error(parse("yes", Stream::consume_boolean)), "invalid xs:boolean value at 1:1");
+    assert_eq!(error(parse("10", Stream::consume_boolean)), "character '0' is not allowed at 1:2");
+    assert_eq!(error(parse("TRUE", Stream::consume_boolean)), "invalid xs:boolean value at 1:1");
+}
+
+#[test]
+fn integer_01() {
+    assert_eq!(parse("0", Stream::consume_integer).unwrap(), 0);
+    assert_eq!(parse("+15", Stream::consume_integer).unwrap(), 15);
+    assert_eq!(parse("-9223372036854775808", Stream::consume_integer).unwrap(), i64::MIN);
+    assert_eq!(parse("9223372036854775807", Stream::consume_integer).unwrap(), i64::MAX);
+    assert_eq!(error(parse("99999999999999999999", Stream::consume_integer)),
+               "invalid xs:integer value at 1:1");
+    assert_eq!(error(parse("-", Stream::consume_integer)), "invalid xs:integer value at 1:2");
+    assert_eq!(error(parse("1.0", Stream::consume_integer)), "character '.' is not allowed at 1:2");
+}
+
+#[test]
+fn decimal_01() {
+    assert_eq!(parse("-1.23", Stream::consume_decimal).unwrap(), -1.23);
+    assert_eq!(parse("12678967.543233", Stream::consume_decimal).unwrap(), 12678967.543233);
+    assert_eq!(parse("+100000.00", Stream::consume_decimal).unwrap(), 100000.0);
+    assert_eq!(parse("210.", Stream::consume_decimal).unwrap(), 210.0);
+    assert_eq!(parse(".5", Stream::consume_decimal).unwrap(), 0.5);
+    assert_eq!(error(parse(".", Stream::consume_decimal)), "invalid xs:decimal value at 1:2");
+    assert_eq!(error(parse("1e2", Stream::consume_decimal)), "character 'e' is not allowed at 1:2");
+    assert_eq!(error(parse("INF", Stream::consume_decimal)), "invalid xs:decimal value at 1:1");
+}
+
+#[test]
+fn double_01() {
+    assert_eq!(parse("-1E4", Stream::consume_double).unwrap(), -1E4);
+    assert_eq!(parse("1267.43233E12", Stream::consume_double).unwrap(), 1267.43233E12);
+    assert_eq!(parse("12.78e-2", Stream::consume_double).unwrap(), 12.78e-2);
+    assert_eq!(parse("12", Stream::consume_double).unwrap(), 12.0);
+    assert_eq!(parse("-0", Stream::consume_double).unwrap(), 0.0);
+    assert_eq!(parse("INF", Stream::consume_double).unwrap(), f64::INFINITY);
+    assert_eq!(parse("+INF", Stream::consume_double).unwrap(), f64::INFINITY);
+    assert!(parse("NaN", Stream::consume_double).unwrap().is_nan());
+}
+
+#[test]
+fn double_02() {
+    assert_eq!(error(parse("-NaN", Stream::consume_double)), "invalid xs:double value at 1:2");
+    assert_eq!(error(parse("1e", Stream::consume_double)), "invalid xs:double value at 1:3");
+    assert_eq!(error(parse("inf", Stream::consume_double)), "invalid xs:double value at 1:1");
+    assert_eq!(error(parse("infinity", Stream::consume_double)), "invalid xs:double value at 1:1");
+    assert_eq!(error(parse("1,5", Stream::consume_double)), "character ',' is not allowed at 1:2");
+}
+
+#[test]
+fn date_01() {
+    assert_eq!(parse("2001-10-26", Stream::consume_date).unwrap(),
+               Date { year: 2001, month: 10, day: 26, timezone: None });
+    assert_eq!(parse("2000-02-29Z", Stream::consume_date).unwrap(),
+               Date { year: 2000, month: 2, day: 29, timezone: Some(0) });
+    assert_eq!(parse("-0044-03-15-05:30", Stream::consume_date).unwrap(),
+               Date { year: -44, month: 3, day: 15, timezone: Some(-330) });
+    assert_eq!(parse("12345-01-01+14:00", Stream::consume_date).unwrap(),
+               Date { year: 12345, month: 1, day: 1, timezone: Some(840) });
+}
+
+#[test]
+fn date_02() {
+    let date = |text| error(parse(text, Stream::consume_date));
+    assert_eq!(date("2001-02-29"), "invalid xs:date value at 1:9");
+    assert_eq!(date("1900-02-29"), "invalid xs:date value at 1:9");
+    assert_eq!(date("2001-04-31"), "invalid xs:date value at 1:9");
+    assert_eq!(date("2001-13-01"), "invalid xs:date value at 1:6");
+    assert_eq!(date("2001-00-01"), "invalid xs:date value at 1:6");
+    assert_eq!(date("01-10-26"), "invalid xs:date value at 1:1");
+    assert_eq!(date("02001-10-26"), "invalid xs:date value at 1:1");
+    assert_eq!(date("2001-1-26"), "invalid xs:date value at 1:7");
+    assert_eq!(date("2001-10-26+15:00"), "invalid xs:date value at 1:11");
+    assert_eq!(date("2001-10-26+14:30"), "invalid xs:date value at 1:11");
+    assert_eq!(date("2001-10-26T00:00:00"), "character 'T' is not allowed at 1:11");
+}
+
+#[test]
+fn date_time_01() {
+    let date = Date { year: 2001, month: 10, day: 26, timezone: None };
+    assert_eq!(parse("2001-10-26T21:32:52", Stream::consume_date_time).unwrap(),
+               DateTime { date, hour: 21, minute: 32, second: 52, nanosecond: 0 });
+    assert_eq!(parse("2001-10-26T21:32:52.12679Z", Stream::consume_date_time).unwrap(),
+               DateTime { date: Date { timezone: Some(0), ..date },
+                          hour: 21, minute: 32, second: 52, nanosecond: 126_790_000 });
+    assert_eq!(parse("2001-10-26T00:00:00.1234567891", Stream::consume_date_time).unwrap(),
+               DateTime { date, hour: 0, minute: 0, second: 0, nanosecond: 123_456_789 });
+    assert_eq!(parse("2001-10-26T24:00:00-01:00", Stream::consume_date_time).unwrap(),
+               DateTime { date: Date { timezone: Some(-60), ..date },
+                          hour: 24, minute: 0, second: 0, nanosecond: 0 });
+}
+
+#[test]
+fn date_time_02() {
+    let date_time = |text| error(parse(text, Stream::consume_date_time));
+    assert_eq!(date_time("2001-10-26"), "invalid xs:dateTime value at 1:11");
+    assert_eq!(date_time("2001-10-26T21:32"), "invalid xs:dateTime value at 1:17");
+    assert_eq!(date_time("2001-10-26T25:00:00"), "invalid xs:dateTime value at 1:12");
+    assert_eq!(date_time("2001-10-26T24:00:01"), "invalid xs:dateTime value at 1:12");
+    assert_eq!(date_time("2001-10-26T21:60:00"), "invalid xs:dateTime value at 1:15");
+    assert_eq!(date_time("2001-10-26T21:32:60"), "invalid xs:dateTime value at 1:18");
+    assert_eq!(date_time("2001-10-26T21:32:52."), "invalid xs:dateTime value at 1:21");
+    assert_eq!(date_time("2001-10-26T21:32:52+02"), "invalid xs:dateTime value at 1:23");
+}
+
+#[test]
+fn duration_01() {
+    let duration = |text| parse(text, Stream::consume_duration).unwrap();
+    assert_eq!(duration("P1Y2M3DT10H30M"),
+               Duration { years: 1, months: 2, days: 3, hours: 10, minutes: 30, ..Duration::default() });
+    assert_eq!(duration("-P120D"), Duration { negative: true, days: 120, ..Duration::default() });
+    assert_eq!(duration("PT90M"), Duration { minutes: 90, ..Duration::default() });
+    assert_eq!(duration("P0Y"), Duration::default());
+    assert_eq!(duration("PT1.5S"), Duration { seconds: 1, nanoseconds: 500_000_000, ..Duration::default() });
+    assert_eq!(duration("P1MT2M3S"), Duration { months: 1, minutes: 2, seconds: 3, ..Duration::default() });
+}
+
+#[test]
+fn duration_02() {
+    let duration = |text| error(parse(text, Stream::consume_duration));
+    assert_eq!(duration("P"), "invalid xs:duration value at 1:2");
+    assert_eq!(duration("PT"), "invalid xs:duration value at 1:3");
+    assert_eq!(duration("P1D2Y"), "invalid xs:duration value at 1:5");
+    assert_eq!(duration("P1H"), "invalid xs:duration value at 1:3");
+    assert_eq!(duration("P1Y1Y"), "invalid xs:duration value at 1:5");
+    assert_eq!(duration("P1.5D"), "invalid xs:duration value at 1:2");
+    assert_eq!(duration("PT1.5M"), "invalid xs:duration value at 1:6");
+    assert_eq!(duration("PT1S2S"), "invalid xs:duration value at 1:6");
+    assert_eq!(duration("PT1S1.5S"), "invalid xs:duration value at 1:5");
+    assert_eq!(duration("+P1D"), "invalid xs:duration value at 1:1");
+    assert_eq!(duration("P4294967296D"), "invalid xs:duration value at 1:2");
+}
+
+#[test]
+fn qname_01() {
+    let (prefix, local) = parse(" svg:rect ", Stream::consume_xs_qname).unwrap();
+    assert_eq!((prefix.to_str(), local.to_str()), ("svg", "rect"));
+    let (prefix, local) = parse("rect", Stream::consume_xs_qname).unwrap();
+    assert_eq!((prefix.to_str(), local.to_str()), ("", "rect"));
+
+    assert_eq!(error(parse("1a", Stream::consume_xs_qname)), "invalid xs:QName value at 1:1");
+    assert_eq!(error(parse("a:1b", Stream::consume_xs_qname)), "invalid xs:QName value at 1:3");
+    assert_eq!(error(parse("a:b:c", Stream::consume_xs_qname)), "invalid xs:QName value at 1:1");
+    assert_eq!(error(parse(":a", Stream::consume_xs_qname)), "invalid xs:QName value at 1:1");
+    assert_eq!(error(parse("a:", Stream::consume_xs_qname)), "invalid xs:QName value at 1:1");
+}
+
+#[test]
+fn any_uri_01() {
+    let uri = |text| parse(text, Stream::consume_any_uri).map(|s| s.to_str());
+    assert_eq!(uri("http://www.w3.org/2001/XMLSchema#a").unwrap(), "http://www.w3.org/2001/XMLSchema#a");
+    assert_eq!(uri("../a%20b.xml").unwrap(), "../a%20b.xml");
+    assert_eq!(uri("\u{e9}t\u{e9}").unwrap(), "\u{e9}t\u{e9}");
+    assert_eq!(uri("").unwrap(), "");
+    assert_eq!(error(uri("a%2")), "invalid xs:anyURI value at 1:2");
+    assert_eq!(error(uri("a{b}")), "invalid xs:anyURI value at 1:2");
+    assert_eq!(error(uri("a b")), "character 'b' is not allowed at 1:3");
+}
+
+#[test]
+fn list_01() {
+    let items: Vec<_> = ValueList::from("id1 id2\n\tid3").map(|s| s.to_str()).collect();
+    assert_eq!(items, vec!["id1", "id2", "id3"]);
+    assert_eq!(ValueList::from("   ").count(), 0);
+
+    let numbers: Result<Vec<_>, _> = ValueList::from("1 2 x")
+        .map(|s| Stream::parse_value(s, Stream::consume_integer))
+        .collect();
+    assert_eq!(numbers.unwrap_err().to_string(), "invalid xs:integer value at 1:5");
+}
+
+#[test]
+fn positions_01() {
+    // Errors point to the original text.
+    let text = "<svg>\n  <rect width='10' height='-1.5e' values='1 2 3'/>\n</svg>";
+    let mut errors = Vec::new();
+    for token in Tokenizer::from(text) {
+        if let Token::Attribute((_, local), value) = token.unwrap() {
+            let result = match local.to_str() {
+                "values" => ValueList::from(value)
+                    .try_for_each(|s| Stream::parse_value(s, Stream::consume_integer).map(|_| ())),
+                _ => Stream::parse_value(value, Stream::consume_double).map(|_| ()),
+            };
+
+            if let Err(e) = result {
+                errors.push(e);
+            }
+        }
+    }
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].to_string(), "invalid xs:double value at 2:33");
+    assert_eq!(errors[0].code(), "XML-E018");
+}
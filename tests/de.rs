@@ -0,0 +1,315 @@
+#![cfg(feature = "serde")]
+
+#[macro_use] extern crate serde_derive;
+extern crate serde;
+extern crate xmlparser as xml;
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use xml::de::{self, Deserializer};
+use xml::tree::Document;
+use xml::TextPos;
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct Item {
+    id: u32,
+    #[serde(rename = "$text", default)]
+    text: String,
+}
+
+#[test]
+fn attributes_01() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Root {
+        a: i32,
+        b: f64,
+        c: bool,
+        d: char,
+        e: Option<String>,
+        f: Option<String>,
+    }
+
+    let v: Root = de::from_str("<root a='-1' b=' 2.5 ' c='1' d='x' e='&amp;'/>").unwrap();
+    assert_eq!(v, Root { a: -1, b: 2.5, c: true, d: 'x', e: Some("&".to_string()), f: None });
+}
+
+#[test]
+fn elements_01() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Root {
+        name: String,
+        size: u8,
+        flag: bool,
+        empty: String,
+    }
+
+    let text = "<root><name>a &lt; b</name><size> 5 </size><flag>false</flag><empty/></root>";
+    let v: Root = de::from_str(text).unwrap();
+    assert_eq!(v, Root { name: "a < b".to_string(), size: 5, flag: false, empty: String::new() });
+}
+
+#[test]
+fn repeated_01() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Root {
+        item: Vec<Item>,
+        other: String,
+    }
+
+    // Repeated elements don't have to be adjacent.
+    let text = "<root><item id='1'>a</item><other>x</other><item id='2'><![CDATA[b]]></item></root>";
+    let v: Root = de::from_str(text).unwrap();
+    assert_eq!(v, Root {
+        item: vec![Item { id: 1, text: "a".to_string() }, Item { id: 2, text: "b".to_string() }],
+        other: "x".to_string(),
+    });
+}
+
+#[test]
+fn repeated_02() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Root {
+        #[serde(default)]
+        item: Vec<Item>,
+    }
+
+    let v: Root = de::from_str("<root/>").unwrap();
+    assert_eq!(v, Root { item: Vec::new() });
+}
+
+#[test]
+fn repeated_03() {
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Root {
+        item: Item,
+    }
+
+    let text = "<root>\n<item id='1'/>\n<item id='2'/>\n</root>";
+    let e = de::from_str::<Root>(text).unwrap_err();
+    assert_eq!(e.to_string(), "duplicated element 'item' at 3:1");
+}
+
+#[test]
+fn text_01() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Root {
+        #[serde(rename = "$text")]
+        text: String,
+    }
+
+    let v: Root = de::from_str("<root>a<!-- comment -->b<e/>c</root>").unwrap();
+    assert_eq!(v.text, "abc");
+}
+
+#[test]
+fn text_02() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Root {
+        #[serde(rename = "@a")]
+        a: String,
+        #[serde(rename = "$text")]
+        text: String,
+    }
+
+    // Control chars references are allowed only in XML 1.1.
+    let v: Root = de::from_str("<?xml version='1.1'?><root a='&#x1;'>&#x2;</root>").unwrap();
+    assert_eq!(v, Root { a: "\u{1}".to_string(), text: "\u{2}".to_string() });
+
+    let v: Root = de::from_str("<root a='&#x1;'>&#x2;</root>").unwrap();
+    assert_eq!(v, Root { a: "&#x1;".to_string(), text: "&#x2;".to_string() });
+}
+
+#[test]
+fn list_01() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Root {
+        points: Vec<u32>,
+        size: (f32, f32),
+        // A single child element is a sequence of elements, not values.
+        item: Vec<String>,
+    }
+
+    let text = "<root points=' 1 2\t3 ' size='1\n2'><item>a b</item></root>";
+    let v: Root = de::from_str(text).unwrap();
+    assert_eq!(v, Root {
+        points: vec![1, 2, 3],
+        size: (1.0, 2.0),
+        item: vec!["a b".to_string()],
+    });
+}
+
+#[test]
+fn enum_01() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    #[serde(rename_all = "lowercase")]
+    enum Color {
+        Red,
+        Green,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    #[serde(rename_all = "lowercase")]
+    enum Shape {
+        Circle { r: f32 },
+        Square(Item),
+        Point,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Root {
+        color: Color,
+        background: Color,
+        shape: Vec<Shape>,
+    }
+
+    let text = "<root color='red'>\
+                    <background>green</background>\
+                    <shape><circle r='1.5'/></shape>\
+                    <shape><square id='1'>2</square></shape>\
+                    <shape><point/></shape>\
+                </root>";
+    let v: Root = de::from_str(text).unwrap();
+    assert_eq!(v, Root {
+        color: Color::Red,
+        background: Color::Green,
+        shape: vec![
+            Shape::Circle { r: 1.5 },
+            Shape::Square(Item { id: 1, text: "2".to_string() }),
+            Shape::Point,
+        ],
+    });
+}
+
+#[test]
+fn map_01() {
+    let v: HashMap<String, String> = de::from_str("<root a='1'><b>2</b>3</root>").unwrap();
+    assert_eq!(v.len(), 3);
+    assert_eq!(v["a"], "1");
+    assert_eq!(v["b"], "2");
+    assert_eq!(v["$text"], "3");
+}
+
+#[test]
+fn borrow_01() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Root<'a> {
+        #[serde(borrow)]
+        a: &'a str,
+        #[serde(borrow)]
+        b: Cow<'a, str>,
+        #[serde(borrow)]
+        c: Cow<'a, str>,
+        #[serde(borrow)]
+        d: &'a str,
+    }
+
+    let text = "<root a='text' b='text' c='&lt;'><d>text</d></root>";
+    let v: Root = de::from_str(text).unwrap();
+    assert_eq!(v.a, "text");
+    assert!(matches!(v.b, Cow::Borrowed("text")));
+    assert!(matches!(v.c, Cow::Owned(_)));
+    assert_eq!(v.d.as_ptr(), text[text.find("text</d>").unwrap()..].as_ptr());
+}
+
+#[test]
+fn borrow_02() {
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Root<'a> {
+        a: &'a str,
+    }
+
+    // A string with references cannot be borrowed.
+    assert!(de::from_str::<Root>("<root a='&amp;'/>").is_err());
+}
+
+#[test]
+fn namespaces_01() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Root {
+        a: u32,
+        b: u32,
+        c: u32,
+    }
+
+    let text = "<r:root xmlns:r='urn:r' xmlns='urn:d' r:a='1' b='2'><r:c>3</r:c></r:root>";
+    let v: Root = de::from_str(text).unwrap();
+    assert_eq!(v, Root { a: 1, b: 2, c: 3 });
+}
+
+#[test]
+fn namespaces_02() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Root {
+        #[serde(rename = "{urn:r}a")]
+        a: u32,
+        b: u32,
+        #[serde(rename = "{urn:d}c")]
+        c: u32,
+        #[serde(rename = "{urn:r}c")]
+        rc: u32,
+        #[serde(rename = "{http://www.w3.org/XML/1998/namespace}lang")]
+        lang: String,
+    }
+
+    let text = "<r:root xmlns:r='urn:r' xmlns='urn:d' r:a='1' b='2' xml:lang='en'>\
+                    <c>3</c><r:c>4</r:c>\
+                </r:root>";
+    let doc = Document::parse(text).unwrap();
+    let mut de = Deserializer::from_document(&doc).unwrap();
+    de.enable_expanded_names();
+    let v = Root::deserialize(de).unwrap();
+    assert_eq!(v, Root { a: 1, b: 2, c: 3, rc: 4, lang: "en".to_string() });
+}
+
+#[test]
+fn errors_01() {
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Root {
+        item: Vec<Item>,
+    }
+
+    let text = "<root>\n  <item id='1'/>\n  <item id='x'/>\n</root>";
+    let e = de::from_str::<Root>(text).unwrap_err();
+    assert_eq!(e.pos(), Some(TextPos::new(3, 13)));
+    assert_eq!(e.to_string(), "invalid value: string \"x\", expected u32 at 3:13");
+
+    let text = "<root>\n  <item>text</item>\n</root>";
+    let e = de::from_str::<Root>(text).unwrap_err();
+    assert_eq!(e.to_string(), "missing field `id` at 2:3");
+}
+
+#[test]
+fn errors_02() {
+    let e = de::from_str::<Item>("<root>\n<a></b></root>").unwrap_err();
+    assert_eq!(e.pos(), Some(TextPos::new(2, 4)));
+    match e {
+        de::Error::Parse(_) => {}
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn errors_03() {
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Root {
+        item: Vec<Item>,
+    }
+
+    // Positions are calculated with the document modes.
+    let text = "<root>\r  <item id='1'/>\r  <item id='x'/>\r</root>";
+    let mut p = xml::Tokenizer::from(text);
+    p.enable_cr_line_breaks_mode();
+    let doc = Document::from_tokenizer(p).unwrap();
+    let e = de::from_document::<Root>(&doc).unwrap_err();
+    assert_eq!(e.pos(), Some(TextPos::new(3, 13)));
+
+    let text = "<?xml version='1.1'?>\u{85}<root>\u{2028}<item>text</item></root>";
+    let e = de::from_str::<Root>(text).unwrap_err();
+    assert_eq!(e.to_string(), "missing field `id` at 3:1");
+}
@@ -0,0 +1,216 @@
+extern crate xmlparser as xml;
+
+use xml::{Error, StreamError, TokenType};
+
+fn first_error(text: &str) -> Error {
+    xml::Tokenizer::from(text).filter_map(|t| t.err()).next().unwrap()
+}
+
+#[test]
+fn error_details_01() {
+    let text = "<a></br/></a>";
+    let e = first_error(text);
+    assert_eq!(e.code(), "XML-E004");
+    assert_eq!(e.token_type(), Some(TokenType::ElementClose));
+    assert_eq!(e.range(), 3..8);
+    assert_eq!(&text[e.range()], "</br/");
+    assert_eq!(e.spec_url(), "https://www.w3.org/TR/xml/#NT-ETag");
+
+    match e.cause() {
+        Some(&StreamError::InvalidChar(actual, expected, pos)) => {
+            assert_eq!(actual, '/');
+            assert_eq!(expected, b'>');
+            assert_eq!(pos, xml::TextPos::new(1, 8));
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn error_details_02() {
+    let text = "<?xml version='1.0' standalone='true'?>";
+    let e = first_error(text);
+    assert_eq!(e.code(), "XML-E008");
+
+    match e.cause() {
+        Some(StreamError::InvalidString(actual, expected, _)) => {
+            assert_eq!(actual, "true");
+            assert_eq!(expected, &["yes", "no"]);
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn error_details_03() {
+    let text = "<a/><b/>";
+    let e = first_error(text);
+    assert_eq!(e.code(), "XML-E012");
+    assert_eq!(e.range(), 4..6);
+}
+
+#[test]
+fn error_details_04() {
+    let text = "<a x='1' /";
+    let e = first_error(text);
+    assert_eq!(e.code(), "XML-E001");
+    assert_eq!(e.range(), 9..10);
+    assert_eq!(e.spec_url(), "https://www.w3.org/TR/xml/#NT-Attribute");
+}
+
+#[test]
+fn error_details_05() {
+    let e = first_error("<!DOCTYPE a [<!ENTITY b c>]>");
+    match e.cause() {
+        Some(&StreamError::InvalidCharMultiple(actual, ref expected, _)) => {
+            assert_eq!(actual, 'c');
+            assert_eq!(expected.as_slice(), b"\"'SP");
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn error_details_06() {
+    let e = first_error("q");
+    assert_eq!(e.code(), "XML-E013");
+    assert_eq!(e.range(), 0..1);
+    assert_eq!(e.spec_url(), "https://www.w3.org/TR/xml/#NT-document");
+}
+
+#[test]
+fn error_details_07() {
+    let text = "<p>текст</p><!-- a -- b -->";
+    let e = first_error(text);
+    assert_eq!(e.code(), "XML-E011");
+    assert_eq!(&text[e.range()], "<!-- a -- b -");
+}
+
+#[test]
+fn error_details_08() {
+    // The actual character is stored as is.
+    let e = first_error("<a></a\u{2192}>");
+    match e.cause() {
+        Some(&StreamError::InvalidChar(actual, expected, _)) => {
+            assert_eq!(actual, '\u{2192}');
+            assert_eq!(expected, b'>');
+        }
+        _ => panic!(),
+    }
+
+    assert_eq!(e.to_string(), "invalid token 'Element Close' at 1:4 cause expected '>' not '\u{2192}' at 1:7");
+}
+
+#[test]
+fn diagnostic_01() {
+    let text = "<a>\n\t<b></c>\n</a>";
+    let e = xml::Tokenizer::from(text).parse_with(&mut NoopHandler).unwrap_err();
+
+    let mut diag = xml::Diagnostic::new(&e, text);
+    diag.set_context_lines(0);
+    assert_eq!(diag.to_string(), "\
+error[XML-E014]: expected 'b' close tag not 'c' at 2:5
+ --> 2:5
+  |
+2 | \t<b></c>
+  | \t   ^^^^ expected '</b>' here
+  |
+  = see: https://www.w3.org/TR/xml/#GIMatch
+");
+}
+
+#[test]
+fn diagnostic_02() {
+    let text = "<a x='1' /";
+    let e = first_error(text);
+    let mut diag = xml::Diagnostic::new(&e, text);
+    diag.enable_colors();
+    let s = diag.to_string();
+    assert!(s.starts_with("\x1b[1;31merror[XML-E001]\x1b[0m"));
+    assert!(s.contains("\x1b[1;31m^ the document ended unexpectedly\x1b[0m"));
+}
+
+#[test]
+fn diagnostic_03() {
+    let text = "<a/>\n\n<b/>";
+    let e = first_error(text);
+    assert_eq!(xml::Diagnostic::new(&e, text).to_string(), "\
+error[XML-E012]: unexpected token 'Element Start' at 3:1
+ --> 3:1
+  |
+1 | <a/>
+2 |
+3 | <b/>
+  | ^^ a document must have exactly one root element
+  |
+  = see: https://www.w3.org/TR/xml/#NT-STag
+");
+}
+
+#[test]
+fn diagnostic_04() {
+    // Ranges that don't match the text must not cause a panic.
+    let text = "<a>\r\n</a>";
+    let e = Error::UnknownToken(xml::TextPos::new(1, 4), 4..5);
+    let mut diag = xml::Diagnostic::new(&e, text);
+    diag.set_context_lines(0);
+    assert_eq!(diag.to_string(), "\
+error[XML-E013]: unknown token at 1:4
+ --> 1:4
+  |
+1 | <a>
+  |    ^ this is not a valid markup; text is allowed only inside elements
+  |
+  = see: https://www.w3.org/TR/xml/#NT-document
+");
+
+    let text = "<\u{e9}>";
+    let e = Error::UnknownToken(xml::TextPos::new(1, 2), 2..3);
+    assert!(xml::Diagnostic::new(&e, text).to_string().contains("1 | <\u{e9}>\n  |  ^ "));
+}
+
+#[test]
+fn diagnostic_05() {
+    let text = "<a>\r\t<b></c>\r</a>";
+    let mut p = xml::Tokenizer::from(text);
+    p.enable_cr_line_breaks_mode();
+    let e = p.parse_with(&mut NoopHandler).unwrap_err();
+
+    let mut diag = xml::Diagnostic::new(&e, text);
+    diag.enable_cr_line_breaks_mode();
+    assert_eq!(diag.to_string(), "\
+error[XML-E014]: expected 'b' close tag not 'c' at 2:5
+ --> 2:5
+  |
+1 | <a>
+2 | \t<b></c>
+  | \t   ^^^^ expected '</b>' here
+3 | </a>
+  |
+  = see: https://www.w3.org/TR/xml/#GIMatch
+");
+}
+
+#[test]
+fn diagnostic_06() {
+    let text = "<a>\u{85}<b></c>\u{2028}</a>";
+    let mut p = xml::Tokenizer::from(text);
+    p.enable_xml11_mode();
+    let e = p.parse_with(&mut NoopHandler).unwrap_err();
+
+    let mut diag = xml::Diagnostic::new(&e, text);
+    diag.enable_xml11_mode();
+    diag.set_context_lines(0);
+    assert_eq!(diag.to_string(), "\
+error[XML-E014]: expected 'b' close tag not 'c' at 2:4
+ --> 2:4
+  |
+2 | <b></c>
+  |    ^^^^ expected '</b>' here
+  |
+  = see: https://www.w3.org/TR/xml/#GIMatch
+");
+}
+
+struct NoopHandler;
+impl<'a> xml::Handler<'a> for NoopHandler {}
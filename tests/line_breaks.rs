@@ -0,0 +1,76 @@
+extern crate xmlparser as xml;
+
+use xml::{ColumnUnit, LineIndex, Stream, TextPos};
+
+#[test]
+fn cr_line_breaks_01() {
+    let text = "<a>\r<b>\r\n<c x=1/></b></a>";
+    let mut p = xml::Tokenizer::from(text);
+    p.enable_cr_line_breaks_mode();
+    let e = p.filter_map(|t| t.err()).next().unwrap();
+    assert_eq!(e.to_string(),
+               "invalid token 'Attribute' at 3:3 cause expected quote mark not '1' at 3:6");
+}
+
+#[test]
+fn cr_line_breaks_02() {
+    struct Ignore;
+    impl<'a> xml::Handler<'a> for Ignore {}
+
+    let text = "<a>\r<b>\r</c></a>";
+    let mut p = xml::Tokenizer::from(text);
+    p.enable_cr_line_breaks_mode();
+    assert_eq!(p.parse_with(&mut Ignore).unwrap_err().to_string(),
+               "expected 'b' close tag not 'c' at 3:1");
+
+    // Only `\n` is a line break by default.
+    let p = xml::Tokenizer::from(text);
+    assert_eq!(p.parse_with(&mut Ignore).unwrap_err().to_string(),
+               "expected 'b' close tag not 'c' at 1:9");
+}
+
+#[test]
+fn cr_line_breaks_03() {
+    let text = "<a>\r<b/>\r\n<c/>\r</a>";
+    let mut p = xml::Tokenizer::from(text);
+    p.enable_cr_line_breaks_mode();
+    let index = LineIndex::from_tokenizer(&p);
+    assert_eq!(index.lines_count(), 4);
+
+    let mut s = Stream::from(text);
+    s.enable_cr_line_breaks_mode();
+    for offset in 0..text.len() + 1 {
+        let pos = index.text_pos(offset);
+        assert_eq!(pos, s.gen_text_pos_from(offset));
+        assert_eq!(index.offset(pos), Some(offset));
+    }
+
+    // Only `\n` is a line break by default.
+    assert_eq!(LineIndex::new(text).text_pos(4), TextPos::new(1, 5));
+}
+
+#[test]
+fn xml11_line_breaks_01() {
+    let text = "<?xml version='1.1'?><a>\u{85}<b/>\r\u{85}<c/>\u{2028}</a>";
+    let mut p = xml::Tokenizer::from(text);
+    p.enable_cr_line_breaks_mode();
+    p.next();
+    let index = LineIndex::from_tokenizer(&p);
+    assert_eq!(index.lines_count(), 4);
+
+    let mut s = Stream::from(text);
+    s.enable_xml11_mode();
+    s.enable_cr_line_breaks_mode();
+    for (offset, _) in text.char_indices().chain(Some((text.len(), '\0'))) {
+        let pos = index.text_pos(offset);
+        assert_eq!(pos, s.gen_text_pos_from(offset));
+        assert_eq!(index.offset(pos), Some(offset));
+
+        let pos = index.text_pos_in(offset, ColumnUnit::Utf16);
+        assert_eq!(index.offset_in(pos, ColumnUnit::Utf16), Some(offset));
+    }
+
+    assert_eq!(index.text_pos(33), TextPos::new(3, 1));
+    assert_eq!(index.offset(TextPos::new(1, 25)), Some(24));
+    assert_eq!(index.offset(TextPos::new(1, 26)), None);
+}
@@ -0,0 +1,51 @@
+extern crate xmlparser as xml;
+
+use xml::{ColumnUnit, LineIndex, Stream, TextPos};
+
+#[test]
+fn line_index_01() {
+    let text = "<a>\n текст 😀 text\n\n<b/>\r\n</a>";
+    let index = LineIndex::new(text);
+    let s = Stream::from(text);
+
+    let mut offset = 0;
+    for c in text.chars().chain(Some('\0')) {
+        let pos = index.text_pos(offset);
+        assert_eq!(pos, s.gen_text_pos_from(offset));
+        assert_eq!(index.offset(pos), Some(offset));
+
+        for unit in &[ColumnUnit::Byte, ColumnUnit::Utf16] {
+            let pos = index.text_pos_in(offset, *unit);
+            assert_eq!(index.offset_in(pos, *unit), Some(offset));
+        }
+
+        offset += c.len_utf8();
+    }
+}
+
+#[test]
+fn line_index_02() {
+    let text = "a😀b\nc";
+    let index = LineIndex::new(text);
+    assert_eq!(index.lines_count(), 2);
+    assert_eq!(index.line_start(2), Some(7));
+    assert_eq!(index.text_pos_in(5, ColumnUnit::Byte), TextPos::new(1, 6));
+    assert_eq!(index.text_pos_in(5, ColumnUnit::Char), TextPos::new(1, 3));
+    assert_eq!(index.text_pos_in(5, ColumnUnit::Utf16), TextPos::new(1, 4));
+    assert_eq!(index.text_pos(100), TextPos::new(2, 2));
+    assert_eq!(index.text_pos(3), TextPos::new(1, 2));
+}
+
+#[test]
+fn line_index_03() {
+    let text = "a😀b\nc";
+    let index = LineIndex::new(text);
+    // Inside a surrogate pair.
+    assert_eq!(index.offset_in(TextPos::new(1, 3), ColumnUnit::Utf16), None);
+    // After the end of the line.
+    assert_eq!(index.offset(TextPos::new(1, 4)), Some(6));
+    assert_eq!(index.offset(TextPos::new(1, 5)), None);
+    assert_eq!(index.offset(TextPos::new(3, 1)), None);
+    assert_eq!(index.offset(TextPos::new(0, 1)), None);
+    assert_eq!(index.offset(TextPos::new(1, 0)), None);
+}
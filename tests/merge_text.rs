@@ -0,0 +1,118 @@
+extern crate xmlparser as xml;
+
+use std::borrow::Cow;
+
+use xml::{ElementEnd, MergedToken, Token, Tokenizer};
+
+fn chunks(text: &str) -> Vec<String> {
+    Tokenizer::from(text).merge_text()
+        .filter_map(|t| match t.unwrap() {
+            MergedToken::Text(chunk) => Some(chunk.as_str().to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn merge_text_01() {
+    assert_eq!(chunks("<p>text</p>"), vec!["text"]);
+}
+
+#[test]
+fn merge_text_02() {
+    assert_eq!(chunks("<p>a <![CDATA[<b>]]> c<![CDATA[]]></p>"), vec!["a <b> c"]);
+}
+
+#[test]
+fn merge_text_03() {
+    assert_eq!(chunks("<p>&lt;&gt;&amp;&apos;&quot;&#x41;&#66;</p>"), vec!["<>&'\"AB"]);
+}
+
+#[test]
+fn merge_text_04() {
+    // Unknown and invalid references are left as is.
+    assert_eq!(chunks("<p>&ent; &amp &#0;</p>"), vec!["&ent; &amp &#0;"]);
+}
+
+#[test]
+fn merge_text_05() {
+    // CDATA is not unescaped.
+    assert_eq!(chunks("<p><![CDATA[&amp;]]></p>"), vec!["&amp;"]);
+}
+
+#[test]
+fn merge_text_06() {
+    // Line endings are normalized, but not the ones from references.
+    assert_eq!(chunks("<p>a\r\nb\rc&#13;<![CDATA[\r\n]]></p>"), vec!["a\nb\nc\r\n"]);
+}
+
+#[test]
+fn merge_text_07() {
+    // Comments and elements split the text.
+    assert_eq!(chunks("<p>a<!--c-->b<e/> <e>c</e></p>"), vec!["a", "b", " ", "c"]);
+}
+
+#[test]
+fn merge_text_08() {
+    let text = "<p>a<![CDATA[b]]>&amp;</p>";
+    let tokens: Vec<_> = Tokenizer::from(text).merge_text().map(|t| t.unwrap()).collect();
+    assert_eq!(tokens.len(), 4);
+
+    match tokens[2] {
+        MergedToken::Text(ref chunk) => {
+            let spans: Vec<_> = chunk.spans().iter().map(|s| (s.to_str(), s.start())).collect();
+            assert_eq!(spans, vec![("a", 3), ("b", 13), ("&amp;", 17)]);
+        }
+        _ => panic!(),
+    }
+
+    match tokens[3] {
+        MergedToken::Token(Token::ElementEnd(ElementEnd::Close(_, local))) => {
+            assert_eq!(local.to_str(), "p");
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn merge_text_09() {
+    let tokens: Vec<_> = Tokenizer::from("<p>text</p>").merge_text().collect();
+    match tokens[2] {
+        Ok(MergedToken::Text(ref chunk)) => {
+            assert!(matches!(chunk.clone().into_text(), Cow::Borrowed("text")));
+        }
+        _ => panic!(),
+    }
+
+    let tokens: Vec<_> = Tokenizer::from("<p>a&amp;b</p>").merge_text().collect();
+    match tokens[2] {
+        Ok(MergedToken::Text(ref chunk)) => {
+            assert!(matches!(chunk.clone().into_text(), Cow::Owned(_)));
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn merge_text_10() {
+    // The text before an error is returned first.
+    let mut iter = Tokenizer::from("<p>text<!-x-></p>").merge_text();
+    assert!(iter.nth(2).unwrap().is_ok());
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn merge_text_11() {
+    // Control characters are allowed as references only in XML 1.1.
+    assert_eq!(chunks("<p>&#x1;</p>"), vec!["&#x1;"]);
+    assert_eq!(chunks("<?xml version='1.1'?><p>&#x1;</p>"), vec!["\u{1}"]);
+}
+
+#[test]
+fn merge_text_12() {
+    // NEL and LINE SEPARATOR are line breaks only in XML 1.1.
+    assert_eq!(chunks("<?xml version='1.1'?><a>x\r\u{85}y\u{2028}z<![CDATA[\u{85}]]></a>"),
+               vec!["x\ny\nz\n"]);
+    assert_eq!(chunks("<a>x\r\u{85}y\u{2028}z</a>"), vec!["x\n\u{85}y\u{2028}z"]);
+}
@@ -0,0 +1,145 @@
+extern crate xmlparser as xml;
+
+use xml::{Token, Tokenizer};
+
+fn serial<'a>(text: &'a str) -> Vec<Token<'a>> {
+    Tokenizer::from(text).map(|t| t.unwrap()).collect()
+}
+
+fn split<'a>(text: &'a str, n: usize) -> (usize, Vec<Token<'a>>) {
+    let parts = Tokenizer::split(text, n);
+    let count = parts.len();
+    (count, parts.into_iter().flatten().map(|t| t.unwrap()).collect())
+}
+
+fn records(n: usize) -> String {
+    let mut s = String::from("<?xml version='1.0'?>\n<!DOCTYPE records>\n<records a='1'>\n");
+    for i in 0..n {
+        s.push_str(&format!("  <r:record id='{}'><value>{}</value><empty/></r:record>\n", i, i));
+    }
+    s.push_str("</records>\n<!-- end -->\n");
+    s
+}
+
+#[test]
+fn split_01() {
+    let text = records(100);
+    let (count, tokens) = split(&text, 8);
+    assert_eq!(count, 9);
+    assert_eq!(tokens, serial(&text));
+}
+
+#[test]
+fn split_02() {
+    // More parts than records.
+    let text = records(3);
+    let (count, tokens) = split(&text, 16);
+    assert_eq!(count, 4);
+    assert_eq!(tokens, serial(&text));
+}
+
+#[test]
+fn split_03() {
+    // Cannot split after a comment.
+    let text = "<a><b>1</b><b>2</b><!-- <b> --><b>3</b><b>4</b><b>5</b></a>";
+    let parts = Tokenizer::split(text, 5);
+    assert_eq!(parts.len(), 3);
+    let tokens: Vec<_> = parts.into_iter().flatten().map(|t| t.unwrap()).collect();
+    assert_eq!(tokens, serial(text));
+}
+
+#[test]
+fn split_04() {
+    // Cannot split at all.
+    assert_eq!(Tokenizer::split("<a/>", 4).len(), 1);
+    assert_eq!(Tokenizer::split("<a>text</a>", 4).len(), 1);
+    assert_eq!(Tokenizer::split("<a><![CDATA[<b>]]></a>", 4).len(), 1);
+}
+
+#[test]
+fn split_05() {
+    // Elements with a similar name are not records.
+    let text = "<a><b>1</b><bb>2</bb><bb>3</bb><bb>4</bb></a>";
+    let (count, tokens) = split(text, 4);
+    assert_eq!(count, 2);
+    assert_eq!(tokens, serial(text));
+}
+
+#[test]
+fn split_06() {
+    // Errors have absolute positions.
+    let mut text = records(10);
+    text.push_str("<x/>");
+    let errors: Vec<_> = Tokenizer::split(&text, 4).into_iter()
+        .flatten()
+        .filter_map(|t| t.err())
+        .map(|e| e.to_string())
+        .collect();
+
+    let expected: Vec<_> = Tokenizer::from(text.as_str())
+        .filter_map(|t| t.err())
+        .map(|e| e.to_string())
+        .collect();
+
+    assert_eq!(errors, expected);
+}
+
+#[test]
+fn split_07() {
+    // Zero parts are treated as one.
+    let text = records(10);
+    let (count, tokens) = split(&text, 0);
+    assert_eq!(count, 2);
+    assert_eq!(tokens, serial(&text));
+}
+
+#[test]
+fn parse_parallel_01() {
+    let text = records(1000);
+    let counts = Tokenizer::parse_parallel(&text, 4, |tokenizer| {
+        tokenizer.filter(|t| match *t {
+            Ok(Token::ElementStart(_, local)) => local.to_str() == "record",
+            _ => false,
+        }).count()
+    });
+
+    assert_eq!(counts.len(), 5);
+    assert_eq!(counts.iter().sum::<usize>(), 1000);
+}
+
+#[test]
+fn parse_parallel_02() {
+    let text = records(10);
+    let counts = Tokenizer::parse_parallel(&text, 0, |tokenizer| tokenizer.count());
+    assert_eq!(counts.len(), 2);
+    assert_eq!(counts.iter().sum::<usize>(), serial(&text).len());
+}
+
+#[test]
+fn into_parts_01() {
+    // Modes are copied into each part.
+    let text = "<a><b/>\r<b x=1/>\r<b>&#x0;</b></a>";
+    let mut p = Tokenizer::from(text);
+    p.enable_strict_mode();
+    p.enable_recovery_mode();
+    p.enable_cr_line_breaks_mode();
+
+    let errors = |parts: Vec<Tokenizer>| -> Vec<String> {
+        parts.into_iter().flatten().filter_map(|t| t.err()).map(|e| e.to_string()).collect()
+    };
+
+    let parts = p.clone().into_parts(3);
+    assert_eq!(parts.len(), 3);
+    assert_eq!(errors(parts), errors(vec![p.clone()]));
+    assert_eq!(errors(p.into_parts(3)), vec![
+        "invalid token 'Attribute' at 2:3 cause expected quote mark not '1' at 2:6",
+        "invalid token 'Character data' at 3:4 cause character '&' is not allowed at 3:4",
+    ]);
+}
+
+#[test]
+fn into_parts_02() {
+    let mut p = Tokenizer::from("<a><b/><b/></a>");
+    p.enable_fragment_mode();
+    assert_eq!(p.into_parts(2).len(), 1);
+}
@@ -0,0 +1,160 @@
+extern crate xmlparser as xml;
+
+use xml::{Path, QueryError, Tokenizer};
+
+fn select<'a>(text: &'a str, path: &str) -> Vec<&'a str> {
+    let path = Path::parse(path).unwrap();
+    Tokenizer::from(text).select(&path).map(|s| s.unwrap().to_str()).collect()
+}
+
+const SVG: &str = "\
+<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink'>
+    <defs>
+        <linearGradient id='lg1'/>
+        <radialGradient id='rg1' xlink:href='#lg1'/>
+        <linearGradient id='lg2'><stop offset='0'/></linearGradient>
+    </defs>
+    <g id='g1'>
+        <rect id='r1'/>
+        <g><rect id='r2' class='a &amp; b'/></g>
+    </g>
+</svg>";
+
+#[test]
+fn child_01() {
+    assert_eq!(select(SVG, "/svg/defs/linearGradient/@id"), vec!["lg1", "lg2"]);
+}
+
+#[test]
+fn child_02() {
+    assert_eq!(select(SVG, "/svg/defs/linearGradient"),
+               vec!["<linearGradient id='lg1'/>",
+                    "<linearGradient id='lg2'><stop offset='0'/></linearGradient>"]);
+}
+
+#[test]
+fn child_03() {
+    assert_eq!(select(SVG, "/defs"), Vec::<&str>::new());
+    assert_eq!(select(SVG, "/svg/rect"), Vec::<&str>::new());
+}
+
+#[test]
+fn descendant_01() {
+    assert_eq!(select(SVG, "//rect/@id"), vec!["r1", "r2"]);
+    assert_eq!(select(SVG, "/svg//rect/@id"), vec!["r1", "r2"]);
+    assert_eq!(select(SVG, "/svg/g//g/rect/@id"), vec!["r2"]);
+}
+
+#[test]
+fn descendant_02() {
+    assert_eq!(select(SVG, "//@id"), vec!["lg1", "rg1", "lg2", "g1", "r1", "r2"]);
+    // Includes the context element itself, like `descendant-or-self::node()/@id`.
+    assert_eq!(select(SVG, "/svg/g//@id"), vec!["g1", "r1", "r2"]);
+}
+
+#[test]
+fn descendant_03() {
+    // Nested matches are returned in document order.
+    let text = "<a id='1'><a id='2'><a id='3'/></a></a>";
+    assert_eq!(select(text, "//a"),
+               vec!["<a id='1'><a id='2'><a id='3'/></a></a>", "<a id='2'><a id='3'/></a>", "<a id='3'/>"]);
+    assert_eq!(select(text, "//a/@id"), vec!["1", "2", "3"]);
+}
+
+#[test]
+fn descendant_04() {
+    // A match inside a pending element goes after it.
+    let text = "<r><a><b id='1'/></a></r>";
+    let path = Path::parse("//*").unwrap();
+    let spans: Vec<_> = Tokenizer::from(text).select(&path).map(|s| s.unwrap().start()).collect();
+    assert_eq!(spans, vec![0, 3, 6]);
+}
+
+#[test]
+fn wildcard_01() {
+    assert_eq!(select(SVG, "/svg/*/@id"), vec!["g1"]);
+    assert_eq!(select(SVG, "/*/defs/*[2]/@*"), vec!["rg1", "#lg1"]);
+}
+
+#[test]
+fn prefix_01() {
+    assert_eq!(select(SVG, "//@xlink:href"), vec!["#lg1"]);
+    assert_eq!(select(SVG, "//@xlink:*"), vec!["#lg1"]);
+    // An unprefixed name doesn't match a prefixed one.
+    assert_eq!(select(SVG, "//@href"), Vec::<&str>::new());
+}
+
+#[test]
+fn prefix_02() {
+    let text = "<x:a xmlns:x='urn:x'><x:b/><b/></x:a>";
+    assert_eq!(select(text, "/x:a/x:b"), vec!["<x:b/>"]);
+    assert_eq!(select(text, "/x:a/b"), vec!["<b/>"]);
+    assert_eq!(select(text, "/x:a/*"), vec!["<x:b/>", "<b/>"]);
+    // Namespace declarations are not attributes.
+    assert_eq!(select(text, "/x:a/@*"), Vec::<&str>::new());
+}
+
+#[test]
+fn position_01() {
+    let text = "<r><a id='1'/><b/><a id='2'/><a id='3'/></r>";
+    assert_eq!(select(text, "/r/a[2]/@id"), vec!["2"]);
+    assert_eq!(select(text, "/r/*[2]"), vec!["<b/>"]);
+    assert_eq!(select(text, "/r/a[4]"), Vec::<&str>::new());
+    assert_eq!(select(text, "/r/a[0]"), Vec::<&str>::new());
+}
+
+#[test]
+fn position_02() {
+    // Positions are counted per parent.
+    let text = "<r><p><a id='1'/><a id='2'/></p><p><a id='3'/></p></r>";
+    assert_eq!(select(text, "//a[1]/@id"), vec!["1", "3"]);
+    assert_eq!(select(text, "/r/p[2]/a[1]/@id"), vec!["3"]);
+}
+
+#[test]
+fn attribute_predicate_01() {
+    assert_eq!(select(SVG, "//*[@id='r2']/@class"), vec!["a &amp; b"]);
+    assert_eq!(select(SVG, "//rect[@class = \"a & b\"]/@id"), vec!["r2"]);
+    assert_eq!(select(SVG, "//*[@xlink:href]/@id"), vec!["rg1"]);
+    assert_eq!(select(SVG, "//*[@class='b']"), Vec::<&str>::new());
+}
+
+#[test]
+fn predicates_01() {
+    // Positions are counted after the previous predicates.
+    let text = "<r><a t='x' id='1'/><a id='2'/><a t='x' id='3'/></r>";
+    assert_eq!(select(text, "/r/a[@t='x'][2]/@id"), vec!["3"]);
+    assert_eq!(select(text, "/r/a[2][@t='x']/@id"), Vec::<&str>::new());
+}
+
+#[test]
+fn errors_01() {
+    let path = Path::parse("//b").unwrap();
+    let mut iter = Tokenizer::from("<a><b/></c>").select(&path);
+    assert_eq!(iter.next().unwrap().unwrap().to_str(), "<b/>");
+    assert_eq!(iter.next().unwrap().unwrap_err().to_string(),
+               "expected 'a' close tag not 'c' at 1:8");
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn errors_02() {
+    let path = Path::parse("//a").unwrap();
+    let mut iter = Tokenizer::from("<a><b>").select(&path);
+    assert_eq!(iter.next().unwrap().unwrap_err().to_string(), "unclosed tag 'b' at 1:4");
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn parse_01() {
+    assert_eq!(Path::parse("a").unwrap_err(), QueryError::InvalidChar('a', 0));
+    assert_eq!(Path::parse("").unwrap_err(), QueryError::UnexpectedEnd);
+    assert_eq!(Path::parse("/a/").unwrap_err(), QueryError::UnexpectedEnd);
+    assert_eq!(Path::parse("/a/@b/c").unwrap_err(), QueryError::InvalidChar('/', 5));
+    assert_eq!(Path::parse("/a/@b[1]").unwrap_err(), QueryError::InvalidChar('[', 5));
+    assert_eq!(Path::parse("/a[x]").unwrap_err(), QueryError::InvalidChar('x', 3));
+    assert_eq!(Path::parse("/a[@b='c]").unwrap_err(), QueryError::UnexpectedEnd);
+    assert_eq!(Path::parse("/1a").unwrap_err(), QueryError::InvalidChar('1', 1));
+    assert_eq!(Path::parse("/a:").unwrap_err(), QueryError::UnexpectedEnd);
+    assert!("/a:b/c:*[ 1 ][@d][@e:f = 'g']//@*".parse::<Path>().is_ok());
+}
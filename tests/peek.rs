@@ -0,0 +1,208 @@
+extern crate xmlparser as xml;
+
+use xml::{Error, Tokenizer};
+
+const SVG: &str = "\
+<svg>
+    <rect x='1' svg:y='2'/>
+    <g>text</g>
+</svg>";
+
+#[test]
+fn peek_01() {
+    let mut p = Tokenizer::from(SVG);
+    let mut tokens = Vec::new();
+    while let Some(token) = p.peek() {
+        let token = token.unwrap();
+        assert_eq!(token, p.next().unwrap().unwrap());
+        tokens.push(token);
+    }
+
+    assert!(p.next().is_none());
+    assert_eq!(tokens, Tokenizer::from(SVG).map(|t| t.unwrap()).collect::<Vec<_>>());
+}
+
+#[test]
+fn peek_02() {
+    let mut p = Tokenizer::from(SVG);
+    p.next();
+    let tokens: Vec<_> = p.clone().take(4).map(|t| t.unwrap()).collect();
+    for (i, token) in tokens.iter().enumerate() {
+        assert_eq!(p.peek_nth(i).unwrap().unwrap(), *token);
+    }
+
+    // Nothing is consumed.
+    assert_eq!(p.next().unwrap().unwrap(), tokens[0]);
+    assert!(p.peek_nth(100).is_none());
+}
+
+#[test]
+fn peek_03() {
+    // Errors are peeked too.
+    let mut p = Tokenizer::from("<a><</a>");
+    assert!(p.peek_nth(2).unwrap().is_err());
+    assert!(p.peek_nth(3).is_none());
+    assert!(p.next().unwrap().is_ok());
+}
+
+#[test]
+fn peek_04() {
+    // Peeked tokens are drained by `next()` with the same positions.
+    let mut p = Tokenizer::from(SVG);
+    assert!(p.peek_nth(5).unwrap().is_ok());
+
+    let mut serial = Tokenizer::from(SVG);
+    for _ in 0..8 {
+        assert_eq!(p.next().unwrap().unwrap(), serial.next().unwrap().unwrap());
+        assert_eq!(p.pos(), serial.pos());
+    }
+
+    // The buffer is reset when the tokenizer is moved.
+    let mut p = Tokenizer::from("<a><b/></a>");
+    p.next();
+    assert!(p.peek_nth(2).unwrap().is_ok());
+    assert_eq!(p.element_source().unwrap().to_str(), "<a><b/></a>");
+    assert!(p.next().is_none());
+}
+
+#[test]
+fn peek_05() {
+    // Modes enabled after peeking are applied to the peeked tokens.
+    let mut p = Tokenizer::from("<a>&#x0;</a>");
+    assert!(p.peek_nth(2).unwrap().is_ok());
+    p.enable_strict_mode();
+    assert!(p.nth(2).unwrap().is_err());
+}
+
+#[test]
+fn peek_06() {
+    let mut p = Tokenizer::from("<a/><b/>");
+    p.peek();
+    p.enable_fragment_mode();
+    assert_eq!(p.filter(|t| t.is_ok()).count(), 4);
+}
+
+#[test]
+fn peek_07() {
+    let mut p = Tokenizer::from("<a><b x></b><c/></a>");
+    p.peek();
+    p.enable_recovery_mode();
+    assert_eq!(p.count(), 9);
+}
+
+#[test]
+fn peek_08() {
+    let mut p = Tokenizer::from("<a/><!--\u{80}-->");
+    assert!(p.peek_nth(1).unwrap().is_ok());
+    p.enable_xml11_mode();
+    assert!(p.nth(2).unwrap().is_err());
+}
+
+#[test]
+fn peek_09() {
+    let mut p = Tokenizer::from("<a>\r<b x/></a>");
+    p.peek();
+    p.enable_cr_line_breaks_mode();
+    let err = p.filter_map(|t| t.err()).next().unwrap();
+    assert_eq!(err.pos().to_string(), "2:3");
+}
+
+#[test]
+fn expect_01() {
+    let mut p = Tokenizer::from(SVG);
+    p.expect_element_start("svg").unwrap();
+    p.next();
+    p.next();
+
+    p.expect_element_start("rect").unwrap();
+    assert_eq!(p.expect_attribute("x").unwrap().to_str(), "1");
+    assert_eq!(p.expect_attribute("svg:y").unwrap().to_str(), "2");
+    p.next();
+    p.next();
+
+    p.expect_element_start("g").unwrap();
+    p.next();
+    p.next();
+    p.expect_close("g").unwrap();
+    p.next();
+    p.expect_close("svg").unwrap();
+    assert!(p.next().is_none());
+}
+
+#[test]
+fn expect_02() {
+    // The token is not consumed on error.
+    let mut p = Tokenizer::from(SVG);
+    let err = p.expect_element_start("g").unwrap_err();
+    assert_eq!(err.to_string(), "unexpected token 'Element Start' at 1:1");
+    assert_eq!(err.range(), 0..4);
+    assert!(p.expect_close("svg").is_err());
+    assert!(p.expect_attribute("svg").is_err());
+    p.expect_element_start("svg").unwrap();
+}
+
+#[test]
+fn expect_03() {
+    // Error ranges don't include spaces around the token.
+    let mut p = Tokenizer::from(SVG);
+    p.nth(2);
+    p.expect_element_start("rect").unwrap();
+
+    let err = p.expect_attribute("y").unwrap_err();
+    assert_eq!(err.to_string(), "unexpected token 'Attribute' at 2:11");
+    assert_eq!(&SVG[err.range()], "x='1'");
+
+    p.next();
+    let err = p.expect_attribute("svg:x").unwrap_err();
+    assert_eq!(&SVG[err.range()], "svg:y='2'");
+
+    // Text is returned as is.
+    p.next();
+    let err = p.expect_close("rect").unwrap_err();
+    assert_eq!(err.to_string(), "unexpected token 'Element Close' at 2:26");
+    p.next();
+    let err = p.expect_close("rect").unwrap_err();
+    assert_eq!(err.to_string(), "unexpected token 'Whitespace' at 2:28");
+    assert_eq!(&SVG[err.range()], "\n    ");
+}
+
+#[test]
+fn expect_04() {
+    let mut p = Tokenizer::from("<a><b></c></a>");
+    p.nth(3);
+    match p.expect_close("b").unwrap_err() {
+        Error::UnexpectedCloseTag(expected, actual, pos, range) => {
+            assert_eq!(&*expected, "b");
+            assert_eq!(&*actual, "c");
+            assert_eq!(pos.to_string(), "1:7");
+            assert_eq!(range, 6..10);
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn expect_05() {
+    // Tokenizer errors are returned as is.
+    let mut p = Tokenizer::from("<a x=1/>");
+    p.next();
+    assert_eq!(p.expect_attribute("x").unwrap_err().to_string(),
+               "invalid token 'Attribute' at 1:3 cause expected quote mark not '1' at 1:6");
+}
+
+#[test]
+fn expect_06() {
+    let mut p = Tokenizer::from("<a/>");
+    p.nth(1);
+    assert_eq!(p.expect_element_start("b").unwrap_err().to_string(),
+               "invalid token 'Element Start' at 1:5 cause unexpected end of stream");
+}
+
+#[test]
+fn tokenizer_methods_01() {
+    // The tokenizer is still available while peeking.
+    let mut p = Tokenizer::from("<a/><b/>");
+    p.enable_fragment_mode();
+    assert!(p.peek_nth(3).unwrap().is_ok());
+    assert_eq!(p.pos(), 0);
+}
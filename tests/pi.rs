@@ -116,3 +116,11 @@ test!(declaration_err_12, "<?xml version='1.0'?><?xml version='1.0'?>",
     Token::Declaration("1.0", None, None),
     Token::Error("unexpected token 'Declaration' at 1:22".to_string())
 );
+
+test!(declaration_13, "<?xml\tversion='1.0'?>",
+    Token::Declaration("1.0", None, None)
+);
+
+test!(declaration_14, "<?xml\r\nversion='1.0'?>",
+    Token::Declaration("1.0", None, None)
+);
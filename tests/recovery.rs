@@ -0,0 +1,68 @@
+extern crate xmlparser as xml;
+
+#[macro_use] mod token;
+use token::*;
+
+macro_rules! test_recovery {
+    ($name:ident, $text:expr, $($token:expr),*) => (
+        #[test]
+        fn $name() {
+            let mut p = xml::Tokenizer::from($text);
+            p.enable_recovery_mode();
+            $(
+                let t = p.next().unwrap();
+                assert_eq!(to_test_token(t), $token);
+            )*
+            assert!(p.next().is_none());
+        }
+    )
+}
+
+test_recovery!(recovery_01, "<a><></a>",
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Error("invalid token 'Element Start' at 1:4 cause invalid name token".to_string()),
+    Token::ElementEnd(ElementEnd::Close("", "a"))
+);
+
+test_recovery!(recovery_02, "<a x='1' y z='2'/>",
+    Token::ElementStart("", "a"),
+    Token::Attribute("", "x", "1"),
+    Token::Error("invalid token 'Attribute' at 1:10 cause expected '=' not 'z' at 1:12".to_string()),
+    Token::ElementEnd(ElementEnd::Empty)
+);
+
+test_recovery!(recovery_03, "<a x='<'>text</a>",
+    Token::ElementStart("", "a"),
+    Token::Error("invalid token 'Attribute' at 1:3 cause attribute value with '<' character is not allowed".to_string()),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Text("text"),
+    Token::ElementEnd(ElementEnd::Close("", "a"))
+);
+
+test_recovery!(recovery_04, "q<a/>",
+    Token::Error("unknown token at 1:1".to_string()),
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Empty)
+);
+
+test_recovery!(recovery_05, "<!DOCTYPE a [<!ENTITY % >]><a/>",
+    Token::DtdStart("a", None),
+    Token::Error("invalid token 'Doctype Entity Declaration' at 1:14 cause invalid name token".to_string()),
+    Token::DtdEnd,
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Empty)
+);
+
+test_recovery!(recovery_06, "<a/><b/><!-- c -->",
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Empty),
+    Token::Error("unexpected token 'Element Start' at 1:5".to_string()),
+    Token::Comment(" c ")
+);
+
+test_recovery!(recovery_07, "<a x='1' /",
+    Token::ElementStart("", "a"),
+    Token::Attribute("", "x", "1"),
+    Token::Error("invalid token 'Attribute' at 1:10 cause unexpected end of stream".to_string())
+);
@@ -0,0 +1,95 @@
+extern crate xmlparser as xml;
+
+use xml::{Attribute, Handler, StrSpan};
+
+#[derive(Default)]
+struct Recorder {
+    events: Vec<String>,
+}
+
+impl<'a> Handler<'a> for Recorder {
+    fn declaration(&mut self, version: StrSpan<'a>, _: Option<StrSpan<'a>>, _: Option<bool>) {
+        self.events.push(format!("decl {}", version));
+    }
+
+    fn doctype(&mut self, name: StrSpan<'a>, _: Option<xml::ExternalId<'a>>) {
+        self.events.push(format!("doctype {}", name));
+    }
+
+    fn start_element(&mut self, prefix: StrSpan<'a>, local: StrSpan<'a>,
+                     attributes: &[Attribute<'a>]) {
+        let attrs: Vec<String> = attributes.iter()
+            .map(|a| format!("{}={}", a.local(), a.value())).collect();
+        self.events.push(format!("start {}:{} [{}]", prefix, local, attrs.join(" ")));
+    }
+
+    fn end_element(&mut self, prefix: StrSpan<'a>, local: StrSpan<'a>) {
+        self.events.push(format!("end {}:{}", prefix, local));
+    }
+
+    fn characters(&mut self, text: StrSpan<'a>) {
+        self.events.push(format!("text {}", text));
+    }
+
+    fn comment(&mut self, text: StrSpan<'a>) {
+        self.events.push(format!("comment {}", text));
+    }
+
+    fn processing_instruction(&mut self, target: StrSpan<'a>, _: Option<StrSpan<'a>>) {
+        self.events.push(format!("pi {}", target));
+    }
+}
+
+fn parse(text: &str) -> Result<Vec<String>, String> {
+    let mut handler = Recorder::default();
+    match xml::Tokenizer::from(text).parse_with(&mut handler) {
+        Ok(()) => Ok(handler.events),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[test]
+fn sax_01() {
+    let events = parse("<?xml version='1.0'?><!DOCTYPE a><!--c--><a x='1' y='2'><b/>t<![CDATA[d]]><?p?></a>");
+    assert_eq!(events.unwrap(), vec![
+        "decl 1.0",
+        "doctype a",
+        "comment c",
+        "start :a [x=1 y=2]",
+        "start :b []",
+        "end :b",
+        "text t",
+        "text d",
+        "pi p",
+        "end :a",
+    ]);
+}
+
+#[test]
+fn sax_02() {
+    let events = parse("<n:a><n:b></n:b></n:a>");
+    assert_eq!(events.unwrap(), vec![
+        "start n:a []",
+        "start n:b []",
+        "end n:b",
+        "end n:a",
+    ]);
+}
+
+#[test]
+fn sax_err_01() {
+    assert_eq!(parse("<a><b></a></b>").unwrap_err(),
+               "expected 'b' close tag not 'a' at 1:7");
+}
+
+#[test]
+fn sax_err_02() {
+    assert_eq!(parse("\n  <a><b></b>").unwrap_err(),
+               "unclosed tag 'a' at 2:3");
+}
+
+#[test]
+fn sax_err_03() {
+    assert_eq!(parse("<a x='1' x>").unwrap_err(),
+               "invalid token 'Attribute' at 1:10 cause expected '=' not '>' at 1:11");
+}
@@ -0,0 +1,353 @@
+#![cfg(feature = "schema")]
+
+extern crate xmlparser as xml;
+
+use xml::{Error, Tokenizer, ValidityError};
+use xml::schema::{Schema, SchemaError};
+
+// Returns validity errors as strings.
+fn errors(schema: &Schema, text: &str) -> Vec<String> {
+    Tokenizer::from(text).validate_schema(schema).filter_map(|t| t.err()).map(|e| e.to_string()).collect()
+}
+
+fn load_error(text: &str) -> String {
+    Schema::parse(text).unwrap_err().to_string()
+}
+
+const BOOK: &str = "\
+<xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema'>
+    <xs:element name='book' type='Book'/>
+    <xs:element name='note' type='xs:string'/>
+    <xs:complexType name='Book'>
+        <xs:sequence>
+            <xs:element name='title' type='xs:string'/>
+            <xs:choice maxOccurs='unbounded'>
+                <xs:element name='chapter' type='Chapter'/>
+                <xs:element ref='note'/>
+            </xs:choice>
+            <xs:element name='index' minOccurs='0'>
+                <xs:complexType/>
+            </xs:element>
+        </xs:sequence>
+        <xs:attribute name='isbn' type='Isbn' use='required'/>
+        <xs:attribute name='pages' type='xs:positiveInteger'/>
+        <xs:attribute name='kind' type='Kind' fixed='paper'/>
+    </xs:complexType>
+    <xs:complexType name='Chapter' mixed='true'>
+        <xs:sequence>
+            <xs:element name='em' type='xs:string' minOccurs='0' maxOccurs='2'/>
+        </xs:sequence>
+    </xs:complexType>
+    <xs:simpleType name='Isbn'>
+        <xs:restriction base='xs:string'>
+            <xs:length value='13'/>
+        </xs:restriction>
+    </xs:simpleType>
+    <xs:simpleType name='Kind'>
+        <xs:restriction base='xs:token'>
+            <xs:enumeration value='paper'/>
+            <xs:enumeration value='ebook'/>
+        </xs:restriction>
+    </xs:simpleType>
+</xs:schema>";
+
+fn book(body: &str) -> Vec<String> {
+    errors(&Schema::parse(BOOK).unwrap(), body)
+}
+
+#[test]
+fn valid_01() {
+    let schema = Schema::parse(BOOK).unwrap();
+    let text = "\
+<book isbn='9780000000000' pages='10' kind=' paper '>
+    <title>Title</title>
+    <chapter>Text <em>a</em> text<!-- c --><em>b</em></chapter>
+    <note>n</note>
+    <chapter/>
+    <index/>
+</book>";
+
+    // Valid documents produce the same tokens.
+    let tokens: Vec<_> = Tokenizer::from(text).validate_schema(&schema).map(|t| t.unwrap()).collect();
+    assert_eq!(tokens, Tokenizer::from(text).map(|t| t.unwrap()).collect::<Vec<_>>());
+}
+
+#[test]
+fn content_01() {
+    let isbn = "isbn='9780000000000'";
+    assert_eq!(book(&format!("<book {}><chapter/></book>", isbn)),
+               vec!["element 'chapter' is not allowed here in 'book' at 1:28",
+                    "element 'book' content is incomplete at 1:38"]);
+    assert_eq!(book(&format!("<book {}><title/></book>", isbn)),
+               vec!["element 'book' content is incomplete at 1:36"]);
+    assert_eq!(book(&format!("<book {}><title/><note/><index/><chapter/></book>", isbn)),
+               vec!["element 'chapter' is not allowed here in 'book' at 1:51"]);
+    assert_eq!(book(&format!("<book {}><title/><note/><em/></book>", isbn)),
+               vec!["element 'em' is not allowed here in 'book' at 1:43"]);
+}
+
+#[test]
+fn content_02() {
+    // maxOccurs, mixed and empty content.
+    let isbn = "isbn='9780000000000'";
+    assert_eq!(book(&format!("<book {}><title/><chapter><em/><em/><em/></chapter></book>", isbn)),
+               vec!["element 'em' is not allowed here in 'chapter' at 1:55"]);
+    assert_eq!(book(&format!("<book {}><title>a</title><chapter/><index>text</index></book>", isbn)),
+               vec!["text is not allowed in 'index' at 1:61"]);
+    assert_eq!(book(&format!("<book {}>text<title/><chapter/></book>", isbn)),
+               vec!["text is not allowed in 'book' at 1:28"]);
+    assert_eq!(book("<chapter/>"), vec!["element 'chapter' is not declared at 1:1"]);
+}
+
+#[test]
+fn content_03() {
+    let schema = Schema::parse("
+<xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema'>
+    <xs:element name='a'>
+        <xs:complexType>
+            <xs:all>
+                <xs:element name='b'/>
+                <xs:element name='c' minOccurs='0'/>
+            </xs:all>
+        </xs:complexType>
+    </xs:element>
+    <xs:element name='d'/>
+</xs:schema>").unwrap();
+
+    assert_eq!(errors(&schema, "<a><c/><b/></a>"), Vec::<String>::new());
+    assert_eq!(errors(&schema, "<a><c/></a>"), vec!["element 'a' content is incomplete at 1:8"]);
+    assert_eq!(errors(&schema, "<a><b/><b/></a>"), vec!["element 'b' is not allowed here in 'a' at 1:8"]);
+
+    // Untyped elements accept anything.
+    assert_eq!(errors(&schema, "<d a='1'>text<e/></d>"), Vec::<String>::new());
+}
+
+#[test]
+fn content_04() {
+    // Wildcards are validated only when a global declaration exists.
+    let schema = Schema::parse("
+<xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema'>
+    <xs:element name='a'>
+        <xs:complexType>
+            <xs:sequence>
+                <xs:any namespace='##local' maxOccurs='unbounded'/>
+            </xs:sequence>
+        </xs:complexType>
+    </xs:element>
+    <xs:element name='b' type='xs:int'/>
+</xs:schema>").unwrap();
+
+    assert_eq!(errors(&schema, "<a><c><d/></c><b>1</b></a>"), Vec::<String>::new());
+    assert_eq!(errors(&schema, "<a><b>x</b></a>"), vec!["element 'b' value must be a valid xs:int at 1:7"]);
+    assert_eq!(errors(&schema, "<a><n:c xmlns:n='urn:n'/></a>"),
+               vec!["element 'n:c' is not allowed here in 'a' at 1:4",
+                    "element 'a' content is incomplete at 1:26"]);
+}
+
+#[test]
+fn attributes_01() {
+    assert_eq!(book("<book><title/><note/></book>"),
+               vec!["required attribute 'isbn' is missing in 'book' at 1:1"]);
+    assert_eq!(book("<book isbn='1' pages='0' kind='cd' color='red'><title/><note/></book>"),
+               vec!["attribute 'isbn' value must be exactly 13 characters long at 1:7",
+                    "attribute 'pages' value must be a valid xs:positiveInteger at 1:16",
+                    "attribute 'kind' value must be one of 'paper', 'ebook' at 1:26",
+                    "attribute 'color' is not declared for 'book' at 1:36"]);
+    assert_eq!(book("<book isbn='9780000000000' kind='ebook'><title/><note/></book>"),
+               vec!["attribute 'kind' value must be 'paper' at 1:28"]);
+
+    // xsi attributes and namespace declarations are ignored.
+    let text = "<book xmlns:xsi='http://www.w3.org/2001/XMLSchema-instance' \
+                xsi:noNamespaceSchemaLocation='book.xsd' isbn='9780000000000'><title/><note/></book>";
+    assert_eq!(book(text), Vec::<String>::new());
+}
+
+#[test]
+fn simple_types_01() {
+    let schema = Schema::parse("
+<xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema'>
+    <xs:element name='a'>
+        <xs:complexType>
+            <xs:sequence>
+                <xs:element name='percent' type='Percent' minOccurs='0'/>
+                <xs:element name='price' minOccurs='0'>
+                    <xs:simpleType>
+                        <xs:restriction base='xs:decimal'>
+                            <xs:minExclusive value='0'/>
+                            <xs:totalDigits value='5'/>
+                            <xs:fractionDigits value='2'/>
+                        </xs:restriction>
+                    </xs:simpleType>
+                </xs:element>
+                <xs:element name='sizes' minOccurs='0'>
+                    <xs:simpleType>
+                        <xs:list itemType='Percent'/>
+                    </xs:simpleType>
+                </xs:element>
+                <xs:element name='date' type='xs:date' minOccurs='0'/>
+            </xs:sequence>
+        </xs:complexType>
+    </xs:element>
+    <xs:simpleType name='Percent'>
+        <xs:restriction base='xs:unsignedByte'>
+            <xs:maxInclusive value='100'/>
+        </xs:restriction>
+    </xs:simpleType>
+</xs:schema>").unwrap();
+
+    let text = "<a><percent> 10 </percent><price>1.50</price><sizes>1 2\n3</sizes><date>2020-02-29</date></a>";
+    assert_eq!(errors(&schema, text), Vec::<String>::new());
+    assert_eq!(errors(&schema, "<a><percent>101</percent></a>"),
+               vec!["element 'percent' value must be less than or equal to 100 at 1:13"]);
+    assert_eq!(errors(&schema, "<a><percent>-1</percent></a>"),
+               vec!["element 'percent' value must be a valid xs:unsignedByte at 1:13"]);
+    assert_eq!(errors(&schema, "<a><price>0</price></a>"),
+               vec!["element 'price' value must be greater than 0 at 1:11"]);
+    assert_eq!(errors(&schema, "<a><price>1.234</price></a>"),
+               vec!["element 'price' value must be at most 2 fraction digits long at 1:11"]);
+    assert_eq!(errors(&schema, "<a><price>1234.56</price></a>"),
+               vec!["element 'price' value must be at most 5 digits long at 1:11"]);
+    assert_eq!(errors(&schema, "<a><sizes>1 200</sizes></a>"),
+               vec!["element 'sizes' value must be less than or equal to 100 at 1:11"]);
+    assert_eq!(errors(&schema, "<a><date>2021-02-29</date></a>"),
+               vec!["element 'date' value must be a valid xs:date at 1:10"]);
+    assert_eq!(errors(&schema, "<a><percent>1<b/></percent></a>"),
+               vec!["element 'b' is not allowed here in 'percent' at 1:14"]);
+}
+
+#[test]
+fn simple_content_01() {
+    let schema = Schema::parse("
+<xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema'>
+    <xs:element name='size'>
+        <xs:complexType>
+            <xs:simpleContent>
+                <xs:extension base='xs:int'>
+                    <xs:attribute name='unit' type='xs:token' default='cm'/>
+                </xs:extension>
+            </xs:simpleContent>
+        </xs:complexType>
+    </xs:element>
+</xs:schema>").unwrap();
+
+    assert_eq!(errors(&schema, "<size unit='mm'>1<!-- c -->0</size>"), Vec::<String>::new());
+    assert_eq!(errors(&schema, "<size>a&amp;</size>"),
+               vec!["element 'size' value must be a valid xs:int at 1:7"]);
+    assert_eq!(errors(&schema, "<size/>"),
+               vec!["element 'size' value must be a valid xs:int at 1:6"]);
+}
+
+#[test]
+fn namespaces_01() {
+    let schema = Schema::parse("
+<xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema' xmlns:t='urn:t'
+           targetNamespace='urn:t' elementFormDefault='qualified'>
+    <xs:element name='a' type='t:A'/>
+    <xs:complexType name='A'>
+        <xs:sequence>
+            <xs:element name='b' type='xs:string'/>
+            <xs:element name='c' type='xs:string' form='unqualified'/>
+            <xs:element ref='t:a' minOccurs='0'/>
+        </xs:sequence>
+        <xs:attribute name='n' type='xs:int'/>
+        <xs:attribute name='q' type='xs:int' form='qualified'/>
+    </xs:complexType>
+</xs:schema>").unwrap();
+
+    assert_eq!(errors(&schema, "<a xmlns='urn:t' n='1'><b/><c xmlns=''/><a><b/><c xmlns=''/></a></a>"),
+               Vec::<String>::new());
+    assert_eq!(errors(&schema, "<t:a xmlns:t='urn:t' t:q='1'><t:b/><c/></t:a>"), Vec::<String>::new());
+    assert_eq!(errors(&schema, "<a><b/><c/></a>"), vec!["element 'a' is not declared at 1:1"]);
+    assert_eq!(errors(&schema, "<a xmlns='urn:t'><b/><c/></a>"),
+               vec!["element 'c' is not allowed here in 'a' at 1:22",
+                    "element 'a' content is incomplete at 1:26"]);
+    assert_eq!(errors(&schema, "<t:a xmlns:t='urn:t' t:n='1' q='1'><t:b/><c/></t:a>"),
+               vec!["attribute 't:n' is not declared for 't:a' at 1:22",
+                    "attribute 'q' is not declared for 't:a' at 1:30"]);
+}
+
+#[test]
+fn recursion_01() {
+    let schema = Schema::parse("
+<xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema'>
+    <xs:element name='list' type='List'/>
+    <xs:complexType name='List'>
+        <xs:sequence minOccurs='0' maxOccurs='unbounded'>
+            <xs:element name='item' type='xs:string'/>
+            <xs:element name='list' type='List' minOccurs='0'/>
+        </xs:sequence>
+    </xs:complexType>
+</xs:schema>").unwrap();
+
+    assert_eq!(errors(&schema, "<list><item/><list><item/><item/><list/></list></list>"), Vec::<String>::new());
+    assert_eq!(errors(&schema, "<list><item/><list><list/></list></list>"),
+               vec!["element 'list' is not allowed here in 'list' at 1:20"]);
+}
+
+#[test]
+fn schema_01() {
+    assert_eq!(load_error("<schema/>"), "unexpected schema element 'schema' at 1:1");
+    assert_eq!(load_error("<xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema'><xs:import/></xs:schema>"),
+               "'xs:import' is not supported at 1:56");
+    assert_eq!(load_error("<xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema'>\
+                           <xs:element type='xs:string'/></xs:schema>"),
+               "'xs:element' must have the 'name' attribute at 1:56");
+    assert_eq!(load_error("<xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema'>\
+                           <xs:element name='a' type='B'/></xs:schema>"),
+               "unknown reference 'B' at 1:56");
+    assert_eq!(load_error("<xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema'>\
+                           <xs:element name='a' nillable='true'/></xs:schema>"),
+               "'nillable' is not supported at 1:56");
+    assert_eq!(load_error("<xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema'>\
+                           <xs:simpleType name='a'><xs:restriction base='xs:string'>\
+                           <xs:pattern value='a'/></xs:restriction></xs:simpleType></xs:schema>"),
+               "'xs:pattern' is not supported at 1:113");
+    assert_eq!(load_error("<xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema'>\
+                           <xs:simpleType name='a'><xs:restriction base='b'/></xs:simpleType>\
+                           <xs:simpleType name='b'><xs:restriction base='a'/></xs:simpleType></xs:schema>"),
+               "unknown reference 'b' at 1:80");
+    assert_eq!(load_error("<xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema'>\
+                           <xs:element name='a'><xs:complexType><xs:sequence>\
+                           <xs:element name='b' minOccurs='2' maxOccurs='1'/>\
+                           </xs:sequence></xs:complexType></xs:element></xs:schema>"),
+               "invalid 'maxOccurs' attribute value at 1:106");
+    assert!(matches!(Schema::parse("<a>"), Err(SchemaError::Xml(_))));
+
+    // Nested particles expansion is limited too.
+    let text = "<xs:schema xmlns:xs='http://www.w3.org/2001/XMLSchema'>\
+                <xs:element name='a'><xs:complexType>\
+                <xs:sequence maxOccurs='1000'><xs:sequence maxOccurs='1000'>\
+                <xs:sequence maxOccurs='1000'><xs:element name='b'/></xs:sequence>\
+                </xs:sequence></xs:sequence></xs:complexType></xs:element></xs:schema>";
+    assert_eq!(load_error(text), "'maxOccurs' is not supported at 1:123");
+}
+
+#[test]
+fn error_01() {
+    let schema = Schema::parse(BOOK).unwrap();
+    let text = "<book isbn='9780000000000'><title/><note>&#x30;</note><chapter>\n<em>1</em>\n</chapter></book>";
+    assert_eq!(errors(&schema, text), Vec::<String>::new());
+
+    let text = "<book isbn='9780000000000' pages='x'><title/><note/></book>";
+    let err = Tokenizer::from(text).validate_schema(&schema).find_map(|t| t.err()).unwrap();
+    match err {
+        Error::InvalidDocument(ref cause, pos, ref range) => {
+            assert_eq!(**cause, ValidityError::InvalidAttributeValue("pages".into(),
+                                                                      "a valid xs:positiveInteger".into()));
+            assert_eq!(pos.to_string(), "1:28");
+            assert_eq!(&text[range.clone()], "pages='x'");
+        }
+        _ => panic!(),
+    }
+
+    assert_eq!(err.code(), "XML-V011");
+}
+
+#[test]
+fn well_formedness_01() {
+    // The validation stops on a well-formedness error.
+    assert_eq!(book("<book><title></book>"),
+               vec!["required attribute 'isbn' is missing in 'book' at 1:1",
+                    "expected 'title' close tag not 'book' at 1:14"]);
+    assert_eq!(book("<book isbn='9780000000000'><title/><note/>"), vec!["unclosed tag 'book' at 1:1"]);
+}
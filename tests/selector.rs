@@ -0,0 +1,149 @@
+extern crate xmlparser as xml;
+
+use xml::{Selector, QueryError, Tokenizer};
+
+fn select<'a>(text: &'a str, selector: &str) -> Vec<&'a str> {
+    let selector = Selector::parse(selector).unwrap();
+    Tokenizer::from(text).select_elements(&selector).map(|m| m.unwrap().span().to_str()).collect()
+}
+
+const HTML: &str = "\
+<html xmlns:svg='http://www.w3.org/2000/svg'>
+    <body>
+        <p id='p1' class='intro'>1</p>
+        <div class=' note  wide '>
+            <p id='p2'>2</p>
+            <span><p id='p3' lang='en'>3</p></span>
+        </div>
+        <svg:svg><svg:rect id='r1' svg:fill='red'/></svg:svg>
+        <p id='p4' title='a &amp; b'/>
+    </body>
+</html>";
+
+#[test]
+fn type_01() {
+    assert_eq!(select(HTML, "p"),
+               vec!["<p id='p1' class='intro'>", "<p id='p2'>", "<p id='p3' lang='en'>",
+                    "<p id='p4' title='a &amp; b'/>"]);
+    assert_eq!(select(HTML, "html"), vec!["<html xmlns:svg='http://www.w3.org/2000/svg'>"]);
+    assert_eq!(select(HTML, "table"), Vec::<&str>::new());
+}
+
+#[test]
+fn type_02() {
+    // A name without a prefix matches any prefix.
+    assert_eq!(select(HTML, "rect"), vec!["<svg:rect id='r1' svg:fill='red'/>"]);
+    assert_eq!(select(HTML, "svg|rect"), vec!["<svg:rect id='r1' svg:fill='red'/>"]);
+    assert_eq!(select(HTML, "|rect"), Vec::<&str>::new());
+    assert_eq!(select(HTML, "|body"), vec!["<body>"]);
+}
+
+#[test]
+fn universal_01() {
+    assert_eq!(select(HTML, "span > *"), vec!["<p id='p3' lang='en'>"]);
+}
+
+#[test]
+fn id_01() {
+    assert_eq!(select(HTML, "#p2"), vec!["<p id='p2'>"]);
+    assert_eq!(select(HTML, "div#p2"), Vec::<&str>::new());
+}
+
+#[test]
+fn class_01() {
+    assert_eq!(select(HTML, ".note"), vec!["<div class=' note  wide '>"]);
+    assert_eq!(select(HTML, "div.wide.note"), vec!["<div class=' note  wide '>"]);
+    assert_eq!(select(HTML, ".no"), Vec::<&str>::new());
+}
+
+#[test]
+fn attribute_01() {
+    assert_eq!(select(HTML, "[lang]"), vec!["<p id='p3' lang='en'>"]);
+    assert_eq!(select(HTML, "p[ lang = en ]"), vec!["<p id='p3' lang='en'>"]);
+    assert_eq!(select(HTML, "[title='a & b']"), vec!["<p id='p4' title='a &amp; b'/>"]);
+    assert_eq!(select(HTML, "[svg|fill=\"red\"]"), vec!["<svg:rect id='r1' svg:fill='red'/>"]);
+    // Attributes without a prefix are not in a namespace.
+    assert_eq!(select(HTML, "[fill]"), Vec::<&str>::new());
+}
+
+#[test]
+fn combinators_01() {
+    assert_eq!(select(HTML, "div p"), vec!["<p id='p2'>", "<p id='p3' lang='en'>"]);
+    assert_eq!(select(HTML, "div > p"), vec!["<p id='p2'>"]);
+    assert_eq!(select(HTML, "div>span>p"), vec!["<p id='p3' lang='en'>"]);
+    assert_eq!(select(HTML, "body > p"), vec!["<p id='p1' class='intro'>", "<p id='p4' title='a &amp; b'/>"]);
+    assert_eq!(select(HTML, "html > p"), Vec::<&str>::new());
+}
+
+#[test]
+fn combinators_02() {
+    // Requires a match of the first compound deeper than the first candidate.
+    let text = "<a><b><a><c/></a></b></a>";
+    assert_eq!(select(text, "b a > c"), vec!["<c/>"]);
+    assert_eq!(select(text, "a > b > a > c"), vec!["<c/>"]);
+    assert_eq!(select(text, "a > c"), vec!["<c/>"]);
+    assert_eq!(select(text, "b > c"), Vec::<&str>::new());
+}
+
+#[test]
+fn first_child_01() {
+    assert_eq!(select(HTML, "p:first-child"), vec!["<p id='p1' class='intro'>", "<p id='p2'>",
+                                                   "<p id='p3' lang='en'>"]);
+    assert_eq!(select(HTML, ":first-child"),
+               vec!["<html xmlns:svg='http://www.w3.org/2000/svg'>", "<body>",
+                    "<p id='p1' class='intro'>", "<p id='p2'>", "<p id='p3' lang='en'>",
+                    "<svg:rect id='r1' svg:fill='red'/>"]);
+}
+
+#[test]
+fn list_01() {
+    assert_eq!(select(HTML, "#p4, .intro , #p2"),
+               vec!["<p id='p1' class='intro'>", "<p id='p2'>", "<p id='p4' title='a &amp; b'/>"]);
+}
+
+#[test]
+fn element_match_01() {
+    let selector = Selector::parse("rect").unwrap();
+    let m = Tokenizer::from(HTML).select_elements(&selector).next().unwrap().unwrap();
+    assert_eq!(m.prefix().to_str(), "svg");
+    assert_eq!(m.local().to_str(), "rect");
+
+    let attrs: Vec<_> = m.attributes().iter()
+        .map(|a| (a.prefix().to_str(), a.local().to_str(), a.value().to_str(), a.value().start()))
+        .collect();
+    let offset = HTML.find("'r1'").unwrap() + 1;
+    assert_eq!(attrs, vec![("", "id", "r1", offset), ("svg", "fill", "red", offset + 14)]);
+    assert_eq!(m.span().start(), HTML.find("<svg:rect").unwrap());
+}
+
+#[test]
+fn errors_01() {
+    let selector = Selector::parse("b").unwrap();
+    let mut iter = Tokenizer::from("<a><b/></c>").select_elements(&selector);
+    assert_eq!(iter.next().unwrap().unwrap().span().to_str(), "<b/>");
+    assert_eq!(iter.next().unwrap().unwrap_err().to_string(),
+               "expected 'a' close tag not 'c' at 1:8");
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn errors_02() {
+    let selector = Selector::parse("b").unwrap();
+    let mut iter = Tokenizer::from("<a><b>").select_elements(&selector);
+    assert_eq!(iter.next().unwrap().unwrap().span().to_str(), "<b>");
+    assert_eq!(iter.next().unwrap().unwrap_err().to_string(), "unclosed tag 'b' at 1:4");
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn parse_01() {
+    assert_eq!(Selector::parse("").unwrap_err(), QueryError::UnexpectedEnd);
+    assert_eq!(Selector::parse("a >").unwrap_err(), QueryError::UnexpectedEnd);
+    assert_eq!(Selector::parse("a,").unwrap_err(), QueryError::UnexpectedEnd);
+    assert_eq!(Selector::parse("a + b").unwrap_err(), QueryError::InvalidChar('+', 2));
+    assert_eq!(Selector::parse("a:hover").unwrap_err(), QueryError::InvalidChar('h', 2));
+    assert_eq!(Selector::parse("[a=]").unwrap_err(), QueryError::InvalidChar(']', 3));
+    assert_eq!(Selector::parse("[a='b]").unwrap_err(), QueryError::UnexpectedEnd);
+    assert_eq!(Selector::parse("#1").unwrap_err(), QueryError::InvalidChar('1', 1));
+    assert!("svg|rect.a#b[c][d|e='f']:first-child > * , x y".parse::<Selector>().is_ok());
+}
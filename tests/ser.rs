@@ -0,0 +1,304 @@
+#![cfg(feature = "serde")]
+
+#[macro_use] extern crate serde_derive;
+extern crate serde;
+extern crate xmlparser as xml;
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+use xml::de::{self, Deserializer};
+use xml::ser::{self, Serializer};
+use xml::tree::Document;
+
+// A deterministic generator, so the test is reproducible.
+#[path = "common/rng.rs"]
+mod rng;
+use rng::Rng;
+
+// Checks that the output is well-formed and can be deserialized back.
+fn round_trip<T>(value: &T) -> String
+    where T: Serialize + for<'a> Deserialize<'a> + PartialEq + Debug
+{
+    let text = ser::to_string(value).unwrap();
+    check_well_formed(&text);
+    assert_eq!(de::from_str::<T>(&text).unwrap(), *value, "{}", text);
+    text
+}
+
+fn check_well_formed(text: &str) {
+    let mut tokenizer = xml::Tokenizer::from(text);
+    tokenizer.enable_strict_mode();
+    for token in tokenizer {
+        if let Err(e) = token {
+            panic!("{}: {}", text, e);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Item {
+    #[serde(rename = "@id")]
+    id: u32,
+    #[serde(rename = "$text", default)]
+    text: String,
+}
+
+#[test]
+fn attributes_01() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Root {
+        #[serde(rename = "@a")]
+        a: i32,
+        #[serde(rename = "@b")]
+        b: f64,
+        #[serde(rename = "@c")]
+        c: bool,
+        #[serde(rename = "@d")]
+        d: char,
+        #[serde(rename = "@e")]
+        e: Option<String>,
+        #[serde(rename = "@f")]
+        f: Option<String>,
+    }
+
+    let text = round_trip(&Root { a: -1, b: 2.5, c: true, d: 'x', e: Some("\"&<".to_string()), f: None });
+    assert_eq!(text, "<Root a=\"-1\" b=\"2.5\" c=\"true\" d=\"x\" e=\"&quot;&amp;&lt;\"/>");
+}
+
+#[test]
+fn attributes_02() {
+    // Whitespaces are escaped to survive the attribute value normalization.
+    let item = Item { id: 1, text: String::new() };
+    let mut map = BTreeMap::new();
+    map.insert("@v", "a\tb\nc\r\nd");
+
+    let mut serializer = Serializer::new();
+    serializer.set_root_name("item");
+    map.serialize(&mut serializer).unwrap();
+    let text = serializer.into_string();
+    assert_eq!(text, "<item v=\"a&#x9;b&#xA;c&#xD;&#xA;d\"/>");
+
+    let doc = Document::parse(&text).unwrap();
+    let map: BTreeMap<String, String> = de::from_document(&doc).unwrap();
+    assert_eq!(map["v"], "a\tb\nc\r\nd");
+
+    assert_eq!(round_trip(&item), "<Item id=\"1\"/>");
+}
+
+#[test]
+fn attributes_03() {
+    // Attributes are written into the start tag even after child elements.
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Root {
+        item: Item,
+        #[serde(rename = "@n")]
+        n: u8,
+    }
+
+    let text = round_trip(&Root { item: Item { id: 1, text: "a".to_string() }, n: 2 });
+    assert_eq!(text, "<Root n=\"2\"><item id=\"1\">a</item></Root>");
+}
+
+#[test]
+fn elements_01() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Root {
+        name: String,
+        size: u32,
+        empty: String,
+        #[serde(rename = "item")]
+        items: Vec<Item>,
+        list: Vec<u32>,
+        missing: Option<u32>,
+    }
+
+    let root = Root {
+        name: "a < b".to_string(),
+        size: 10,
+        empty: String::new(),
+        items: vec![Item { id: 1, text: "x".to_string() }, Item { id: 2, text: String::new() }],
+        list: vec![1, 2],
+        missing: None,
+    };
+
+    assert_eq!(round_trip(&root),
+               "<Root><name>a &lt; b</name><size>10</size><empty/>\
+                <item id=\"1\">x</item><item id=\"2\"/><list>1</list><list>2</list></Root>");
+}
+
+#[test]
+fn text_01() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Root {
+        #[serde(rename = "$text")]
+        text: Vec<u32>,
+    }
+
+    assert_eq!(round_trip(&Root { text: vec![1, 2, 3] }), "<Root>1 2 3</Root>");
+}
+
+#[test]
+fn text_02() {
+    // `]]>` and `\r` must not appear in the text as is.
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Root {
+        #[serde(rename = "$text")]
+        text: String,
+    }
+
+    let text = round_trip(&Root { text: "]]>\r\n".to_string() });
+    assert_eq!(text, "<Root>]]&gt;&#xD;\n</Root>");
+}
+
+#[test]
+fn enums_01() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Kind {
+        A,
+        B(u32),
+        C { #[serde(rename = "@x")] x: u32, y: String },
+        D(u32, u32),
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Root {
+        #[serde(rename = "@attr")]
+        attr: Kind,
+        kind: Vec<Kind>,
+    }
+
+    let root = Root {
+        attr: Kind::A,
+        kind: vec![Kind::A, Kind::B(1), Kind::C { x: 2, y: "y".to_string() }, Kind::D(3, 4)],
+    };
+
+    assert_eq!(round_trip(&root),
+               "<Root attr=\"A\"><kind>A</kind><kind><B>1</B></kind>\
+                <kind><C x=\"2\"><y>y</y></C></kind><kind><D>3 4</D></kind></Root>");
+}
+
+#[test]
+fn map_01() {
+    let mut map = BTreeMap::new();
+    map.insert("a".to_string(), 1);
+    map.insert("b".to_string(), 2);
+
+    let mut serializer = Serializer::new();
+    serializer.set_root_name("root");
+    map.serialize(&mut serializer).unwrap();
+    assert_eq!(serializer.into_string(), "<root><a>1</a><b>2</b></root>");
+}
+
+#[test]
+fn namespaces_01() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Root {
+        #[serde(rename = "@{urn:x}a")]
+        a: u32,
+        #[serde(rename = "@b")]
+        b: u32,
+        #[serde(rename = "{urn:x}c")]
+        c: u32,
+        #[serde(rename = "{urn:y}d")]
+        d: u32,
+        #[serde(rename = "@{http://www.w3.org/XML/1998/namespace}lang")]
+        lang: String,
+    }
+
+    let root = Root { a: 1, b: 2, c: 3, d: 4, lang: "en".to_string() };
+
+    let mut serializer = Serializer::new();
+    serializer.set_root_name("{urn:y}root");
+    serializer.add_namespace("", "urn:y");
+    serializer.add_namespace("x", "urn:x");
+    serializer.enable_declaration();
+    root.serialize(&mut serializer).unwrap();
+    let text = serializer.into_string();
+    assert_eq!(text, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                      <root xmlns=\"urn:y\" xmlns:x=\"urn:x\" x:a=\"1\" b=\"2\" xml:lang=\"en\">\
+                      <x:c>3</x:c><d>4</d></root>");
+    check_well_formed(&text);
+
+    let doc = Document::parse(&text).unwrap();
+    let mut de = Deserializer::from_document(&doc).unwrap();
+    de.enable_expanded_names();
+    assert_eq!(Root::deserialize(de).unwrap(), root);
+}
+
+#[test]
+fn errors_01() {
+    #[derive(Serialize)]
+    struct Root {
+        #[serde(rename = "@{urn:x}a")]
+        a: u32,
+    }
+
+    assert_eq!(ser::to_string(&Root { a: 1 }).unwrap_err().to_string(),
+               "undeclared namespace 'urn:x'");
+    assert_eq!(ser::to_string(&5).unwrap_err().to_string(),
+               "the root element name is not set");
+    assert_eq!(ser::to_string(&vec![Item { id: 1, text: String::new() }]).unwrap_err().to_string(),
+               "cannot serialize a sequence as the root element");
+
+    let mut serializer = Serializer::new();
+    serializer.set_root_name("1a");
+    assert_eq!(5.serialize(&mut serializer).unwrap_err().to_string(), "invalid name '1a'");
+
+    let mut serializer = Serializer::new();
+    serializer.set_root_name("a");
+    assert_eq!("\u{1}".serialize(&mut serializer).unwrap_err().to_string(),
+               "a character U+0001 is not allowed");
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Node {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@flag")]
+    flag: Option<bool>,
+    #[serde(rename = "$text", default)]
+    text: String,
+    value: Option<i64>,
+    #[serde(rename = "node", default)]
+    children: Vec<Node>,
+}
+
+// Extra generators for the round trip test.
+impl Rng {
+    fn string(&mut self) -> String {
+        const CHARS: &[char] = &[
+            'a', 'b', ' ', '\t', '\n', '\r', '&', '<', '>', '"', '\'', ';', '#', ']', '\u{E9}', '\u{1F600}',
+        ];
+
+        let len = self.range(8);
+        (0..len).map(|_| CHARS[self.range(CHARS.len())]).collect()
+    }
+
+    // Whitespace-only text doesn't round trip, since the deserializer ignores it
+    // by design (see the `de` module docs), so it's never generated.
+    fn text(&mut self) -> String {
+        let s = self.string();
+        if s.chars().all(char::is_whitespace) { String::new() } else { s }
+    }
+
+    fn node(&mut self, depth: u32) -> Node {
+        let children = if depth < 3 { self.range(3) } else { 0 };
+        Node {
+            name: self.string(),
+            flag: match self.range(3) { 0 => None, 1 => Some(false), _ => Some(true) },
+            text: self.text(),
+            value: if self.range(2) == 0 { None } else { Some(self.next() as i64) },
+            children: (0..children).map(|_| self.node(depth + 1)).collect(),
+        }
+    }
+}
+
+#[test]
+fn round_trip_01() {
+    let mut rng = Rng::new();
+    for _ in 0..500 {
+        round_trip(&rng.node(0));
+    }
+}
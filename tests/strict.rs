@@ -0,0 +1,202 @@
+extern crate xmlparser as xml;
+
+#[macro_use] mod token;
+use token::*;
+
+macro_rules! test_strict {
+    ($name:ident, $text:expr, $($token:expr),*) => (
+        #[test]
+        fn $name() {
+            let mut p = xml::Tokenizer::from($text);
+            p.enable_strict_mode();
+            $(
+                let t = p.next().unwrap();
+                assert_eq!(to_test_token(t), $token);
+            )*
+            assert!(p.next().is_none());
+        }
+    )
+}
+
+test_strict!(declaration_01, "<?xml version='1.0' encoding='UTF-8' standalone='yes' ?>",
+    Token::Declaration("1.0", Some("UTF-8"), Some(true))
+);
+
+test_strict!(declaration_02, "<?xml\tversion=\"1.1\"\nencoding=\"x-1\"?>",
+    Token::Declaration("1.1", Some("x-1"), None)
+);
+
+test!(declaration_03, "<?xml version='1.'?>",
+    Token::Declaration("1.", None, None)
+);
+
+test_strict!(declaration_err_01, "<?xml version='1.'?>",
+    Token::Error("invalid token 'Declaration' at 1:1 cause expected '0', '1', '2', '3', '4', \
+                  '5', '6', '7', '8', '9' not ''' at 1:18".to_string())
+);
+
+test!(declaration_04, "<?xml version='1.0\"?>",
+    Token::Declaration("1.0", None, None)
+);
+
+test_strict!(declaration_err_02, "<?xml version='1.0\"?>",
+    Token::Error("invalid token 'Declaration' at 1:1 cause expected ''' not '\"' at 1:19".to_string())
+);
+
+test!(declaration_05, "<?xml version='1.0'encoding='UTF-8'standalone='no'?>",
+    Token::Declaration("1.0", Some("UTF-8"), Some(false))
+);
+
+test_strict!(declaration_err_03, "<?xml version='1.0'encoding='UTF-8'?>",
+    Token::Error("invalid token 'Declaration' at 1:1 cause expected space not 'e' at 1:20".to_string())
+);
+
+test_strict!(declaration_err_04, "<?xml version='1.0' encoding='UTF-8'standalone='no'?>",
+    Token::Error("invalid token 'Declaration' at 1:1 cause expected space not 's' at 1:37".to_string())
+);
+
+test_strict!(declaration_err_05, "<?xml version='1.0' encoding='8bit'?>",
+    Token::Error("invalid token 'Declaration' at 1:1 cause character '8' is not allowed at 1:31".to_string())
+);
+
+test_strict!(declaration_err_06, "<?xml version='1.0' standalone='no' encoding='UTF-8'?>",
+    Token::Error("invalid token 'Declaration' at 1:1 cause expected '?>' not 'en' at 1:37".to_string())
+);
+
+test!(pi_01, "<?XML version='1.0'?>",
+    Token::PI("XML", Some("version='1.0'"))
+);
+
+test_strict!(pi_err_01, "<?XML version='1.0'?>",
+    Token::Error("invalid token 'Processing Instruction' at 1:1 cause name 'XML' is reserved at 1:3".to_string())
+);
+
+test_strict!(pi_err_02, "<a><?xMl?></a>",
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Error("invalid token 'Processing Instruction' at 1:4 cause name 'xMl' is reserved at 1:6".to_string())
+);
+
+test_strict!(pi_02, "<?xml-stylesheet href='a.css'?>",
+    Token::PI("xml-stylesheet", Some("href='a.css'"))
+);
+
+#[test]
+fn error_code_01() {
+    let mut p = xml::Tokenizer::from("<?xml version='1.0' encoding='-'?>");
+    p.enable_strict_mode();
+    assert_eq!(p.next().unwrap().unwrap_err().code(), "XML-E016");
+
+    let mut p = xml::Tokenizer::from("<?XmL?>");
+    p.enable_strict_mode();
+    let e = p.next().unwrap().unwrap_err();
+    assert_eq!(e.code(), "XML-E017");
+    assert_eq!(e.spec_url(), "https://www.w3.org/TR/xml/#NT-PITarget");
+}
+
+test!(text_01, "<a>a\u{1}]]>&b</a>",
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Text("a\u{1}]]>&b"),
+    Token::ElementEnd(ElementEnd::Close("", "a"))
+);
+
+test_strict!(text_02, "<a>&amp;&#x3C;&#60;&ent; ] ]] &gt;</a>",
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Text("&amp;&#x3C;&#60;&ent; ] ]] &gt;"),
+    Token::ElementEnd(ElementEnd::Close("", "a"))
+);
+
+test_strict!(text_err_01, "<a>te\u{1}xt</a>",
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Error("invalid token 'Character data' at 1:4 cause character '\\u{1}' is not allowed at 1:6".to_string())
+);
+
+test_strict!(text_err_02, "<a>\n  a]]>b</a>",
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Error("invalid token 'Character data' at 1:4 cause character '>' is not allowed at 2:6".to_string())
+);
+
+test_strict!(text_err_03, "<a>a & b</a>",
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Error("invalid token 'Character data' at 1:4 cause character '&' is not allowed at 1:6".to_string())
+);
+
+test_strict!(text_err_04, "<a>&#0;</a>",
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Error("invalid token 'Character data' at 1:4 cause character '&' is not allowed at 1:4".to_string())
+);
+
+test_strict!(text_err_05, "<a>текст\u{FFFF}</a>",
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Error("invalid token 'Character data' at 1:4 cause character '\\u{ffff}' is not allowed at 1:9".to_string())
+);
+
+test_strict!(text_xml11_01, "<?xml version='1.1'?><a>&#x1;\u{85}</a>",
+    Token::Declaration("1.1", None, None),
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Text("&#x1;\u{85}"),
+    Token::ElementEnd(ElementEnd::Close("", "a"))
+);
+
+test_strict!(text_xml11_err_01, "<?xml version='1.1'?><a>\u{86}</a>",
+    Token::Declaration("1.1", None, None),
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Error("invalid token 'Character data' at 1:25 cause character '\\u{86}' is not allowed at 1:25".to_string())
+);
+
+test_strict!(attribute_01, "<a b='&lt;&#x20;' c=\"]]>\"/>",
+    Token::ElementStart("", "a"),
+    Token::Attribute("", "b", "&lt;&#x20;"),
+    Token::Attribute("", "c", "]]>"),
+    Token::ElementEnd(ElementEnd::Empty)
+);
+
+test_strict!(attribute_err_01, "<a b='x&y'/>",
+    Token::ElementStart("", "a"),
+    Token::Error("invalid token 'Attribute' at 1:3 cause character '&' is not allowed at 1:8".to_string())
+);
+
+test_strict!(attribute_err_02, "<a b='\u{2}'/>",
+    Token::ElementStart("", "a"),
+    Token::Error("invalid token 'Attribute' at 1:3 cause character '\\u{2}' is not allowed at 1:7".to_string())
+);
+
+test_strict!(cdata_01, "<a><![CDATA[&<>]]></a>",
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Cdata("&<>"),
+    Token::ElementEnd(ElementEnd::Close("", "a"))
+);
+
+test_strict!(cdata_err_01, "<a><![CDATA[x\u{1}]]></a>",
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Error("invalid token 'CDATA' at 1:4 cause character '\\u{1}' is not allowed at 1:14".to_string())
+);
+
+test!(comment_01, "<!-- a --->",
+    Token::Comment(" a -")
+);
+
+test_strict!(comment_err_01, "<!-- a --->",
+    Token::Error("invalid token 'Comment' at 1:1".to_string())
+);
+
+#[test]
+fn text_range_01() {
+    let text = "<a>te\u{1}xt</a>";
+    let mut p = xml::Tokenizer::from(text);
+    p.enable_strict_mode();
+    let e = p.filter_map(|t| t.err()).next().unwrap();
+    assert_eq!(e.range(), 3..6);
+    assert_eq!(e.code(), "XML-E016");
+}
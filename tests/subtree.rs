@@ -0,0 +1,189 @@
+extern crate xmlparser as xml;
+
+use xml::{ElementEnd, Path, StrSpan, Token, Tokenizer};
+
+// Returns the tokenizer positioned right after the `n`th element start.
+fn start_nth<'a>(text: &'a str, n: usize) -> Tokenizer<'a> {
+    let mut p = Tokenizer::from(text);
+    let mut count = 0;
+    loop {
+        if let Token::ElementStart(..) = p.next().unwrap().unwrap() {
+            if count == n {
+                return p;
+            }
+
+            count += 1;
+        }
+    }
+}
+
+fn source(text: &str, n: usize) -> &str {
+    start_nth(text, n).element_source().unwrap().to_str()
+}
+
+fn rest<'a>(p: Tokenizer<'a>) -> Vec<Token<'a>> {
+    p.map(|t| t.unwrap()).collect()
+}
+
+#[test]
+fn element_source_01() {
+    let text = "<a><b x='1'><c/>text<c></c></b><d/></a>";
+    assert_eq!(source(text, 0), text);
+    assert_eq!(source(text, 1), "<b x='1'><c/>text<c></c></b>");
+    assert_eq!(source(text, 2), "<c/>");
+    assert_eq!(source(text, 3), "<c></c>");
+    assert_eq!(source(text, 4), "<d/>");
+}
+
+#[test]
+fn element_source_02() {
+    // Tags inside comments, CDATA, PI and attribute values are ignored.
+    let text = "<a><b t='</b>' u=\"/>\"><!-- </b> --><![CDATA[</b>]]><?pi </b> ?></b  ></a>";
+    assert_eq!(source(text, 1),
+               "<b t='</b>' u=\"/>\"><!-- </b> --><![CDATA[</b>]]><?pi </b> ?></b  >");
+}
+
+#[test]
+fn element_source_03() {
+    // Attributes can be already processed.
+    let text = "<a x='1' y='2'><b/></a>";
+    let mut p = start_nth(text, 0);
+    match p.next().unwrap().unwrap() {
+        Token::Attribute((_, local), _) => assert_eq!(local.to_str(), "x"),
+        _ => panic!(),
+    }
+    assert_eq!(p.element_source().unwrap().to_str(), text);
+
+    let mut p = start_nth("<a x='1'/>", 0);
+    p.next();
+    assert_eq!(p.element_source().unwrap().to_str(), "<a x='1'/>");
+}
+
+#[test]
+fn element_source_04() {
+    // Same as full tokenization.
+    let text = "\
+<?xml version='1.0'?>
+<!DOCTYPE svg>
+<svg xmlns:x='urn:x'>
+    <g id='g1'>
+        <x:rect/>
+        <g><!-- <g> --><rect>&amp;<![CDATA[<]]></rect></g>
+        <?pi <?>
+    </g>
+    <text
+        x = '>' >a<tspan>b</tspan></text>
+</svg>
+";
+
+    let path = Path::parse("//*").unwrap();
+    for (i, span) in Tokenizer::from(text).select(&path).enumerate() {
+        let span = span.unwrap();
+        let actual = start_nth(text, i).element_source().unwrap();
+        assert_eq!(actual.to_str(), span.to_str());
+        assert_eq!(actual.start(), span.start());
+    }
+}
+
+#[test]
+fn skip_element_01() {
+    let text = "<a><b><c/></b><d/></a>";
+    let mut p = start_nth(text, 1);
+    p.skip_element().unwrap();
+    let all = rest(Tokenizer::from(text));
+    assert_eq!(rest(p), &all[all.len() - 3..]);
+}
+
+#[test]
+fn skip_element_02() {
+    // Tokenization continues after the root element.
+    let text = "<a><b/></a><!--c--><e/>";
+    let mut p = start_nth(text, 0);
+    p.skip_element().unwrap();
+    match p.next().unwrap().unwrap() {
+        Token::Comment(comment) => assert_eq!(comment.to_str(), "c"),
+        _ => panic!(),
+    }
+    assert_eq!(p.next().unwrap().unwrap_err().to_string(), "unexpected token 'Element Start' at 1:20");
+}
+
+#[test]
+fn skip_element_03() {
+    let text = "<a><b/><c>1</c></a>";
+    let mut p = Tokenizer::from(StrSpan::from_substr(text, 3, text.len() - 4));
+    p.enable_fragment_mode();
+    p.next();
+    p.skip_element().unwrap();
+    p.next();
+    assert_eq!(p.element_source().unwrap().start(), 7);
+    assert!(p.next().is_none());
+}
+
+#[test]
+fn skip_element_04() {
+    let mut text = String::from("<records>");
+    for i in 0..100 {
+        text.push_str(&format!("<record id='{}'><v>{}</v></record>", i, i));
+    }
+    text.push_str("</records>");
+
+    let mut p = Tokenizer::from(text.as_str());
+    let mut ids = Vec::new();
+    while let Some(token) = p.next() {
+        if let Token::Attribute(_, value) = token.unwrap() {
+            ids.push(value.to_str().to_string());
+            p.skip_element().unwrap();
+        }
+    }
+
+    assert_eq!(ids.len(), 100);
+    assert_eq!(ids[99], "99");
+}
+
+#[test]
+fn errors_01() {
+    let mut p = start_nth("<a><b></c></a>", 1);
+    let err = p.skip_element().unwrap_err();
+    assert_eq!(err.to_string(), "expected 'b' close tag not 'c' at 1:7");
+    assert_eq!(err.range(), 6..10);
+    assert!(p.next().is_none());
+}
+
+#[test]
+fn errors_02() {
+    let mut p = start_nth("<a><b><c x='1'>", 1);
+    let err = p.element_source().unwrap_err();
+    assert_eq!(err.to_string(), "unclosed tag 'c' at 1:7");
+    assert_eq!(err.range(), 6..8);
+
+    let mut p = start_nth("<a><b x='>", 1);
+    assert_eq!(p.element_source().unwrap_err().to_string(), "unclosed tag 'b' at 1:4");
+}
+
+#[test]
+fn errors_03() {
+    let mut p = start_nth("<a><!-- </a>", 0);
+    assert_eq!(p.element_source().unwrap_err().to_string(),
+               "invalid token 'Comment' at 1:4 cause unexpected end of stream");
+
+    let mut p = start_nth("<a><!DOCTYPE a></a>", 0);
+    assert_eq!(p.element_source().unwrap_err().to_string(), "unknown token at 1:4");
+}
+
+#[test]
+fn errors_04() {
+    // Must be called right after an element start.
+    let mut p = Tokenizer::from("<a><b/></a>");
+    p.next();
+    p.next();
+    assert_eq!(p.element_source().unwrap_err().to_string(), "unexpected token 'Element Start' at 1:4");
+}
+
+#[test]
+fn recovery_01() {
+    // The tokenizer is left unchanged.
+    let mut p = start_nth("<a><b></c></a>", 1);
+    p.enable_recovery_mode();
+    assert!(p.skip_element().is_err());
+    assert_eq!(p.next().unwrap().unwrap(), Token::ElementEnd(ElementEnd::Open));
+}
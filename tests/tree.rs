@@ -0,0 +1,107 @@
+#![cfg(feature = "tree")]
+
+extern crate xmlparser as xml;
+
+use xml::tree::{Document, NodeKind};
+
+#[test]
+fn tree_01() {
+    let doc = Document::parse("<a><b/>text<c x='1' y:z='2'>t</c></a>").unwrap();
+    let a = doc.root_element().unwrap();
+    assert!(a.has_tag_name("a"));
+    assert_eq!(a.children().count(), 3);
+
+    let b = a.first_child().unwrap();
+    assert!(b.has_tag_name("b"));
+    assert_eq!(b.span().to_str(), "<b/>");
+    assert_eq!(b.parent(), Some(a));
+
+    let text = b.next_sibling().unwrap();
+    assert!(text.is_text());
+    assert_eq!(text.text().unwrap().to_str(), "text");
+
+    let c = a.last_child().unwrap();
+    assert_eq!(c.prev_sibling(), Some(text));
+    assert_eq!(c.span().to_str(), "<c x='1' y:z='2'>t</c>");
+    assert_eq!(c.attribute("x").unwrap().to_str(), "1");
+    assert_eq!(c.attribute("y:z").unwrap().to_str(), "2");
+    assert!(c.attribute("z").is_none());
+    assert_eq!(c.attributes().len(), 2);
+    assert_eq!(c.ancestors().count(), 2);
+}
+
+#[test]
+fn tree_02() {
+    let text = "<?xml version='1.0'?>\n<!-- c1 -->\n<svg:svg>\n  <![CDATA[data]]><?pi content?>\n</svg:svg>";
+    let doc = Document::parse(text).unwrap();
+
+    let kinds: Vec<NodeKind> = doc.descendants().map(|n| n.kind()).collect();
+    assert_eq!(kinds.len(), 7);
+    assert_eq!(kinds[0], NodeKind::Root);
+
+    let comment = doc.root().first_child().unwrap();
+    assert!(comment.is_comment());
+    assert_eq!(comment.span().to_str(), "<!-- c1 -->");
+    assert_eq!(comment.text_pos(), xml::TextPos::new(2, 1));
+
+    let svg = doc.root_element().unwrap();
+    assert!(svg.has_tag_name("svg:svg"));
+    assert_eq!(svg.text_pos(), xml::TextPos::new(3, 1));
+
+    let cdata = svg.descendants().find(|n| n.is_cdata()).unwrap();
+    assert_eq!(cdata.text().unwrap().to_str(), "data");
+    assert_eq!(cdata.span().to_str(), "<![CDATA[data]]>");
+
+    let pi = cdata.next_sibling().unwrap();
+    assert!(pi.is_pi());
+    assert_eq!(pi.span().to_str(), "<?pi content?>");
+}
+
+#[test]
+fn tree_03() {
+    let mut p = xml::Tokenizer::from("<p/>text<p/>");
+    p.enable_fragment_mode();
+    let doc = Document::from_tokenizer(p).unwrap();
+    assert_eq!(doc.root().children().count(), 3);
+}
+
+#[test]
+fn tree_err_01() {
+    let err = Document::parse("<root><child></root></child>").unwrap_err();
+    assert_eq!(err.to_string(), "expected 'child' close tag not 'root' at 1:14");
+}
+
+#[test]
+fn tree_err_02() {
+    let err = Document::parse("<root>\n  <child>\n</root>").unwrap_err();
+    assert_eq!(err.to_string(), "expected 'child' close tag not 'root' at 3:1");
+}
+
+#[test]
+fn tree_err_03() {
+    let err = Document::parse("<root>\n  <a:child>").unwrap_err();
+    assert_eq!(err.to_string(), "unclosed tag 'a:child' at 2:3");
+}
+
+#[test]
+fn tree_err_04() {
+    let mut p = xml::Tokenizer::from("<p/></p>");
+    p.enable_fragment_mode();
+    let err = Document::from_tokenizer(p).unwrap_err();
+    assert_eq!(err.to_string(), "unexpected token 'Element Close' at 1:5");
+}
+
+#[test]
+fn tree_err_05() {
+    let err = Document::parse("<a/><a/>").unwrap_err();
+    assert_eq!(err.to_string(), "unexpected token 'Element Start' at 1:5");
+}
+
+#[test]
+fn line_breaks_01() {
+    let mut p = xml::Tokenizer::from("<?xml version='1.1'?>\u{85}<a>\r<b/></a>");
+    p.enable_cr_line_breaks_mode();
+    let doc = Document::from_tokenizer(p).unwrap();
+    let b = doc.descendants().find(|n| n.is_element() && n.span().to_str() == "<b/>").unwrap();
+    assert_eq!(b.text_pos(), xml::TextPos::new(3, 1));
+}
@@ -0,0 +1,212 @@
+extern crate xmlparser as xml;
+
+use xml::{Error, Token, Tokenizer, ValidityError};
+
+// Returns validity errors as strings.
+fn errors(text: &str) -> Vec<String> {
+    Tokenizer::from(text).validate().filter_map(|t| t.err()).map(|e| e.to_string()).collect()
+}
+
+const DTD: &str = "\
+<!DOCTYPE book [
+    <!ELEMENT book (title, (chapter | appendix)+, index?)>
+    <!ELEMENT title (#PCDATA)>
+    <!ELEMENT chapter (#PCDATA | em)*>
+    <!ELEMENT appendix ANY>
+    <!ELEMENT index EMPTY>
+    <!ELEMENT em (#PCDATA)>
+]>
+";
+
+fn book(body: &str) -> Vec<String> {
+    errors(&format!("{}{}", DTD, body))
+}
+
+#[test]
+fn valid_01() {
+    let text = format!("{}{}", DTD, "\
+<book>
+    <title>Title</title>
+    <chapter>Text <em>a</em> text<!-- c --></chapter>
+    <appendix><em>b</em><?pi?></appendix>
+    <chapter/>
+    <index/>
+</book>");
+
+    // Valid documents produce the same tokens.
+    let tokens: Vec<_> = Tokenizer::from(text.as_str()).validate().map(|t| t.unwrap()).collect();
+    assert_eq!(tokens, Tokenizer::from(text.as_str()).map(|t| t.unwrap()).collect::<Vec<_>>());
+}
+
+#[test]
+fn content_01() {
+    assert_eq!(book("<book><chapter/></book>"),
+               vec!["element 'chapter' is not allowed here in 'book' at 9:7",
+                    "element 'book' content is incomplete at 9:17"]);
+    assert_eq!(book("<book><title/></book>"),
+               vec!["element 'book' content is incomplete at 9:15"]);
+    assert_eq!(book("<book><title/><index/><chapter/></book>"),
+               vec!["element 'index' is not allowed here in 'book' at 9:15"]);
+    assert_eq!(book("<book><title/><chapter/><index/><index/></book>"),
+               vec!["element 'index' is not allowed here in 'book' at 9:33"]);
+}
+
+#[test]
+fn content_02() {
+    // Mixed, EMPTY and ANY.
+    assert_eq!(book("<book><title>a<em/></title><chapter/></book>"),
+               vec!["element 'em' is not allowed here in 'title' at 9:15"]);
+    assert_eq!(book("<book><title/><chapter/><index> </index></book>"),
+               vec!["element 'index' is declared EMPTY but has content at 9:32"]);
+    assert_eq!(book("<book><title/><chapter/><index><!-- c --></index></book>"),
+               vec!["element 'index' is declared EMPTY but has content at 9:32"]);
+    assert_eq!(book("<book><title/><appendix>a<b/></appendix></book>"),
+               vec!["element 'b' is not declared at 9:26"]);
+}
+
+#[test]
+fn content_03() {
+    // Only whitespaces are allowed in element content.
+    assert_eq!(book("<book>\n\t<title/> <chapter/>\n</book>"), Vec::<String>::new());
+    assert_eq!(book("<book>text<title/><chapter/></book>"),
+               vec!["text is not allowed in 'book' at 9:7"]);
+    assert_eq!(book("<book><![CDATA[ ]]><title/><chapter/></book>"),
+               vec!["text is not allowed in 'book' at 9:7"]);
+}
+
+#[test]
+fn content_04() {
+    // Non-deterministic content models are allowed.
+    let text = "\
+<!DOCTYPE a [
+    <!ELEMENT a ((b, c) | (b, d))*>
+    <!ELEMENT b EMPTY>
+    <!ELEMENT c EMPTY>
+    <!ELEMENT d EMPTY>
+]>
+<a><b/><d/><b/><c/></a>";
+    assert_eq!(errors(text), Vec::<String>::new());
+    assert_eq!(errors(&text.replace("<c/></a>", "</a>")),
+               vec!["element 'a' content is incomplete at 7:16"]);
+}
+
+#[test]
+fn root_01() {
+    assert_eq!(errors("<a/>"), vec!["the document has no DTD at 1:1"]);
+    assert_eq!(errors("<!DOCTYPE a [<!ELEMENT b EMPTY>]><b/>"),
+               vec!["expected 'a' root element not 'b' at 1:34"]);
+    assert_eq!(errors("<!DOCTYPE a [<!ELEMENT a EMPTY><!ELEMENT a ANY>]><a/>"),
+               vec!["element 'a' is declared more than once at 1:32"]);
+}
+
+const ATTLIST: &str = "\
+<!DOCTYPE a [
+    <!ENTITY logo SYSTEM 'logo.png' NDATA png><!ENTITY icon SYSTEM 'icon.xml'>
+    <!ELEMENT a ANY>
+    <!ELEMENT b EMPTY>
+    <!ATTLIST b
+        id ID #IMPLIED
+        ref IDREF #IMPLIED
+        refs IDREFS #IMPLIED
+        kind (x | y) 'x'
+        size NMTOKEN #IMPLIED
+        src ENTITY #IMPLIED
+        version CDATA #FIXED '1'
+        name CDATA #REQUIRED>
+    <!ATTLIST b name CDATA #IMPLIED xml:lang CDATA 'en'>
+]>
+";
+
+fn attributes(body: &str) -> Vec<String> {
+    errors(&format!("{}{}", ATTLIST, body))
+}
+
+#[test]
+fn attributes_01() {
+    assert_eq!(attributes("<a><b name='n' id='i1' kind=' y ' size='10' src='logo'/></a>"),
+               Vec::<String>::new());
+    assert_eq!(attributes("<a><b/></a>"),
+               vec!["required attribute 'name' is missing in 'b' at 16:4"]);
+    assert_eq!(attributes("<a><b name='n' color='red'/></a>"),
+               vec!["attribute 'color' is not declared for 'b' at 16:16"]);
+    assert_eq!(attributes("<a><b name='n' version='2'/></a>"),
+               vec!["attribute 'version' value must be '1' at 16:16"]);
+    assert_eq!(attributes("<a><b name='n' version=' 1 '/></a>"),
+               vec!["attribute 'version' value must be '1' at 16:16"]);
+}
+
+#[test]
+fn attributes_02() {
+    assert_eq!(attributes("<a><b name='n' kind='z' size='a b' src='icon' id='1'/></a>"),
+               vec!["attribute 'kind' value must be one of 'x', 'y' at 16:16",
+                    "attribute 'size' value must be a name token at 16:25",
+                    "attribute 'src' value must be a declared unparsed entity name at 16:36",
+                    "attribute 'id' value must be a name at 16:47"]);
+}
+
+#[test]
+fn attributes_03() {
+    // Default values.
+    let text = format!("{}{}", ATTLIST, "<a><b name='n' kind='y'/></a>");
+    let names: Vec<_> = Tokenizer::from(text.as_str()).validate()
+        .skip_while(|t| !matches!(*t, Ok(Token::ElementStart(_, local)) if local.to_str() == "b"))
+        .take_while(|t| !matches!(*t, Ok(Token::ElementEnd(_))))
+        .filter_map(|t| match t {
+            Ok(Token::Attribute((prefix, local), value)) => {
+                Some(format!("{}:{}={}", prefix.to_str(), local.to_str(), value.to_str()))
+            }
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(names, vec![":name=n", ":kind=y", ":version=1", "xml:lang=en"]);
+}
+
+#[test]
+fn ids_01() {
+    assert_eq!(attributes("<a><b name='n' id='a1' refs=' a2  a1'/><b name='n' id='a2' ref='a1'/></a>"),
+               Vec::<String>::new());
+    assert_eq!(attributes("<a><b name='n' id='a1'/><b name='n' id=' a1 '/></a>"),
+               vec!["ID 'a1' is already used at 16:37"]);
+    assert_eq!(attributes("<a><b name='n' ref='a1' refs='a2 a3'/><b name='n' id='a2'/></a>"),
+               vec!["IDREF 'a1' doesn't match any ID at 16:21",
+                    "IDREF 'a3' doesn't match any ID at 16:31"]);
+}
+
+#[test]
+fn dtd_01() {
+    // Default values are checked too.
+    assert_eq!(errors("<!DOCTYPE a [<!ELEMENT a EMPTY><!ATTLIST a n NMTOKEN 'a b'>]><a/>"),
+               vec!["attribute 'n' value must be a name token at 1:55"]);
+
+    let mut p = Tokenizer::from("<!DOCTYPE a [<!ELEMENT a (b|c,d)>]><a/>").validate();
+    let err = p.find_map(|t| t.err()).unwrap();
+    assert_eq!(err.to_string(),
+               "invalid token 'Doctype Element Declaration' at 1:14 cause expected '|', ')' not ',' at 1:30");
+    assert!(p.next().is_none());
+}
+
+#[test]
+fn error_01() {
+    let text = format!("{}{}", DTD, "<book><chapter/></book>");
+    let err = Tokenizer::from(text.as_str()).validate().find_map(|t| t.err()).unwrap();
+    match err {
+        Error::InvalidDocument(ref cause, pos, ref range) => {
+            assert_eq!(**cause, ValidityError::UnexpectedElement("book".into(), "chapter".into()));
+            assert_eq!(pos.to_string(), "9:7");
+            assert_eq!(&text[range.clone()], "<chapter");
+        }
+        _ => panic!(),
+    }
+
+    assert_eq!(err.code(), "XML-V005");
+    assert_eq!(err.spec_url(), "https://www.w3.org/TR/xml/#elementvalid");
+    assert!(err.token_type().is_none());
+}
+
+#[test]
+fn well_formedness_01() {
+    // The validation stops on a well-formedness error.
+    assert_eq!(book("<book><title></book>"), vec!["expected 'title' close tag not 'book' at 9:14"]);
+    assert_eq!(book("<book><title/><chapter/>"), vec!["unclosed tag 'book' at 9:1"]);
+}
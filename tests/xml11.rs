@@ -0,0 +1,132 @@
+extern crate xmlparser as xml;
+
+#[macro_use] mod token;
+use token::*;
+
+macro_rules! test_xml11 {
+    ($name:ident, $text:expr, $($token:expr),*) => (
+        #[test]
+        fn $name() {
+            let mut p = xml::Tokenizer::from($text);
+            p.enable_xml11_mode();
+            $(
+                let t = p.next().unwrap();
+                assert_eq!(to_test_token(t), $token);
+            )*
+            assert!(p.next().is_none());
+        }
+    )
+}
+
+test!(xml11_01, "<?xml version='1.1'?><a>\u{85}\u{2028}</a>",
+    Token::Declaration("1.1", None, None),
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Whitespaces("\u{85}\u{2028}"),
+    Token::ElementEnd(ElementEnd::Close("", "a"))
+);
+
+// NEL is just a char in XML 1.0.
+test!(xml11_02, "<?xml version='1.0'?><a>\u{85}</a>",
+    Token::Declaration("1.0", None, None),
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Text("\u{85}"),
+    Token::ElementEnd(ElementEnd::Close("", "a"))
+);
+
+test_xml11!(xml11_03, "<a\u{85}b='1'\u{2028}/>",
+    Token::ElementStart("", "a"),
+    Token::Attribute("", "b", "1"),
+    Token::ElementEnd(ElementEnd::Empty)
+);
+
+test_xml11!(xml11_04, "<!--\u{85}--><?pi\u{2028}x?>",
+    Token::Comment("\u{85}"),
+    Token::PI("pi", Some("x"))
+);
+
+// C1 control chars are restricted in XML 1.1.
+test_xml11!(xml11_05, "<!--\u{86}-->",
+    Token::Error("invalid token 'Comment' at 1:1".to_string())
+);
+
+test!(xml11_06, "<!--\u{86}-->",
+    Token::Comment("\u{86}")
+);
+
+test_xml11!(xml11_07, "<!--\u{7F}-->",
+    Token::Error("invalid token 'Comment' at 1:1".to_string())
+);
+
+#[test]
+fn xml11_char_ref_01() {
+    let mut s = xml::Stream::from("&#x1;");
+    assert!(s.consume_reference().is_err());
+
+    let mut s = xml::Stream::from("&#x1;");
+    s.enable_xml11_mode();
+    assert_eq!(s.consume_reference().unwrap(), xml::Reference::CharRef('\u{1}'));
+
+    let mut s = xml::Stream::from("&#x0;");
+    s.enable_xml11_mode();
+    assert!(s.consume_reference().is_err());
+}
+
+#[test]
+fn xml11_auto_01() {
+    let mut p = xml::Tokenizer::from("<?xml version='1.1'?><a/>");
+    assert!(!p.is_xml11_mode());
+    p.next();
+    assert!(p.is_xml11_mode());
+}
+
+#[test]
+fn xml11_split_01() {
+    let text = "<?xml version='1.1'?><a><b>\u{85}</b><b>\u{85}</b><b>\u{85}</b></a>";
+    let tokens: Vec<_> = xml::Tokenizer::split(text, 3).into_iter().flatten()
+        .map(|t| t.unwrap()).collect();
+    let expected: Vec<_> = xml::Tokenizer::from(text).map(|t| t.unwrap()).collect();
+    assert_eq!(tokens, expected);
+}
+
+#[test]
+fn xml11_text_pos_01() {
+    let text = "a\u{85}b\u{2028}c";
+    let mut s = xml::Stream::from(text);
+    s.enable_xml11_mode();
+    assert_eq!(s.gen_text_pos_from(3), xml::TextPos::new(2, 1));
+    assert_eq!(s.gen_text_pos_from(7), xml::TextPos::new(3, 1));
+
+    // NEL and LINE SEPARATOR are just chars in XML 1.0.
+    let s = xml::Stream::from(text);
+    assert_eq!(s.gen_text_pos_from(7), xml::TextPos::new(1, 5));
+}
+
+#[test]
+fn xml11_text_pos_02() {
+    // `\r` followed by NEL is a single line end.
+    let mut s = xml::Stream::from("a\r\u{85}b");
+    s.enable_xml11_mode();
+    s.enable_cr_line_breaks_mode();
+    assert_eq!(s.gen_text_pos_from(4), xml::TextPos::new(2, 1));
+}
+
+test!(xml11_text_pos_03, "<?xml version='1.1'?><a>\u{2028}<b x=1/></a>",
+    Token::Declaration("1.1", None, None),
+    Token::ElementStart("", "a"),
+    Token::ElementEnd(ElementEnd::Open),
+    Token::Whitespaces("\u{2028}"),
+    Token::ElementStart("", "b"),
+    Token::Error("invalid token 'Attribute' at 2:3 cause expected quote mark not '1' at 2:6".to_string())
+);
+
+#[test]
+fn xml11_text_pos_04() {
+    struct Ignore;
+    impl<'a> xml::Handler<'a> for Ignore {}
+
+    let p = xml::Tokenizer::from("<?xml version='1.1'?><a>\u{85}<b>\u{2028}</c></a>");
+    assert_eq!(p.parse_with(&mut Ignore).unwrap_err().to_string(),
+               "expected 'b' close tag not 'c' at 3:1");
+}